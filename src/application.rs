@@ -11,7 +11,8 @@ use crate::{
     sctk_event::{
         DataSourceEvent, IcedSctkEvent, InputMethodKeyboardEventVariant,
         KeyboardEventVariant, LayerSurfaceEventVariant, PopupEventVariant,
-        SctkEvent, StartCause, InputMethodEventVariant,
+        SctkEvent, StartCause, InputMethodEventVariant, DataControlEvent,
+        ForeignToplevelEvent, TabletEventVariant, TouchEventVariant, VirtualKeyboardEvent,
     },
     settings,
 };
@@ -95,6 +96,10 @@ pub enum Event<Message> {
     Popup(platform_specific::wayland::popup::Action<Message>),
     /// data device requests from the client
     DataDevice(platform_specific::wayland::data_device::Action<Message>),
+    /// foreign toplevel requests from the client
+    ForeignToplevel(
+        platform_specific::wayland::foreign_toplevel::Action<Message>,
+    ),
     /// virtual Keyboard requests from the client
     VirtualKeyboard(
         platform_specific::wayland::virtual_keyboard::Action<Message>,
@@ -225,6 +230,7 @@ where
     A: Application + 'static,
     E: Executor + 'static,
     C: Compositor<Renderer = A::Renderer> + 'static,
+    C::Settings: 'static,
     <A::Renderer as Renderer>::Theme: StyleSheet,
     A::Flags: Clone,
 {
@@ -280,6 +286,11 @@ where
         wl_surface,
     };
 
+    #[cfg(feature = "software")]
+    let compositor_settings = crate::software::connect(
+        compositor_settings,
+        &event_loop.state.software_frames,
+    );
     #[allow(unsafe_code)]
     let (compositor, renderer) =
         C::new(compositor_settings, Some(&wrapper)).unwrap();
@@ -474,6 +485,48 @@ where
                             | PointerEventKind::Axis { .. } => {}
                         }
                     }
+                    SctkEvent::TouchEvent { variant, surface, .. } => {
+                        let state = match surface_ids
+                            .get(&surface.id())
+                            .and_then(|id| states.get_mut(&id.inner()))
+                        {
+                            Some(s) => s,
+                            None => continue,
+                        };
+                        // Widgets hit test against the cursor, it follows
+                        // the finger like it does on other platforms.
+                        match variant {
+                            TouchEventVariant::Down { position, .. }
+                            | TouchEventVariant::Motion { position, .. } => {
+                                state.set_cursor_position(Some(position));
+                            }
+                            TouchEventVariant::Up { .. }
+                            | TouchEventVariant::Cancel { .. } => {}
+                        }
+                    }
+                    SctkEvent::TabletEvent { variant, surface, .. } => {
+                        let state = match surface_ids
+                            .get(&surface.id())
+                            .and_then(|id| states.get_mut(&id.inner()))
+                        {
+                            Some(s) => s,
+                            None => continue,
+                        };
+                        // The compositor stops emulating the pointer for
+                        // tablets once they are bound, the cursor follows
+                        // the tool instead.
+                        match variant {
+                            TabletEventVariant::ProximityIn { axes, .. }
+                            | TabletEventVariant::Down { axes, .. }
+                            | TabletEventVariant::Motion { axes, .. } => {
+                                state.set_cursor_position(Some(LogicalPosition { x: axes.x, y: axes.y }));
+                            }
+                            TabletEventVariant::ProximityOut { .. }
+                            | TabletEventVariant::Up { .. }
+                            | TabletEventVariant::Button { .. }
+                            | TabletEventVariant::PadButton { .. } => {}
+                        }
+                    }
                     SctkEvent::KeyboardEvent { variant, .. } => match variant {
                         KeyboardEventVariant::Leave(_) => {
                             kbd_surface_id.take();
@@ -556,6 +609,42 @@ where
                                 Status::Ignored
                             )
                         },
+                        InputMethodEventVariant::Privacy(privacy) => {
+                            runtime.broadcast(
+                                iced_runtime::core::Event::PlatformSpecific(
+                                    PlatformSpecific::Wayland(
+                                        wayland::Event::InputMethod(
+                                            wayland::InputMethodEvent::Privacy(privacy.into())
+                                        )
+                                    )
+                                ),
+                                Status::Ignored
+                            )
+                        },
+                        InputMethodEventVariant::Profile(profile) => {
+                            runtime.broadcast(
+                                iced_runtime::core::Event::PlatformSpecific(
+                                    PlatformSpecific::Wayland(
+                                        wayland::Event::InputMethod(
+                                            wayland::InputMethodEvent::Profile(profile.into())
+                                        )
+                                    )
+                                ),
+                                Status::Ignored
+                            )
+                        },
+                        InputMethodEventVariant::TextChanged(change) => {
+                            runtime.broadcast(
+                                iced_runtime::core::Event::PlatformSpecific(
+                                    PlatformSpecific::Wayland(
+                                        wayland::Event::InputMethod(
+                                            wayland::InputMethodEvent::TextChanged(change.into())
+                                        )
+                                    )
+                                ),
+                                Status::Ignored
+                            )
+                        },
                         InputMethodEventVariant::Done => {
                             runtime.broadcast(
                                 iced_runtime::core::Event::PlatformSpecific(
@@ -569,6 +658,46 @@ where
                             )
                         },
                     },
+                    SctkEvent::DataControl(DataControlEvent::Selection { mime_type, data }) => {
+                        runtime.broadcast(
+                            iced_runtime::core::Event::PlatformSpecific(
+                                PlatformSpecific::Wayland(
+                                    wayland::Event::DataControl(
+                                        wayland::DataControlEvent::Selection { mime_type, data }
+                                    )
+                                )
+                            ),
+                            Status::Ignored
+                        )
+                    },
+                    SctkEvent::ForeignToplevel(event) => {
+                        let event = match event {
+                            ForeignToplevelEvent::Opened(toplevel) => wayland::ForeignToplevelEvent::Opened(toplevel),
+                            ForeignToplevelEvent::Changed(toplevel) => wayland::ForeignToplevelEvent::Changed(toplevel),
+                            ForeignToplevelEvent::Closed(toplevel) => wayland::ForeignToplevelEvent::Closed(toplevel),
+                            ForeignToplevelEvent::List(toplevels) => wayland::ForeignToplevelEvent::List(toplevels),
+                        };
+                        runtime.broadcast(
+                            iced_runtime::core::Event::PlatformSpecific(
+                                PlatformSpecific::Wayland(
+                                    wayland::Event::ForeignToplevel(event)
+                                )
+                            ),
+                            Status::Ignored
+                        )
+                    },
+                    SctkEvent::VirtualKeyboard(VirtualKeyboardEvent::Modifiers(raw_modifiers)) => {
+                        runtime.broadcast(
+                            iced_runtime::core::Event::PlatformSpecific(
+                                PlatformSpecific::Wayland(
+                                    wayland::Event::VirtualKeyboard(
+                                        wayland::VirtualKeyboardEvent::Modifiers(raw_modifiers.into())
+                                    )
+                                )
+                            ),
+                            Status::Ignored
+                        )
+                    },
                     SctkEvent::InputMethodKeyboardEvent { variant } =>
                     match variant {
                         InputMethodKeyboardEventVariant::Press(ke) => {
@@ -654,6 +783,42 @@ where
                                 Status::Ignored
                             )
                         }
+                        InputMethodKeyboardEventVariant::Compose(status) => {
+                            runtime.broadcast(
+                                iced_runtime::core::Event::PlatformSpecific(
+                                    PlatformSpecific::Wayland(
+                                        wayland::Event::InputMethodKeyboard(
+                                            wayland::InputMethodKeyboardEvent::Compose(status.into())
+                                        )
+                                    )
+                                ),
+                                Status::Ignored
+                            )
+                        }
+                        InputMethodKeyboardEventVariant::Layouts(layouts) => {
+                            runtime.broadcast(
+                                iced_runtime::core::Event::PlatformSpecific(
+                                    PlatformSpecific::Wayland(
+                                        wayland::Event::InputMethodKeyboard(
+                                            wayland::InputMethodKeyboardEvent::Layouts(layouts)
+                                        )
+                                    )
+                                ),
+                                Status::Ignored
+                            )
+                        }
+                        InputMethodKeyboardEventVariant::LayoutChanged { index, name } => {
+                            runtime.broadcast(
+                                iced_runtime::core::Event::PlatformSpecific(
+                                    PlatformSpecific::Wayland(
+                                        wayland::Event::InputMethodKeyboard(
+                                            wayland::InputMethodKeyboardEvent::LayoutChanged { index, name }
+                                        )
+                                    )
+                                ),
+                                Status::Ignored
+                            )
+                        }
                     },
                     SctkEvent::InputMethodPopupEvent { variant, id } => match variant {
                         crate::sctk_event::InputMethodPopupEventVariant::Created(object_id, native_id) => {
@@ -2243,6 +2408,9 @@ fn run_command<A, E>(
             command::Action::PlatformSpecific(platform_specific::Action::Wayland(platform_specific::wayland::Action::DataDevice(data_device_action))) => {
                 proxy.send_event(Event::DataDevice(data_device_action));
             }
+            command::Action::PlatformSpecific(platform_specific::Action::Wayland(platform_specific::wayland::Action::ForeignToplevel(foreign_toplevel_action))) => {
+                proxy.send_event(Event::ForeignToplevel(foreign_toplevel_action));
+            }
             command::Action::PlatformSpecific(platform_specific::Action::Wayland(platform_specific::wayland::Action::VirtualKeyboard(virtual_keyboard_action)))
             => {
                 proxy.send_event(Event::VirtualKeyboard(virtual_keyboard_action))
@@ -2326,6 +2494,8 @@ fn event_is_for_surface(
         SctkEvent::PointerEvent { variant, .. } => {
             &variant.surface.id() == object_id
         }
+        SctkEvent::TouchEvent { surface, .. } => &surface.id() == object_id,
+        SctkEvent::TabletEvent { surface, .. } => &surface.id() == object_id,
         SctkEvent::KeyboardEvent { variant, .. } => match variant {
             KeyboardEventVariant::Leave(id) => &id.id() == object_id,
             _ => has_kbd_focus,
@@ -2338,8 +2508,11 @@ fn event_is_for_surface(
         | SctkEvent::RemovedOutput(_) => false,
         SctkEvent::ScaleFactorChanged { id, .. } => &id.id() == object_id,
         SctkEvent::DndOffer { surface, .. } => &surface.id() == object_id,
-        SctkEvent::SelectionOffer(_) => true,
+        SctkEvent::SelectionOffer(..) => true,
         SctkEvent::DataSource(_) => true,
+        SctkEvent::DataControl(_) => false,
+        SctkEvent::ForeignToplevel(_) => false,
+        SctkEvent::VirtualKeyboard(_) => false,
         SctkEvent::InputMethodEvent { .. } => false,
         SctkEvent::InputMethodKeyboardEvent { .. } => false,
         SctkEvent::InputMethodPopupEvent { variant:_, id } => &id.id() == object_id, // TODO: what does this do?
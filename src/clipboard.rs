@@ -1,6 +1,12 @@
 //! Access the clipboard.
+//!
+//! [`Clipboard`] reads and writes text. Other formats, e.g. `text/html` or
+//! `image/png`, are offered with [`write_data`] and requested with
+//! [`read_data`]; the data is transferred through pipes by the event loop
+//! without blocking it.
 pub use iced_runtime::clipboard::Action;
 
+use iced_runtime::command::platform_specific::wayland::data_device::DataFromMimeType;
 use iced_runtime::command::{self, Command};
 use std::ffi::c_void;
 use std::sync::{Arc, Mutex};
@@ -79,3 +85,80 @@ pub fn read<Message>(
 pub fn write<Message>(contents: String) -> Command<Message> {
     Command::single(command::Action::Clipboard(Action::Write(contents)))
 }
+
+/// Offer `data` on the clipboard for each of `mime_types`. The data is
+/// converted when another client pastes it.
+pub fn write_data<Message>(
+    mime_types: Vec<String>,
+    data: Box<dyn DataFromMimeType + Send + Sync>,
+) -> Command<Message> {
+    crate::commands::data_device::set_selection(mime_types, data)
+}
+
+/// Read the clipboard as `mime_type`. The data is delivered as a
+/// `SelectionOffer` event with `SelectionOfferEvent::Data` once complete.
+pub fn read_data<Message>(mime_type: String) -> Command<Message> {
+    crate::commands::data_device::request_selection(mime_type)
+}
+
+/// Offer `contents` on the clipboard in all of its formats.
+pub fn write_contents<Message>(contents: Contents) -> Command<Message> {
+    write_data(contents.mime_types(), Box::new(contents))
+}
+
+/// Clipboard contents in one or more formats.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Contents {
+    formats: Vec<(String, Vec<u8>)>,
+}
+
+impl Contents {
+    /// Contents without any format.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Plain text, offered as the usual text mime types.
+    pub fn text(text: impl Into<String>) -> Self {
+        let text = text.into().into_bytes();
+        ["text/plain;charset=utf-8", "text/plain", "UTF8_STRING"]
+            .into_iter()
+            .fold(Self::new(), |contents, mime_type| {
+                contents.with(mime_type, text.clone())
+            })
+    }
+
+    /// Add a format, replacing data already set for `mime_type`.
+    pub fn with(
+        mut self,
+        mime_type: impl Into<String>,
+        data: impl Into<Vec<u8>>,
+    ) -> Self {
+        let mime_type = mime_type.into();
+        let data = data.into();
+        match self.formats.iter_mut().find(|(m, _)| *m == mime_type) {
+            Some((_, d)) => *d = data,
+            None => self.formats.push((mime_type, data)),
+        }
+        self
+    }
+
+    /// The offered mime types, in the order they were added.
+    pub fn mime_types(&self) -> Vec<String> {
+        self.formats.iter().map(|(m, _)| m.clone()).collect()
+    }
+
+    /// The data for `mime_type`.
+    pub fn get(&self, mime_type: &str) -> Option<&[u8]> {
+        self.formats
+            .iter()
+            .find(|(m, _)| m == mime_type)
+            .map(|(_, d)| d.as_slice())
+    }
+}
+
+impl DataFromMimeType for Contents {
+    fn from_mime_type(&self, mime_type: &str) -> Option<Vec<u8>> {
+        self.get(mime_type).map(<[u8]>::to_vec)
+    }
+}
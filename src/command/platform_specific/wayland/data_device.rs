@@ -49,6 +49,21 @@ pub enum ActionInner {
         /// The mime type that the selection should be converted to.
         mime_type: String,
     },
+    /// Indicate that you are setting the primary selection and will respond
+    /// to requests with data of the advertised mime types.
+    SetPrimarySelection {
+        /// The mime types that the primary selection can be converted to.
+        mime_types: Vec<String>,
+        /// The data to send.
+        data: Box<dyn DataFromMimeType + Send + Sync>,
+    },
+    /// Unset the primary selection.
+    UnsetPrimarySelection,
+    /// Request the primary selection data.
+    RequestPrimarySelectionData {
+        /// The mime type that the primary selection should be converted to.
+        mime_type: String,
+    },
     /// Start a drag and drop operation. When a client asks for the selection, an event will be delivered
     /// This is used for internal drags, where the client is the source of the drag.
     /// The client will be resposible for data transfer.
@@ -123,6 +138,17 @@ impl fmt::Debug for ActionInner {
             Self::RequestSelectionData { mime_type } => {
                 f.debug_tuple("RequestSelection").field(mime_type).finish()
             }
+            Self::SetPrimarySelection { mime_types, .. } => f
+                .debug_tuple("SetPrimarySelection")
+                .field(mime_types)
+                .finish(),
+            Self::UnsetPrimarySelection => {
+                f.debug_tuple("UnsetPrimarySelection").finish()
+            }
+            Self::RequestPrimarySelectionData { mime_type } => f
+                .debug_tuple("RequestPrimarySelection")
+                .field(mime_type)
+                .finish(),
             Self::StartInternalDnd { origin_id, icon_id } => f
                 .debug_tuple("StartInternalDnd")
                 .field(origin_id)
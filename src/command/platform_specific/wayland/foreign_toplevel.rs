@@ -0,0 +1,57 @@
+use std::{fmt, marker::PhantomData};
+
+use iced_futures::MaybeSend;
+
+/// Foreign toplevel action
+pub struct Action<T> {
+    /// The inner action
+    pub inner: ActionInner,
+    /// The phantom data
+    _phantom: PhantomData<T>,
+}
+
+impl<T> From<ActionInner> for Action<T> {
+    fn from(inner: ActionInner) -> Self {
+        Self {
+            inner,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Action<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+/// Foreign toplevel Actions
+pub enum ActionInner {
+    /// Request the open toplevels, they are delivered as
+    /// `ForeignToplevelEvent::List`.
+    RequestToplevels,
+}
+
+impl<T> Action<T> {
+    /// Maps the output of a foreign toplevel [`Action`] using the provided
+    /// closure.
+    pub fn map<A>(
+        self,
+        _: impl Fn(T) -> A + 'static + MaybeSend + Sync,
+    ) -> Action<A>
+    where
+        T: 'static,
+    {
+        Action::from(self.inner)
+    }
+}
+
+impl fmt::Debug for ActionInner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RequestToplevels => {
+                f.debug_tuple("Request toplevels").finish()
+            }
+        }
+    }
+}
@@ -47,7 +47,9 @@ pub enum ActionInner {
         before_length: u32,
         /// Number of bytes after current cursor index (excluding the preedit text) to delete
         after_length: u32
-    }
+    },
+    /// Reload the compose table of the input method keyboard
+    ReloadCompose,
 }
 
 impl<T> Action<T> {
@@ -72,6 +74,7 @@ impl fmt::Debug for ActionInner {
                 f.debug_tuple("Set Preedit String").field(string).field(cursor_begin).field(cursor_end).finish(),
             Self::DeleteSurroundingText { before_length, after_length } => 
                 f.debug_tuple("Delete Sorrunding Text").field(before_length).field(after_length).finish(),
+            Self::ReloadCompose => f.write_str("Reload Compose"),
         }
     }
 }
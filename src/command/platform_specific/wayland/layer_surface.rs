@@ -1,5 +1,6 @@
 use std::fmt;
 use std::marker::PhantomData;
+use std::time::Duration;
 
 use iced_core::layout::Limits;
 use iced_futures::MaybeSend;
@@ -146,6 +147,19 @@ pub enum Action<T> {
         /// layer of the layer surface
         layer: Layer,
     },
+    /// Create the layer surface while a text input is active and destroy it
+    /// otherwise, e.g. for an on-screen keyboard. `None` stops managing the
+    /// surface.
+    AutoShow {
+        /// surface builder
+        builder: Option<SctkLayerSurfaceSettings>,
+        /// how long the text input has to stay (in)active before the surface
+        /// is shown or hidden
+        debounce: Duration,
+    },
+    /// Override the visibility of the automatically shown layer surface,
+    /// `None` follows the text input again
+    AutoShowVisible(Option<bool>),
 }
 
 impl<T> Action<T> {
@@ -179,6 +193,10 @@ impl<T> Action<T> {
                 keyboard_interactivity,
             },
             Action::Layer { id, layer } => Action::Layer { id, layer },
+            Action::AutoShow { builder, debounce } => {
+                Action::AutoShow { builder, debounce }
+            }
+            Action::AutoShowVisible(visible) => Action::AutoShowVisible(visible),
         }
     }
 }
@@ -219,6 +237,14 @@ impl<T> fmt::Debug for Action<T> {
                 f,
                 "Action::LayerSurfaceAction::Margin {{ id: {:#?}, layer: {:?} }}", id, layer
             ),
+            Action::AutoShow { builder, debounce } => write!(
+                f,
+                "Action::LayerSurfaceAction::AutoShow {{ builder: {:?}, debounce: {:?} }}", builder, debounce
+            ),
+            Action::AutoShowVisible(visible) => write!(
+                f,
+                "Action::LayerSurfaceAction::AutoShowVisible {{ visible: {:?} }}", visible
+            ),
         }
     }
 }
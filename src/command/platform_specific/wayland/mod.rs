@@ -4,6 +4,8 @@ use iced_futures::MaybeSend;
 
 /// data device actions
 pub mod data_device;
+/// foreign toplevel actions
+pub mod foreign_toplevel;
 /// input method actions
 pub mod input_method;
 /// input method popup actions
@@ -27,6 +29,8 @@ pub enum Action<T> {
     Popup(popup::Action<T>),
     /// data device
     DataDevice(data_device::Action<T>),
+    /// foreign toplevels
+    ForeignToplevel(foreign_toplevel::Action<T>),
     /// virtual keyboard
     VirtualKeyboard(virtual_keyboard::Action<T>),
     /// input method
@@ -50,6 +54,7 @@ impl<T> Action<T> {
             Action::Window(a) => Action::Window(a.map(f)),
             Action::Popup(a) => Action::Popup(a.map(f)),
             Action::DataDevice(a) => Action::DataDevice(a.map(f)),
+            Action::ForeignToplevel(a) => Action::ForeignToplevel(a.map(f)),
             Action::VirtualKeyboard(a) => Action::VirtualKeyboard(a.map(f)),
             Action::InputMethod(a) => Action::InputMethod(a.map(f)),
             Action::InputMethodPopup(a) => Action::InputMethodPopup(a.map(f)),
@@ -68,6 +73,9 @@ impl<T> Debug for Action<T> {
             Self::DataDevice(arg0) => {
                 f.debug_tuple("DataDevice").field(arg0).finish()
             }
+            Self::ForeignToplevel(arg0) => {
+                f.debug_tuple("ForeignToplevel").field(arg0).finish()
+            }
             Self::VirtualKeyboard(arg0) => {
                 f.debug_tuple("VirtualKeyboard").field(arg0).finish()
             }
@@ -34,6 +34,18 @@ pub enum ActionInner {
     KeyReleased(KeyEvent),
     /// Forward modifiers to client
     Modifiers(RawModifiers),
+    /// Tap sticky modifiers in the mask: released ones latch until the next
+    /// key, latched ones lock on a double tap and are released otherwise,
+    /// locked ones are released.
+    TapModifiers(u32),
+    /// Release every latched and locked modifier.
+    ClearModifiers,
+    /// Switch the effective layout, the XKB group, of the virtual keyboard
+    /// keymap by locking the group. The keymap itself is not changed.
+    SetGroup(u32),
+    /// Replace the keymap of the virtual keyboard with an XKB keymap in the
+    /// text format. Key codes of later key events refer to this keymap.
+    Keymap(String),
 }
 
 impl<T> Action<T> {
@@ -61,6 +73,14 @@ impl fmt::Debug for ActionInner {
             Self::Modifiers(modifiers) => {
                 f.debug_tuple("Modifier event").field(modifiers).finish()
             }
+            Self::TapModifiers(mask) => {
+                f.debug_tuple("Tap modifiers").field(mask).finish()
+            }
+            Self::ClearModifiers => f.debug_tuple("Clear modifiers").finish(),
+            Self::SetGroup(group) => {
+                f.debug_tuple("Set group").field(group).finish()
+            }
+            Self::Keymap(_) => f.debug_tuple("Keymap").finish(),
         }
     }
 }
@@ -54,6 +54,47 @@ pub fn request_selection<Message>(mime_type: String) -> Command<Message> {
     ))
 }
 
+/// Set the primary selection. When a client asks for it, the data is written
+/// for the requested mime type.
+pub fn set_primary_selection<Message>(
+    mime_types: Vec<String>,
+    data: Box<dyn DataFromMimeType + Send + Sync>,
+) -> Command<Message> {
+    Command::single(command::Action::PlatformSpecific(
+        platform_specific::Action::Wayland(wayland::Action::DataDevice(
+            wayland::data_device::ActionInner::SetPrimarySelection {
+                mime_types,
+                data,
+            }
+            .into(),
+        )),
+    ))
+}
+
+/// unset the primary selection
+pub fn unset_primary_selection<Message>() -> Command<Message> {
+    Command::single(command::Action::PlatformSpecific(
+        platform_specific::Action::Wayland(wayland::Action::DataDevice(
+            wayland::data_device::ActionInner::UnsetPrimarySelection.into(),
+        )),
+    ))
+}
+
+/// request the primary selection
+/// This will trigger a selection offer event of the primary kind with the data.
+pub fn request_primary_selection<Message>(
+    mime_type: String,
+) -> Command<Message> {
+    Command::single(command::Action::PlatformSpecific(
+        platform_specific::Action::Wayland(wayland::Action::DataDevice(
+            wayland::data_device::ActionInner::RequestPrimarySelectionData {
+                mime_type,
+            }
+            .into(),
+        )),
+    ))
+}
+
 /// start an internal drag and drop operation. Events will only be delivered to the same client.
 /// The client is responsible for data transfer.
 pub fn start_internal_drag<Message>(
@@ -0,0 +1,23 @@
+//! Query the toplevels of other clients.
+use iced_runtime::command::platform_specific::wayland::foreign_toplevel::ActionInner;
+use iced_runtime::command::{
+    self,
+    platform_specific::{self, wayland},
+    Command,
+};
+
+pub fn foreign_toplevel_action<Message>(
+    action_inner: ActionInner,
+) -> Command<Message> {
+    Command::single(command::Action::PlatformSpecific(
+        platform_specific::Action::Wayland(wayland::Action::ForeignToplevel(
+            action_inner.into(),
+        )),
+    ))
+}
+
+/// Request the open toplevels, delivered as `ForeignToplevelEvent::List`.
+/// Nothing is delivered when the compositor does not share its toplevels.
+pub fn request_toplevels<Message>() -> Command<Message> {
+    foreign_toplevel_action(ActionInner::RequestToplevels)
+}
@@ -19,6 +19,11 @@ pub fn input_method_action<Message>(
     ))
 }
 
+/// Reload the compose table, e.g. after the compose file was edited.
+pub fn reload_compose<Message>() -> Command<Message> {
+    input_method_action(ActionInner::ReloadCompose)
+}
+
 pub fn get_input_method_popup<Message>(builder: InputMethodPopupSettings) -> Command<Message> {
     Command::single(command::Action::PlatformSpecific(
         platform_specific::Action::Wayland(wayland::Action::InputMethodPopup(
@@ -1,5 +1,5 @@
 //! Interact with the window of your application.
-use std::marker::PhantomData;
+use std::{marker::PhantomData, time::Duration};
 
 use iced_runtime::command::{
     self,
@@ -121,3 +121,34 @@ pub fn set_layer<Message>(id: SurfaceId, layer: Layer) -> Command<Message> {
         )),
     ))
 }
+
+/// Create the layer surface described by `builder` while a text input is
+/// active and destroy it when none is, the way an on-screen keyboard behaves.
+///
+/// Focus changes shorter than `debounce`, e.g. moving between two text
+/// fields, don't hide and show the surface again. If `builder` has no
+/// exclusive zone, the surface reserves its height. `None` stops managing
+/// the surface and destroys it if shown.
+pub fn auto_show<Message>(
+    builder: Option<SctkLayerSurfaceSettings>,
+    debounce: Duration,
+) -> Command<Message> {
+    Command::single(command::Action::PlatformSpecific(
+        platform_specific::Action::Wayland(wayland::Action::LayerSurface(
+            wayland::layer_surface::Action::AutoShow { builder, debounce },
+        )),
+    ))
+}
+
+/// Show (`Some(true)`) or hide (`Some(false)`) the surface managed by
+/// [`auto_show`] regardless of the text input, or follow the text input
+/// again (`None`).
+pub fn set_auto_show_visible<Message>(
+    visible: Option<bool>,
+) -> Command<Message> {
+    Command::single(command::Action::PlatformSpecific(
+        platform_specific::Action::Wayland(wayland::Action::LayerSurface(
+            wayland::layer_surface::Action::AutoShowVisible(visible),
+        )),
+    ))
+}
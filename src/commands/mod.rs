@@ -1,6 +1,7 @@
 //! Interact with the wayland objects of your application.
 
 pub mod data_device;
+pub mod foreign_toplevel;
 pub mod layer_surface;
 pub mod input_method;
 pub mod popup;
@@ -15,3 +15,28 @@ pub fn virtual_keyboard_action<Message>(
         )),
     ))
 }
+
+/// Tap the sticky modifiers in `mask`, latching, locking or releasing them.
+/// A latched modifier only locks when tapped again within
+/// `VirtualKeyboardSettings::double_tap_interval`. Changes are reported as
+/// `VirtualKeyboardEvent::Modifiers`.
+pub fn tap_modifiers<Message>(mask: u32) -> Command<Message> {
+    virtual_keyboard_action(ActionInner::TapModifiers(mask))
+}
+
+/// Release every latched and locked modifier of the virtual keyboard.
+pub fn clear_modifiers<Message>() -> Command<Message> {
+    virtual_keyboard_action(ActionInner::ClearModifiers)
+}
+
+/// Switch the virtual keyboard to the layout at `group` of its keymap, the
+/// one last uploaded with `ActionInner::Keymap`, e.g. by the on-screen
+/// keyboard. Only the modifiers are sent, the keymap is left alone. The new
+/// group is reported as `VirtualKeyboardEvent::Modifiers`.
+///
+/// The layouts reported as `InputMethodKeyboardEvent::Layouts` belong to the
+/// keymap of the physical keyboard, `group` only indexes them if the same
+/// keymap was uploaded.
+pub fn set_group<Message>(group: u32) -> Command<Message> {
+    virtual_keyboard_action(ActionInner::SetGroup(group))
+}
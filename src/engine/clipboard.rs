@@ -0,0 +1,148 @@
+//! Clipboard history.
+//!
+//! Selections reported by [`DataControlEvent::Selection`] are kept most
+//! recent first, any of them can be committed to the focused text input.
+//! The history is only held in memory.
+//!
+//! [`DataControlEvent::Selection`]: iced_futures::core::event::wayland::DataControlEvent::Selection
+use std::collections::VecDeque;
+
+use iced_futures::core::event::wayland::{DataControlEvent, Privacy};
+use iced_runtime::command::platform_specific::wayland::input_method::ActionInner;
+
+use super::{Candidate, PrivacyWatch, Response};
+
+/// Labels of history candidates are cut to this many characters.
+const MAX_LABEL_CHARS: usize = 40;
+
+/// A bounded list of copied texts, most recent first.
+#[derive(Debug, Clone)]
+pub struct ClipboardHistory {
+    entries: VecDeque<String>,
+    capacity: usize,
+    privacy: PrivacyWatch,
+}
+
+impl Default for ClipboardHistory {
+    fn default() -> Self {
+        Self::new(50)
+    }
+}
+
+impl ClipboardHistory {
+    /// A history keeping up to `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity: capacity.max(1),
+            privacy: PrivacyWatch::default(),
+        }
+    }
+
+    /// Limit the number of entries, the oldest are dropped first.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        self.entries.truncate(self.capacity);
+    }
+
+    /// Apply the privacy of the text input. Nothing is recorded, offered or
+    /// committed while the input is private, text copied from a password
+    /// field stays out of the history.
+    pub fn set_privacy(&mut self, privacy: Privacy) {
+        self.privacy.set(privacy);
+    }
+
+    /// Follow `watch` instead of the watch shared with the default event
+    /// loop settings.
+    pub fn watch_privacy(&mut self, watch: PrivacyWatch) {
+        self.privacy = watch;
+    }
+
+    fn is_private(&self) -> bool {
+        self.privacy.get().private
+    }
+
+    /// Record the selection of a data control event. Data which is not
+    /// valid UTF-8 is ignored.
+    pub fn handle_event(&mut self, event: &DataControlEvent) {
+        match event {
+            DataControlEvent::Selection { data, .. } => {
+                if let Ok(text) = std::str::from_utf8(data) {
+                    self.push(text);
+                }
+            }
+        }
+    }
+
+    /// Record a copied text. Copying an entry again moves it to the front.
+    pub fn push(&mut self, text: &str) {
+        if self.is_private() || text.trim().is_empty() {
+            return;
+        }
+        if let Some(i) = self.entries.iter().position(|e| e == text) {
+            self.entries.remove(i);
+        }
+        self.entries.push_front(text.to_string());
+        self.entries.truncate(self.capacity);
+    }
+
+    /// The entries, most recent first.
+    pub fn entries(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(String::as_str)
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the history has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entries as candidates, labelled with their first line.
+    pub fn candidates(&self) -> Vec<Candidate> {
+        if self.is_private() {
+            return Vec::new();
+        }
+        self.entries
+            .iter()
+            .map(|text| Candidate {
+                text: text.clone(),
+                label: label(text),
+            })
+            .collect()
+    }
+
+    /// Commit the entry at `index` and move it to the front.
+    pub fn commit(&mut self, index: usize) -> Response {
+        if self.is_private() {
+            return Response::ignored();
+        }
+        let Some(text) = self.entries.remove(index) else {
+            return Response::ignored();
+        };
+        self.entries.push_front(text.clone());
+        Response::consumed(vec![ActionInner::CommitString(text)])
+    }
+
+    /// Remove the entry at `index`.
+    pub fn remove(&mut self, index: usize) -> Option<String> {
+        self.entries.remove(index)
+    }
+
+    /// Remove every entry.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+fn label(text: &str) -> String {
+    let line = text.trim().lines().next().unwrap_or_default();
+    let mut label: String = line.chars().take(MAX_LABEL_CHARS).collect();
+    if label.len() < line.len() || line.len() < text.trim().len() {
+        label.push('…');
+    }
+    label
+}
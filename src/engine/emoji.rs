@@ -0,0 +1,843 @@
+//! Emoji lookup by keyword.
+//!
+//! Typing `:` at the start of the text or after whitespace starts a query,
+//! so times like `10:30` are typed as usual. The following characters are
+//! shown as preedit and matched against the CLDR annotations. `Return`, `Tab`
+//! or a closing `:` commit the selected emoji, `Escape` and `space` leave the
+//! typed text as is.
+use std::{
+    collections::{HashMap, VecDeque},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use iced_futures::core::event::wayland::{KeyEvent, Privacy, TextChange};
+use iced_runtime::command::platform_specific::wayland::{
+    data_device::DataFromMimeType, input_method::ActionInner,
+    layer_surface::SctkLayerSurfaceSettings,
+};
+use iced_runtime::{window, Command};
+use sctk::seat::keyboard::Keysym;
+use sctk::shell::wlr_layer::{KeyboardInteractivity, Layer};
+
+use super::{Candidate, PrivacyWatch, Response};
+use crate::commands::{data_device, input_method::input_method_action};
+
+const ANNOTATIONS: &str = include_str!("../../data/emoji/annotations-en.xml");
+const VARIANTS: &str = include_str!("../../data/emoji/variants.txt");
+
+const ZWJ: char = '\u{200D}';
+const VS16: char = '\u{FE0F}';
+
+/// The character which starts an emoji query.
+pub const TRIGGER: char = ':';
+
+/// Fitzpatrick skin tone modifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SkinTone {
+    /// Type 1-2
+    Light,
+    /// Type 3
+    MediumLight,
+    /// Type 4
+    Medium,
+    /// Type 5
+    MediumDark,
+    /// Type 6
+    Dark,
+}
+
+impl SkinTone {
+    /// The modifier character appended to the base emoji.
+    pub fn modifier(self) -> char {
+        match self {
+            SkinTone::Light => '\u{1F3FB}',
+            SkinTone::MediumLight => '\u{1F3FC}',
+            SkinTone::Medium => '\u{1F3FD}',
+            SkinTone::MediumDark => '\u{1F3FE}',
+            SkinTone::Dark => '\u{1F3FF}',
+        }
+    }
+}
+
+/// Gender signs joined to person emoji with a zero width joiner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Gender {
+    /// ♀
+    Female,
+    /// ♂
+    Male,
+}
+
+impl Gender {
+    /// The sign joined to the base emoji.
+    pub fn sign(self) -> char {
+        match self {
+            Gender::Female => '\u{2640}',
+            Gender::Male => '\u{2642}',
+        }
+    }
+}
+
+/// An annotated emoji.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Emoji {
+    /// The emoji without any modifiers.
+    pub emoji: String,
+    /// The CLDR text-to-speech name.
+    pub name: String,
+    /// Lowercase CLDR keywords.
+    pub keywords: Vec<String>,
+    /// Whether the emoji accepts a skin tone modifier.
+    pub skin_tone: bool,
+    /// Whether the emoji accepts a gender sign.
+    pub gender: bool,
+}
+
+impl Emoji {
+    /// The emoji with the given skin tone and gender applied where supported.
+    pub fn variant(
+        &self,
+        skin_tone: Option<SkinTone>,
+        gender: Option<Gender>,
+    ) -> String {
+        let mut chars = self.emoji.chars().peekable();
+        let mut variant = String::with_capacity(self.emoji.len() + 8);
+        if let Some(first) = chars.next() {
+            variant.push(first);
+        }
+        if let Some(tone) = skin_tone.filter(|_| self.skin_tone) {
+            // The modifier replaces the emoji presentation selector.
+            if chars.peek() == Some(&VS16) {
+                chars.next();
+            }
+            variant.push(tone.modifier());
+        }
+        variant.extend(chars);
+        if let Some(gender) = gender.filter(|_| self.gender) {
+            variant.push(ZWJ);
+            variant.push(gender.sign());
+            variant.push(VS16);
+        }
+        variant
+    }
+
+    fn score(&self, query: &str) -> Option<u8> {
+        if self.name == query {
+            Some(0)
+        } else if self.keywords.iter().any(|k| k == query) {
+            Some(1)
+        } else if self.name.starts_with(query) {
+            Some(2)
+        } else if self.keywords.iter().any(|k| k.starts_with(query)) {
+            Some(3)
+        } else if self.name.contains(query) {
+            Some(4)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parse a CLDR annotations file, e.g. `common/annotations/en.xml`.
+///
+/// Keyword annotations and `type="tts"` names for the same code point are
+/// merged into one [`Emoji`], in the order they first appear.
+pub fn parse_annotations(xml: &str) -> Vec<Emoji> {
+    let mut emoji: Vec<Emoji> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    let mut rest = xml;
+    while let Some(start) = rest.find("<annotation ") {
+        rest = &rest[start + "<annotation ".len()..];
+        let Some(tag_end) = rest.find('>') else {
+            break;
+        };
+        let attributes = &rest[..tag_end];
+        rest = &rest[tag_end + 1..];
+        let Some(content_end) = rest.find("</annotation>") else {
+            break;
+        };
+        let content = unescape(&rest[..content_end]);
+        rest = &rest[content_end..];
+
+        let Some(cp) = attribute(attributes, "cp").map(unescape) else {
+            continue;
+        };
+        let i = *index.entry(cp.clone()).or_insert_with(|| {
+            emoji.push(Emoji {
+                emoji: cp,
+                name: String::new(),
+                keywords: Vec::new(),
+                skin_tone: false,
+                gender: false,
+            });
+            emoji.len() - 1
+        });
+        if attribute(attributes, "type") == Some("tts") {
+            emoji[i].name = content.trim().to_lowercase();
+        } else {
+            emoji[i].keywords.extend(
+                content
+                    .split('|')
+                    .map(|k| k.trim().to_lowercase())
+                    .filter(|k| !k.is_empty()),
+            );
+        }
+    }
+
+    emoji
+}
+
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!("{name}=\"");
+    let start = attributes.find(&pattern)? + pattern.len();
+    let len = attributes[start..].find('"')?;
+    Some(&attributes[start..start + len])
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Recently used emoji, most recent first.
+#[derive(Debug, Clone)]
+pub struct Recent {
+    path: Option<PathBuf>,
+    entries: VecDeque<String>,
+    capacity: usize,
+}
+
+impl Recent {
+    /// Load the recently used list from `path`, a missing file is an empty list.
+    pub fn load(path: impl Into<PathBuf>, capacity: usize) -> Self {
+        let path = path.into();
+        let entries = fs::read_to_string(&path)
+            .map(|s| {
+                s.lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .take(capacity)
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            path: Some(path),
+            entries,
+            capacity,
+        }
+    }
+
+    /// A list which is never written to disk.
+    pub fn in_memory(capacity: usize) -> Self {
+        Self {
+            path: None,
+            entries: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// The recently used emoji, most recent first.
+    pub fn entries(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(String::as_str)
+    }
+
+    /// Move `emoji` to the front of the list and save it.
+    pub fn push(&mut self, emoji: &str) -> io::Result<()> {
+        self.entries.retain(|e| e != emoji);
+        self.entries.push_front(emoji.to_string());
+        self.entries.truncate(self.capacity);
+        self.save()
+    }
+
+    fn rank(&self, emoji: &str) -> usize {
+        self.entries
+            .iter()
+            .position(|e| e == emoji)
+            .unwrap_or(usize::MAX)
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+        let mut contents = String::new();
+        for entry in &self.entries {
+            contents.push_str(entry);
+            contents.push('\n');
+        }
+        super::write_atomic(path, contents.as_bytes())
+    }
+}
+
+/// Keyword search over emoji with skin tone and gender preferences.
+#[derive(Debug, Clone)]
+pub struct EmojiEngine {
+    emoji: Vec<Emoji>,
+    recent: Recent,
+    skin_tone: Option<SkinTone>,
+    gender: Option<Gender>,
+    max_candidates: usize,
+    query: Option<String>,
+    candidates: Vec<Candidate>,
+    selected: usize,
+    privacy: PrivacyWatch,
+    /// The external changes of the watch the query knows about.
+    external_changes: u64,
+    before_cursor: String,
+}
+
+impl Default for EmojiEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EmojiEngine {
+    /// An engine with the bundled English annotations and the recently used
+    /// list from the user data directory.
+    pub fn new() -> Self {
+        let recent = match super::data_dir() {
+            Some(dir) => Recent::load(dir.join("emoji").join("recent"), 32),
+            None => Recent::in_memory(32),
+        };
+        Self::with_annotations(ANNOTATIONS, recent)
+    }
+
+    /// An engine with the annotations from a CLDR annotations file.
+    pub fn with_annotations(xml: &str, recent: Recent) -> Self {
+        let mut engine = Self {
+            emoji: Vec::new(),
+            recent,
+            skin_tone: None,
+            gender: None,
+            max_candidates: 9,
+            query: None,
+            candidates: Vec::new(),
+            selected: 0,
+            privacy: PrivacyWatch::default(),
+            external_changes: 0,
+            before_cursor: String::new(),
+        };
+        engine.external_changes = engine.privacy.external_changes();
+        engine.add_annotations(xml);
+        engine
+    }
+
+    /// Load additional annotations, e.g. for another locale.
+    pub fn load_annotations(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> io::Result<()> {
+        let xml = fs::read_to_string(path)?;
+        self.add_annotations(&xml);
+        Ok(())
+    }
+
+    fn add_annotations(&mut self, xml: &str) {
+        for new in parse_annotations(xml) {
+            match self.emoji.iter_mut().find(|e| e.emoji == new.emoji) {
+                Some(existing) => {
+                    if existing.name.is_empty() {
+                        existing.name = new.name;
+                    }
+                    existing.keywords.extend(new.keywords);
+                }
+                None => self.emoji.push(new),
+            }
+        }
+        for line in VARIANTS.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let (Some(base), Some(flags)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            if let Some(e) = self.emoji.iter_mut().find(|e| e.emoji == base) {
+                e.skin_tone = flags.contains('t');
+                e.gender = flags.contains('g');
+            }
+        }
+    }
+
+    /// Set the skin tone applied to candidates which support it.
+    pub fn set_skin_tone(&mut self, skin_tone: Option<SkinTone>) {
+        self.skin_tone = skin_tone;
+        self.refresh();
+    }
+
+    /// Set the gender applied to candidates which support it.
+    pub fn set_gender(&mut self, gender: Option<Gender>) {
+        self.gender = gender;
+        self.refresh();
+    }
+
+    /// Set how many candidates a query produces.
+    pub fn set_max_candidates(&mut self, max_candidates: usize) {
+        self.max_candidates = max_candidates.max(1);
+        self.refresh();
+    }
+
+    /// Apply the privacy of the text input. Private inputs do not update the
+    /// recently used list, bypassing composition disables the engine.
+    pub fn set_privacy(&mut self, privacy: Privacy) {
+        if privacy.bypass_composition {
+            self.reset();
+        }
+        self.privacy.set(privacy);
+    }
+
+    /// Follow `watch` instead of the watch shared with the default event
+    /// loop settings.
+    pub fn watch_privacy(&mut self, watch: PrivacyWatch) {
+        self.external_changes = watch.external_changes();
+        self.privacy = watch;
+    }
+
+    /// Update the context from a `SurroundingText` event. `cursor` is a byte
+    /// offset into `text`. Without it `:` always starts a query.
+    pub fn surrounding_text(&mut self, text: &str, cursor: u32) {
+        let cursor = (cursor as usize).min(text.len());
+        self.before_cursor = text.get(..cursor).unwrap_or_default().to_string();
+    }
+
+    /// Whether a query is in progress.
+    pub fn is_composing(&self) -> bool {
+        self.query.is_some()
+    }
+
+    /// The query typed after the trigger, if any.
+    pub fn query(&self) -> Option<&str> {
+        self.query.as_deref()
+    }
+
+    /// The candidates for the current query.
+    pub fn candidates(&self) -> &[Candidate] {
+        &self.candidates
+    }
+
+    /// The index of the selected candidate.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// The recently used emoji.
+    pub fn recent(&self) -> &Recent {
+        &self.recent
+    }
+
+    /// Search the annotations, `_` matches a space.
+    ///
+    /// An empty query lists the recently used emoji.
+    pub fn search(&self, query: &str) -> Vec<Candidate> {
+        let query = query.to_lowercase().replace('_', " ");
+        if query.is_empty() {
+            return self
+                .recent
+                .entries()
+                .take(self.max_candidates)
+                .map(|e| Candidate {
+                    text: e.to_string(),
+                    label: self
+                        .emoji
+                        .iter()
+                        .find(|a| e.starts_with(a.emoji.trim_end_matches(VS16)))
+                        .map(|a| a.name.clone())
+                        .unwrap_or_default(),
+                })
+                .collect();
+        }
+
+        let mut matches: Vec<_> = self
+            .emoji
+            .iter()
+            .enumerate()
+            .filter_map(|(i, e)| {
+                let text = e.variant(self.skin_tone, self.gender);
+                e.score(&query)
+                    .map(|score| (score, self.recent.rank(&text), i, text))
+            })
+            .collect();
+        matches.sort_by_key(|(score, rank, i, _)| (*score, *rank, *i));
+        matches
+            .into_iter()
+            .take(self.max_candidates)
+            .map(|(_, _, i, text)| Candidate {
+                text,
+                label: self.emoji[i].name.clone(),
+            })
+            .collect()
+    }
+
+    /// Feed a key press to the engine.
+    pub fn handle_key(&mut self, key: &KeyEvent) -> Response {
+        if self.privacy.get().bypass_composition {
+            // The query may have started before the input became private.
+            self.reset();
+            return Response::ignored();
+        }
+        let external_changes = self.privacy.external_changes();
+        if external_changes != self.external_changes {
+            // The event loop already cleared the preedit of the query.
+            self.external_changes = external_changes;
+            self.reset();
+        }
+        let text = key.utf8.as_deref().unwrap_or_default();
+        let Some(query) = self.query.as_mut() else {
+            let at_word_start = self
+                .before_cursor
+                .chars()
+                .next_back()
+                .map_or(true, char::is_whitespace);
+            if at_word_start
+                && text.starts_with(TRIGGER)
+                && text.chars().count() == 1
+            {
+                self.query = Some(String::new());
+                self.refresh();
+                return Response::consumed(vec![self.preedit()]);
+            }
+            return Response::ignored();
+        };
+
+        match key.keysym {
+            Keysym::Escape => Response::consumed(self.cancel()),
+            Keysym::Return | Keysym::KP_Enter | Keysym::Tab => {
+                if self.candidates.is_empty() {
+                    // The key still does what the user meant, e.g. submit.
+                    Response {
+                        actions: self.cancel(),
+                        consumed: false,
+                    }
+                } else {
+                    self.choose(self.selected)
+                }
+            }
+            Keysym::BackSpace => {
+                if query.pop().is_none() {
+                    self.reset();
+                    Response::consumed(vec![ActionInner::SetPreeditString {
+                        string: String::new(),
+                        cursor_begin: 0,
+                        cursor_end: 0,
+                    }])
+                } else {
+                    self.refresh();
+                    Response::consumed(vec![self.preedit()])
+                }
+            }
+            Keysym::Up | Keysym::Left => {
+                self.selected = self.selected.saturating_sub(1);
+                Response::consumed(Vec::new())
+            }
+            Keysym::Down | Keysym::Right => {
+                if self.selected + 1 < self.candidates.len() {
+                    self.selected += 1;
+                }
+                Response::consumed(Vec::new())
+            }
+            _ if text.starts_with(TRIGGER) && !query.is_empty() => {
+                if self.candidates.is_empty() {
+                    let mut actions = self.cancel();
+                    actions.push(ActionInner::CommitString(text.to_string()));
+                    Response::consumed(actions)
+                } else {
+                    self.choose(self.selected)
+                }
+            }
+            _ if !text.is_empty()
+                && text.chars().all(|c| {
+                    c.is_alphanumeric() || matches!(c, '_' | '-' | '+')
+                }) =>
+            {
+                query.push_str(text);
+                self.refresh();
+                Response::consumed(vec![self.preedit()])
+            }
+            _ if !text.is_empty() => {
+                // Anything else ends the query and is typed as usual.
+                let mut actions = self.cancel();
+                actions.push(ActionInner::CommitString(text.to_string()));
+                Response::consumed(actions)
+            }
+            _ => Response::ignored(),
+        }
+    }
+
+    /// Commit the candidate at `index`, e.g. when it is clicked in the popup.
+    pub fn choose(&mut self, index: usize) -> Response {
+        let Some(candidate) = self.candidates.get(index).cloned() else {
+            return Response::ignored();
+        };
+        if !self.privacy.get().private {
+            if let Err(err) = self.recent.push(&candidate.text) {
+                log::warn!("Failed to save recently used emoji: {}", err);
+            }
+        }
+        self.reset();
+        Response::consumed(vec![ActionInner::CommitString(candidate.text)])
+    }
+
+    /// Abort the query and commit the typed text unchanged.
+    pub fn cancel(&mut self) -> Vec<ActionInner> {
+        let Some(query) = self.query.take() else {
+            return Vec::new();
+        };
+        self.reset();
+        vec![ActionInner::CommitString(format!("{TRIGGER}{query}"))]
+    }
+
+    /// Drop the query when the text was changed outside the input method, as
+    /// the preedit no longer belongs where the cursor is.
+    ///
+    /// Engines following the event loop's watch drop the query on the next
+    /// key by themselves, this drops it right away.
+    pub fn text_changed(&mut self, change: TextChange) -> Vec<ActionInner> {
+        if change == TextChange::External && self.is_composing() {
+            self.reset();
+            vec![ActionInner::SetPreeditString {
+                string: String::new(),
+                cursor_begin: 0,
+                cursor_end: 0,
+            }]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Drop the query without sending anything, e.g. on `Deactivate`.
+    pub fn reset(&mut self) {
+        self.query = None;
+        self.candidates.clear();
+        self.selected = 0;
+    }
+
+    fn refresh(&mut self) {
+        if let Some(query) = self.query.as_ref() {
+            self.candidates = self.search(query);
+            self.selected = 0;
+        }
+    }
+
+    fn preedit(&self) -> ActionInner {
+        let string =
+            format!("{TRIGGER}{}", self.query.as_deref().unwrap_or_default());
+        let cursor = string.len() as i32;
+        ActionInner::SetPreeditString {
+            string,
+            cursor_begin: cursor,
+            cursor_end: cursor,
+        }
+    }
+}
+
+/// Settings for a standalone picker on a layer surface, for use when no text
+/// input is active. The surface is sized to its content.
+pub fn picker_surface(id: window::Id) -> SctkLayerSurfaceSettings {
+    SctkLayerSurfaceSettings {
+        id,
+        layer: Layer::Overlay,
+        keyboard_interactivity: KeyboardInteractivity::Exclusive,
+        namespace: String::from("emoji-picker"),
+        size: None,
+        ..Default::default()
+    }
+}
+
+/// Output a picked emoji.
+///
+/// With an active text input the emoji is committed, otherwise it is placed
+/// in the clipboard so it can be pasted.
+pub fn pick<Message>(
+    emoji: String,
+    input_method_active: bool,
+) -> Command<Message> {
+    if input_method_active {
+        input_method_action(ActionInner::CommitString(emoji))
+    } else {
+        data_device::set_selection(
+            TEXT_MIME_TYPES.iter().map(|m| m.to_string()).collect(),
+            Box::new(Text(emoji)),
+        )
+    }
+}
+
+const TEXT_MIME_TYPES: [&str; 3] =
+    ["text/plain;charset=utf-8", "UTF8_STRING", "text/plain"];
+
+struct Text(String);
+
+impl DataFromMimeType for Text {
+    fn from_mime_type(&self, mime_type: &str) -> Option<Vec<u8>> {
+        TEXT_MIME_TYPES
+            .contains(&mime_type)
+            .then(|| self.0.as_bytes().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iced_runtime::command::{
+        self,
+        platform_specific::{self, wayland},
+    };
+
+    use super::*;
+
+    const XML: &str = r#"<annotations>
+        <annotation cp="😄">face | smile</annotation>
+        <annotation cp="😄" type="tts">grinning face with smiling eyes</annotation>
+        <annotation cp="👍">+1 | hand | thumbs up</annotation>
+        <annotation cp="👍" type="tts">thumbs up</annotation>
+        <annotation cp="🙋">hand | raised</annotation>
+        <annotation cp="🙋" type="tts">person raising hand</annotation>
+    </annotations>"#;
+
+    fn engine() -> EmojiEngine {
+        let mut engine =
+            EmojiEngine::with_annotations(XML, Recent::in_memory(8));
+        // Tests must not change the privacy shared by every engine.
+        engine.watch_privacy(PrivacyWatch::new(Privacy::default()));
+        engine
+    }
+
+    fn key(keysym: Keysym, utf8: Option<&str>) -> KeyEvent {
+        KeyEvent {
+            time: 0,
+            raw_code: 0,
+            keysym,
+            utf8: utf8.map(str::to_string),
+        }
+    }
+
+    /// Type `text`, one key per character.
+    fn type_text(engine: &mut EmojiEngine, text: &str) -> Response {
+        let mut response = Response::ignored();
+        for c in text.chars() {
+            let utf8 = c.to_string();
+            let keysym = Keysym::from_char(c);
+            response = engine.handle_key(&key(keysym, Some(&utf8)));
+        }
+        response
+    }
+
+    fn texts(candidates: &[Candidate]) -> Vec<&str> {
+        candidates.iter().map(|c| c.text.as_str()).collect()
+    }
+
+    #[test]
+    fn trigger_starts_a_query_only_at_word_starts() {
+        let mut engine = engine();
+        engine.surrounding_text("10", 2);
+        assert!(!type_text(&mut engine, ":").consumed);
+        engine.surrounding_text("a", 1);
+        assert!(!type_text(&mut engine, ":").consumed);
+        assert!(!engine.is_composing());
+
+        engine.surrounding_text("at ", 3);
+        let response = type_text(&mut engine, ":");
+        assert!(response.consumed);
+        assert_eq!(engine.query(), Some(""));
+
+        let response = type_text(&mut engine, "smi");
+        assert_eq!(engine.query(), Some("smi"));
+        assert!(matches!(
+            response.actions.as_slice(),
+            [ActionInner::SetPreeditString { string, cursor_begin: 4, .. }]
+                if string == ":smi"
+        ));
+        assert_eq!(texts(engine.candidates()), ["😄"]);
+
+        let response = engine.handle_key(&key(Keysym::Return, None));
+        assert!(response.consumed);
+        assert!(matches!(
+            response.actions.as_slice(),
+            [ActionInner::CommitString(emoji)] if emoji == "😄"
+        ));
+        assert!(!engine.is_composing());
+        assert_eq!(engine.recent().entries().collect::<Vec<_>>(), ["😄"]);
+    }
+
+    #[test]
+    fn unmatched_query_is_typed_and_return_passes_through() {
+        let mut engine = engine();
+        type_text(&mut engine, ":xqz");
+        assert!(engine.candidates().is_empty());
+
+        let response = engine.handle_key(&key(Keysym::Return, None));
+        assert!(!response.consumed);
+        assert!(matches!(
+            response.actions.as_slice(),
+            [ActionInner::CommitString(text)] if text == ":xqz"
+        ));
+        assert!(!engine.is_composing());
+    }
+
+    #[test]
+    fn names_rank_before_keywords() {
+        let engine = engine();
+        assert_eq!(texts(&engine.search("thumbs_up")), ["👍"]);
+        assert_eq!(texts(&engine.search("hand")), ["👍", "🙋"]);
+        assert_eq!(texts(&engine.search("rais")), ["🙋"]);
+        assert_eq!(
+            engine.search("person raising hand")[0].label,
+            "person raising hand"
+        );
+        assert!(engine.search("").is_empty());
+    }
+
+    #[test]
+    fn skin_tone_and_gender_apply_where_supported() {
+        let mut engine = engine();
+        engine.set_skin_tone(Some(SkinTone::Medium));
+        engine.set_gender(Some(Gender::Female));
+        assert_eq!(
+            texts(&engine.search("hand")),
+            ["👍\u{1F3FD}", "🙋\u{1F3FD}\u{200D}\u{2640}\u{FE0F}"]
+        );
+        assert_eq!(texts(&engine.search("smile")), ["😄"]);
+    }
+
+    #[test]
+    fn pick_commits_or_copies() {
+        let actions = pick::<()>("👍".to_string(), true).actions();
+        let [command::Action::PlatformSpecific(
+            platform_specific::Action::Wayland(wayland::Action::InputMethod(
+                action,
+            )),
+        )] = actions.as_slice()
+        else {
+            panic!("unexpected actions {actions:?}");
+        };
+        assert!(
+            matches!(&action.inner, ActionInner::CommitString(e) if e == "👍")
+        );
+
+        let actions = pick::<()>("👍".to_string(), false).actions();
+        let [command::Action::PlatformSpecific(
+            platform_specific::Action::Wayland(wayland::Action::DataDevice(
+                action,
+            )),
+        )] = actions.as_slice()
+        else {
+            panic!("unexpected actions {actions:?}");
+        };
+        let wayland::data_device::ActionInner::SetSelection {
+            mime_types,
+            data,
+        } = &action.inner
+        else {
+            panic!("unexpected action {action:?}");
+        };
+        assert!(mime_types.iter().any(|m| m == "text/plain"));
+        assert_eq!(data.from_mime_type("UTF8_STRING"), Some("👍".into()));
+        assert_eq!(data.from_mime_type("image/png"), None);
+    }
+}
@@ -0,0 +1,535 @@
+//! Handwriting recognition.
+//!
+//! Strokes are drawn with a finger, the mouse or a tablet tool on a
+//! [`Pad`](crate::osk::handwriting::Pad), usually on a layer surface, and
+//! passed to a [`HandwritingEngine`]. It asks a [`Recognizer`] for the
+//! characters they may be and offers those as candidates for the input
+//! method popup. Choosing one commits it. Private inputs hide their text, so
+//! no candidates are offered for them, accepting commits the best match.
+//!
+//! The bundled [`TemplateRecognizer`] knows digits and Latin letters. It
+//! compares the strokes with templates as clouds of points, so neither the
+//! order nor the direction of strokes matters. Templates files hold one
+//! template per line, the text followed by its strokes separated by `|`:
+//!
+//! ```text
+//! # text x,y x,y ... | x,y ...
+//! T 0,0 6,0 | 3,0 3,10
+//! ```
+use std::{fmt, fs, io, path::Path};
+
+use iced_runtime::command::platform_specific::wayland::input_method::ActionInner;
+use iced_runtime::core::Point;
+
+use iced_futures::core::event::wayland::Privacy;
+
+use super::{Candidate, PrivacyWatch, Response};
+
+const TEMPLATES: &str = include_str!("../../data/handwriting/latin.txt");
+
+/// The number of points strokes are resampled to.
+const SAMPLES: usize = 32;
+
+/// A stroke, the positions of a pointer from touching the surface to
+/// leaving it.
+pub type Stroke = Vec<Point>;
+
+/// Turns strokes into the text they may stand for.
+pub trait Recognizer {
+    /// At most `max` candidates for `strokes`, best first.
+    fn recognize(&self, strokes: &[Stroke], max: usize) -> Vec<Candidate>;
+}
+
+/// Recognizes characters by comparing them with templates.
+#[derive(Debug, Clone)]
+pub struct TemplateRecognizer {
+    templates: Vec<(String, Vec<Point>)>,
+}
+
+impl Default for TemplateRecognizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TemplateRecognizer {
+    /// A recognizer with the bundled templates for digits and Latin
+    /// letters.
+    pub fn new() -> Self {
+        Self::parse(TEMPLATES)
+    }
+
+    /// A recognizer without templates.
+    pub fn empty() -> Self {
+        Self {
+            templates: Vec::new(),
+        }
+    }
+
+    /// Load a templates file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// Parse the templates format, malformed lines are skipped.
+    pub fn parse(s: &str) -> Self {
+        let mut recognizer = Self::empty();
+        recognizer.extend(s);
+        recognizer
+    }
+
+    /// Add the templates in `s`, malformed lines are skipped.
+    pub fn extend(&mut self, s: &str) {
+        for line in s.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((text, strokes)) = line.split_once(char::is_whitespace)
+            else {
+                continue;
+            };
+            let strokes: Option<Vec<Stroke>> =
+                strokes.split('|').map(parse_stroke).collect();
+            if let Some(strokes) = strokes {
+                self.add(text, &strokes);
+            }
+        }
+    }
+
+    /// Add a template for `text`, e.g. strokes the user wrote for it.
+    pub fn add(&mut self, text: &str, strokes: &[Stroke]) {
+        if let Some(cloud) = cloud(strokes) {
+            self.templates.push((text.to_string(), cloud));
+        }
+    }
+
+    /// The number of templates.
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    /// Whether there are no templates.
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+}
+
+impl Recognizer for TemplateRecognizer {
+    fn recognize(&self, strokes: &[Stroke], max: usize) -> Vec<Candidate> {
+        let Some(input) = cloud(strokes) else {
+            return Vec::new();
+        };
+        let mut scored: Vec<(f32, &str)> = self
+            .templates
+            .iter()
+            .map(|(text, template)| {
+                (cloud_distance(&input, template), text.as_str())
+            })
+            .collect();
+        scored.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut candidates: Vec<Candidate> = Vec::new();
+        for (_, text) in scored {
+            if candidates.len() >= max {
+                break;
+            }
+            // Texts with several templates are offered once.
+            if candidates.iter().all(|c| c.text != text) {
+                candidates.push(Candidate {
+                    text: text.to_string(),
+                    label: String::new(),
+                });
+            }
+        }
+        candidates
+    }
+}
+
+/// Handwriting input.
+pub struct HandwritingEngine {
+    recognizer: Box<dyn Recognizer>,
+    strokes: Vec<Stroke>,
+    candidates: Vec<Candidate>,
+    max_candidates: usize,
+    privacy: PrivacyWatch,
+}
+
+impl fmt::Debug for HandwritingEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HandwritingEngine")
+            .field("strokes", &self.strokes)
+            .field("candidates", &self.candidates)
+            .field("max_candidates", &self.max_candidates)
+            .field("privacy", &self.privacy)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for HandwritingEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HandwritingEngine {
+    /// An engine with the bundled templates and the ones in the user data
+    /// directory, `handwriting/templates`.
+    pub fn new() -> Self {
+        let mut recognizer = TemplateRecognizer::new();
+        if let Some(templates) = super::data_dir().and_then(|dir| {
+            fs::read_to_string(dir.join("handwriting/templates")).ok()
+        }) {
+            recognizer.extend(&templates);
+        }
+        Self::with_recognizer(recognizer)
+    }
+
+    /// An engine using `recognizer`.
+    pub fn with_recognizer(recognizer: impl Recognizer + 'static) -> Self {
+        Self {
+            recognizer: Box::new(recognizer),
+            strokes: Vec::new(),
+            candidates: Vec::new(),
+            max_candidates: 5,
+            privacy: PrivacyWatch::default(),
+        }
+    }
+
+    /// Set how many candidates are offered.
+    pub fn set_max_candidates(&mut self, max_candidates: usize) {
+        self.max_candidates = max_candidates.max(1);
+        self.recognize();
+    }
+
+    /// Apply the privacy of the text input. Private inputs get no
+    /// candidates, they would show the hidden text.
+    pub fn set_privacy(&mut self, privacy: Privacy) {
+        self.privacy.set(privacy);
+    }
+
+    /// Follow `watch` instead of the watch shared with the default event
+    /// loop settings.
+    pub fn watch_privacy(&mut self, watch: PrivacyWatch) {
+        self.privacy = watch;
+    }
+
+    fn is_private(&self) -> bool {
+        self.privacy.get().private
+    }
+
+    /// The strokes of the character being written.
+    pub fn strokes(&self) -> &[Stroke] {
+        &self.strokes
+    }
+
+    /// The candidates for the strokes, best first.
+    pub fn candidates(&self) -> &[Candidate] {
+        if self.is_private() {
+            return &[];
+        }
+        &self.candidates
+    }
+
+    /// Add a finished stroke and recognize the strokes again.
+    pub fn add_stroke(&mut self, stroke: Stroke) -> &[Candidate] {
+        if !stroke.is_empty() {
+            self.strokes.push(stroke);
+            self.recognize();
+        }
+        self.candidates()
+    }
+
+    /// Remove the last stroke.
+    pub fn undo(&mut self) -> &[Candidate] {
+        if self.strokes.pop().is_some() {
+            self.recognize();
+        }
+        self.candidates()
+    }
+
+    /// Commit the candidate at `index` and start the next character.
+    pub fn accept(&mut self, index: usize) -> Response {
+        let Some(candidate) = self.candidates.get(index) else {
+            return Response::ignored();
+        };
+        let text = candidate.text.clone();
+        self.reset();
+        Response::consumed(vec![ActionInner::CommitString(text)])
+    }
+
+    /// Drop the strokes and candidates, e.g. on `Deactivate`.
+    pub fn reset(&mut self) {
+        self.strokes.clear();
+        self.candidates.clear();
+    }
+
+    fn recognize(&mut self) {
+        self.candidates = if self.strokes.is_empty() {
+            Vec::new()
+        } else {
+            self.recognizer
+                .recognize(&self.strokes, self.max_candidates)
+        };
+    }
+}
+
+fn parse_stroke(s: &str) -> Option<Stroke> {
+    let stroke: Option<Stroke> = s
+        .split_whitespace()
+        .map(|p| {
+            let (x, y) = p.split_once(',')?;
+            Some(Point::new(x.parse().ok()?, y.parse().ok()?))
+        })
+        .collect();
+    stroke.filter(|s| !s.is_empty())
+}
+
+/// The points of `strokes` resampled to [`SAMPLES`] points evenly spaced
+/// along them, centered on the origin and scaled to fit a unit box.
+fn cloud(strokes: &[Stroke]) -> Option<Vec<Point>> {
+    let strokes: Vec<&Stroke> =
+        strokes.iter().filter(|s| !s.is_empty()).collect();
+    if strokes.is_empty() {
+        return None;
+    }
+    let total: f32 = strokes.iter().map(|s| length(s)).sum();
+    let mut points = Vec::with_capacity(SAMPLES);
+    if total == 0.0 {
+        // Only dots, spread the samples over them.
+        for i in 0..SAMPLES {
+            points.push(strokes[i * strokes.len() / SAMPLES][0]);
+        }
+    } else {
+        let step = total / (SAMPLES - 1) as f32;
+        let mut walked = 0.0;
+        let mut target = 0.0;
+        for stroke in &strokes {
+            if stroke.len() == 1 || length(stroke) == 0.0 {
+                // A dot gets at least one point, it matters for `i` and `j`.
+                points.push(stroke[0]);
+                continue;
+            }
+            for w in stroke.windows(2) {
+                let segment = w[0].distance(w[1]);
+                if segment == 0.0 {
+                    continue;
+                }
+                while walked + segment >= target {
+                    let t = (target - walked) / segment;
+                    points.push(Point::new(
+                        w[0].x + t * (w[1].x - w[0].x),
+                        w[0].y + t * (w[1].y - w[0].y),
+                    ));
+                    target += step;
+                }
+                walked += segment;
+            }
+        }
+        // Dots and rounding change the count, keep it fixed for matching.
+        if points.len() > SAMPLES {
+            let count = points.len();
+            points =
+                (0..SAMPLES).map(|i| points[i * count / SAMPLES]).collect();
+        } else {
+            let last = *strokes[strokes.len() - 1].last()?;
+            points.resize(SAMPLES, last);
+        }
+    }
+    Some(normalize(&points))
+}
+
+fn length(stroke: &[Point]) -> f32 {
+    stroke.windows(2).map(|w| w[0].distance(w[1])).sum()
+}
+
+fn normalize(points: &[Point]) -> Vec<Point> {
+    let (min, max) = points.iter().fold(
+        (
+            Point::new(f32::MAX, f32::MAX),
+            Point::new(f32::MIN, f32::MIN),
+        ),
+        |(min, max), p| {
+            (
+                Point::new(min.x.min(p.x), min.y.min(p.y)),
+                Point::new(max.x.max(p.x), max.y.max(p.y)),
+            )
+        },
+    );
+    let scale = (max.x - min.x).max(max.y - min.y).max(f32::EPSILON);
+    let n = points.len().max(1) as f32;
+    let center = Point::new(
+        points.iter().map(|p| p.x).sum::<f32>() / n,
+        points.iter().map(|p| p.y).sum::<f32>() / n,
+    );
+    points
+        .iter()
+        .map(|p| Point::new((p.x - center.x) / scale, (p.y - center.y) / scale))
+        .collect()
+}
+
+/// The distance between two clouds of the same size, matching every point
+/// of one greedily with the closest unmatched point of the other from a few
+/// starting points in both directions.
+fn cloud_distance(a: &[Point], b: &[Point]) -> f32 {
+    let step = (a.len() as f32).sqrt().max(1.0) as usize;
+    (0..a.len())
+        .step_by(step)
+        .map(|start| greedy_match(a, b, start).min(greedy_match(b, a, start)))
+        .fold(f32::MAX, f32::min)
+}
+
+fn greedy_match(a: &[Point], b: &[Point], start: usize) -> f32 {
+    let n = a.len();
+    let mut matched = vec![false; b.len()];
+    let mut sum = 0.0;
+    for i in 0..n {
+        let p = a[(start + i) % n];
+        let Some((j, distance)) = b
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| !matched[*j])
+            .map(|(j, q)| (j, p.distance(*q)))
+            .min_by(|x, y| x.1.total_cmp(&y.1))
+        else {
+            break;
+        };
+        matched[j] = true;
+        // Earlier matches had more choice and count more.
+        let weight = 1.0 - i as f32 / n as f32;
+        sum += weight * distance;
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A wobbly stroke through `points` on a grid of 20 pixels, as drawn on
+    /// a pad.
+    fn pen(points: &[(f32, f32)]) -> Stroke {
+        let at = |x: f32, y: f32| Point::new(x * 20.0 + 100.0, y * 20.0 + 50.0);
+        let mut stroke = vec![at(points[0].0, points[0].1)];
+        for w in points.windows(2) {
+            let ((x0, y0), (x1, y1)) = (w[0], w[1]);
+            for i in 1..=10 {
+                let t = i as f32 / 10.0;
+                let wobble = if i % 2 == 0 { 0.5 } else { -0.5 };
+                let p = at(x0 + t * (x1 - x0), y0 + t * (y1 - y0));
+                stroke.push(Point::new(p.x + wobble, p.y));
+            }
+        }
+        stroke
+    }
+
+    fn engine() -> HandwritingEngine {
+        let mut engine =
+            HandwritingEngine::with_recognizer(TemplateRecognizer::new());
+        // Tests must not change the privacy shared by every engine.
+        engine.watch_privacy(PrivacyWatch::new(Privacy::default()));
+        engine
+    }
+
+    fn best(strokes: &[&[(f32, f32)]]) -> String {
+        let strokes: Vec<Stroke> = strokes.iter().map(|s| pen(s)).collect();
+        TemplateRecognizer::new().recognize(&strokes, 1)[0]
+            .text
+            .clone()
+    }
+
+    #[test]
+    fn templates_are_parsed() {
+        let recognizer = TemplateRecognizer::parse(
+            "# comment\n\
+             T 0,0 6,0 | 3,0 3,10\n\
+             L 0,0 0,10 6,10\n\
+             broken\n\
+             X 0,0 a,10\n\
+             \n\
+             Y 0,0 3,5 6,0 |\n",
+        );
+        assert_eq!(recognizer.len(), 2);
+        assert!(TemplateRecognizer::empty().is_empty());
+        assert!(TemplateRecognizer::new().len() >= 62);
+    }
+
+    #[test]
+    fn bundled_templates_recognize_characters() {
+        assert_eq!(
+            best(&[&[(0.0, 0.0), (10.0, 0.0)], &[(5.0, 0.0), (5.0, 15.0)]]),
+            "T"
+        );
+        // Neither stroke order nor direction matters.
+        assert_eq!(
+            best(&[&[(5.0, 15.0), (5.0, 0.0)], &[(10.0, 0.0), (0.0, 0.0)]]),
+            "T"
+        );
+        assert_eq!(best(&[&[(0.0, 0.0), (0.0, 15.0), (9.0, 15.0)]]), "L");
+        assert_eq!(
+            best(&[&[(0.0, 0.0), (9.0, 14.0)], &[(9.0, 0.0), (0.0, 14.0)]]),
+            "X"
+        );
+        assert_eq!(best(&[&[(0.0, 0.0), (10.0, 0.0), (3.0, 16.0)]]), "7");
+        assert_eq!(
+            best(&[&[
+                (0.0, 3.0),
+                (3.0, 0.0),
+                (8.0, 0.0),
+                (10.0, 3.0),
+                (10.0, 6.0),
+                (0.0, 15.0),
+                (10.0, 15.0),
+            ]]),
+            "2"
+        );
+        assert_eq!(
+            best(&[
+                &[(7.0, 0.0), (0.0, 10.0), (10.0, 10.0)],
+                &[(7.0, 4.0), (7.0, 15.0)]
+            ]),
+            "4"
+        );
+    }
+
+    #[test]
+    fn undo_and_accept() {
+        let mut engine = engine();
+        engine.add_stroke(pen(&[(5.0, 0.0), (5.0, 15.0)]));
+        engine.add_stroke(pen(&[(0.0, 0.0), (10.0, 0.0)]));
+        assert_eq!(engine.candidates()[0].text, "T");
+
+        engine.undo();
+        assert_eq!(engine.strokes().len(), 1);
+        assert_ne!(engine.candidates()[0].text, "T");
+        engine.undo();
+        assert!(engine.candidates().is_empty());
+        assert!(engine.undo().is_empty());
+
+        engine.add_stroke(pen(&[(0.0, 0.0), (0.0, 15.0), (9.0, 15.0)]));
+        let response = engine.accept(0);
+        assert!(response.consumed);
+        assert!(matches!(
+            response.actions.as_slice(),
+            [ActionInner::CommitString(text)] if text == "L"
+        ));
+        assert!(engine.strokes().is_empty());
+        assert!(!engine.accept(0).consumed);
+    }
+
+    #[test]
+    fn private_inputs_show_no_candidates() {
+        let mut engine = engine();
+        engine.set_privacy(Privacy {
+            private: true,
+            bypass_composition: false,
+        });
+        assert!(engine
+            .add_stroke(pen(&[(0.0, 0.0), (0.0, 15.0), (9.0, 15.0)]))
+            .is_empty());
+        assert!(matches!(
+            engine.accept(0).actions.as_slice(),
+            [ActionInner::CommitString(text)] if text == "L"
+        ));
+    }
+}
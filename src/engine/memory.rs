@@ -0,0 +1,387 @@
+//! Per text field engine memory.
+//!
+//! An [`EngineMemory`] remembers which engine or profile the user picked for
+//! a kind of text field and hands it back when such a field is activated
+//! again, so the chat field keeps pinyin while the terminal stays English.
+//! The input method protocol does not say which client a text input belongs
+//! to, fields are told apart by a [`Heuristic`] over the content type and,
+//! when the compositor shares its toplevels, the app id of the focused one.
+//!
+//! The event loop does not drive the memory. The app passes it every
+//! [`InputMethodEvent`] and, to tell apps apart, every
+//! [`ForeignToplevelEvent`], in the order they arrive. On the `Done`
+//! completing an activation [`EngineMemory::handle_event`] returns the
+//! remembered choice, which the app applies to its engines itself.
+//!
+//! Choices live in `$XDG_DATA_HOME/iced_wayland_input_method/memory`, one
+//! per line, the field key and the choice separated by a tab. Content
+//! purposes are written as the lowercase names of the protocol, hints as
+//! their hexadecimal bits:
+//!
+//! ```text
+//! app:org.gnome.Console	en
+//! type:normal/0	pinyin
+//! ```
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufRead, Write},
+    path::PathBuf,
+};
+
+use iced_futures::core::event::wayland::{
+    ForeignToplevelEvent, InputMethodEvent,
+};
+use sctk::reexports::protocols::wp::text_input::zv3::client::zwp_text_input_v3::{
+    ContentHint, ContentPurpose,
+};
+
+/// The names of content purposes in keys, stable across protocol crate
+/// versions.
+const PURPOSES: [(ContentPurpose, &str); 14] = [
+    (ContentPurpose::Normal, "normal"),
+    (ContentPurpose::Alpha, "alpha"),
+    (ContentPurpose::Digits, "digits"),
+    (ContentPurpose::Number, "number"),
+    (ContentPurpose::Phone, "phone"),
+    (ContentPurpose::Url, "url"),
+    (ContentPurpose::Email, "email"),
+    (ContentPurpose::Name, "name"),
+    (ContentPurpose::Password, "password"),
+    (ContentPurpose::Pin, "pin"),
+    (ContentPurpose::Date, "date"),
+    (ContentPurpose::Time, "time"),
+    (ContentPurpose::Datetime, "datetime"),
+    (ContentPurpose::Terminal, "terminal"),
+];
+
+/// How text fields are told apart.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Heuristic {
+    /// By content purpose and hint.
+    #[default]
+    ContentType,
+    /// By the app id of the focused toplevel, falling back to the content
+    /// type while it is unknown.
+    AppId,
+    /// By app id and content type together, e.g. the URL bar and the page of
+    /// a browser are different fields.
+    AppIdAndContentType,
+}
+
+/// Engine choices remembered per text field.
+#[derive(Debug, Clone)]
+pub struct EngineMemory {
+    path: Option<PathBuf>,
+    heuristic: Heuristic,
+    choices: HashMap<String, String>,
+    app_id: Option<String>,
+    content_type: (ContentHint, ContentPurpose),
+    activating: bool,
+    dirty: bool,
+}
+
+impl EngineMemory {
+    /// Open the memory in the user data directory. A missing or unreadable
+    /// file is an empty memory.
+    pub fn open(heuristic: Heuristic) -> Self {
+        let path = super::data_dir().map(|dir| dir.join("memory"));
+        let mut memory = Self {
+            path,
+            ..Self::in_memory(heuristic)
+        };
+        if let Some(path) = memory.path.as_ref() {
+            match fs::File::open(path) {
+                Ok(file) => {
+                    if let Err(err) = memory.import(io::BufReader::new(file)) {
+                        log::warn!(
+                            "Failed to read engine memory {}: {}",
+                            path.display(),
+                            err
+                        );
+                    }
+                    memory.dirty = false;
+                }
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => log::warn!(
+                    "Failed to open engine memory {}: {}",
+                    path.display(),
+                    err
+                ),
+            }
+        }
+        memory
+    }
+
+    /// A memory which is never written to disk.
+    pub fn in_memory(heuristic: Heuristic) -> Self {
+        Self {
+            path: None,
+            heuristic,
+            choices: HashMap::new(),
+            app_id: None,
+            content_type: (ContentHint::None, ContentPurpose::Normal),
+            activating: false,
+            dirty: false,
+        }
+    }
+
+    /// Change how text fields are told apart. Choices made under another
+    /// heuristic are kept but may not match anymore.
+    pub fn set_heuristic(&mut self, heuristic: Heuristic) {
+        self.heuristic = heuristic;
+    }
+
+    /// Set the app id of the focused toplevel, `None` when it is unknown.
+    pub fn set_app_id(&mut self, app_id: Option<String>) {
+        self.app_id = app_id.filter(|id| !id.is_empty());
+    }
+
+    /// Follow the activated toplevel, for compositors reporting it.
+    pub fn handle_toplevel_event(&mut self, event: &ForeignToplevelEvent) {
+        match event {
+            ForeignToplevelEvent::Opened(toplevel)
+            | ForeignToplevelEvent::Changed(toplevel)
+                if toplevel.activated =>
+            {
+                self.set_app_id(Some(toplevel.app_id.clone()));
+            }
+            ForeignToplevelEvent::Closed(toplevel) if toplevel.activated => {
+                self.set_app_id(None);
+            }
+            ForeignToplevelEvent::List(toplevels) => {
+                if let Some(toplevel) = toplevels.iter().find(|t| t.activated) {
+                    self.set_app_id(Some(toplevel.app_id.clone()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Track the text input. Returns the choice remembered for the field
+    /// on the `Done` completing an activation.
+    pub fn handle_event(&mut self, event: &InputMethodEvent) -> Option<&str> {
+        match event {
+            InputMethodEvent::Activate => {
+                self.activating = true;
+                self.content_type = (ContentHint::None, ContentPurpose::Normal);
+            }
+            InputMethodEvent::Deactivate => self.activating = false,
+            InputMethodEvent::ContentType(hint, purpose) => {
+                self.content_type = (
+                    hint.into_result().unwrap_or(ContentHint::None),
+                    purpose.into_result().unwrap_or(ContentPurpose::Normal),
+                );
+            }
+            InputMethodEvent::Done if self.activating => {
+                self.activating = false;
+                return self.recall();
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// The key of the active text field.
+    pub fn key(&self) -> String {
+        let (hint, purpose) = self.content_type;
+        let purpose = PURPOSES
+            .iter()
+            .find(|(p, _)| *p == purpose)
+            .map_or("normal", |(_, name)| name);
+        let content_type = format!("type:{purpose}/{:x}", hint.bits());
+        match (self.heuristic, self.app_id.as_ref()) {
+            (Heuristic::ContentType, _) | (_, None) => content_type,
+            (Heuristic::AppId, Some(app_id)) => format!("app:{app_id}"),
+            (Heuristic::AppIdAndContentType, Some(app_id)) => {
+                format!("app:{app_id} {content_type}")
+            }
+        }
+    }
+
+    /// The choice remembered for the active text field.
+    pub fn recall(&self) -> Option<&str> {
+        self.choices.get(&self.key()).map(String::as_str)
+    }
+
+    /// Remember `choice` for the active text field.
+    pub fn remember(&mut self, choice: &str) {
+        let choice = choice.trim();
+        if choice.is_empty() || choice.contains(['\t', '\n']) {
+            return;
+        }
+        let key = self.key();
+        if self.choices.get(&key).map(String::as_str) != Some(choice) {
+            self.choices.insert(key, choice.to_string());
+            self.dirty = true;
+        }
+    }
+
+    /// Forget the choice for the active text field, returns whether there
+    /// was one.
+    pub fn forget(&mut self) -> bool {
+        let removed = self.choices.remove(&self.key()).is_some();
+        self.dirty |= removed;
+        removed
+    }
+
+    /// Forget every choice.
+    pub fn clear(&mut self) {
+        self.dirty |= !self.choices.is_empty();
+        self.choices.clear();
+    }
+
+    /// Merge choices in the text format, replacing existing ones. Malformed
+    /// lines are skipped.
+    pub fn import(&mut self, reader: impl BufRead) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            let Some((key, choice)) = line.split_once('\t') else {
+                continue;
+            };
+            let choice = choice.trim();
+            if !key.is_empty() && !choice.is_empty() {
+                self.choices.insert(key.to_string(), choice.to_string());
+                self.dirty = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write all choices in the text format, sorted by key.
+    pub fn export(&self, mut writer: impl Write) -> io::Result<()> {
+        let mut choices: Vec<_> = self.choices.iter().collect();
+        choices.sort();
+        for (key, choice) in choices {
+            writeln!(writer, "{key}\t{choice}")?;
+        }
+        Ok(())
+    }
+
+    /// Write the memory to disk if it changed.
+    pub fn save(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+        let mut contents = Vec::new();
+        self.export(&mut contents)?;
+        super::write_atomic(path, &contents)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sctk::reexports::client::WEnum;
+
+    use super::*;
+
+    fn content_type(
+        hint: ContentHint,
+        purpose: ContentPurpose,
+    ) -> InputMethodEvent {
+        InputMethodEvent::ContentType(WEnum::Value(hint), WEnum::Value(purpose))
+    }
+
+    /// Activate a text field with the given content type, returns the
+    /// recalled choice.
+    fn activate(
+        memory: &mut EngineMemory,
+        hint: ContentHint,
+        purpose: ContentPurpose,
+    ) -> Option<String> {
+        assert_eq!(memory.handle_event(&InputMethodEvent::Activate), None);
+        assert_eq!(memory.handle_event(&content_type(hint, purpose)), None);
+        memory
+            .handle_event(&InputMethodEvent::Done)
+            .map(str::to_string)
+    }
+
+    #[test]
+    fn keys_follow_the_heuristic() {
+        let mut memory = EngineMemory::in_memory(Heuristic::ContentType);
+        activate(&mut memory, ContentHint::Spellcheck, ContentPurpose::Email);
+        assert_eq!(memory.key(), "type:email/2");
+
+        // Without an app id every heuristic falls back to the content type.
+        memory.set_heuristic(Heuristic::AppId);
+        assert_eq!(memory.key(), "type:email/2");
+        memory.set_app_id(Some("org.gnome.Console".to_string()));
+        assert_eq!(memory.key(), "app:org.gnome.Console");
+        memory.set_heuristic(Heuristic::AppIdAndContentType);
+        assert_eq!(memory.key(), "app:org.gnome.Console type:email/2");
+        memory.set_heuristic(Heuristic::ContentType);
+        assert_eq!(memory.key(), "type:email/2");
+
+        memory.set_app_id(Some(String::new()));
+        memory.set_heuristic(Heuristic::AppId);
+        assert_eq!(memory.key(), "type:email/2");
+    }
+
+    #[test]
+    fn choice_is_recalled_on_activation() {
+        let mut memory = EngineMemory::in_memory(Heuristic::ContentType);
+        activate(&mut memory, ContentHint::None, ContentPurpose::Terminal);
+        memory.remember("en");
+        activate(&mut memory, ContentHint::None, ContentPurpose::Normal);
+        memory.remember(" pinyin ");
+
+        assert_eq!(
+            activate(&mut memory, ContentHint::None, ContentPurpose::Terminal)
+                .as_deref(),
+            Some("en")
+        );
+        // Only the `Done` completing an activation recalls.
+        assert_eq!(memory.handle_event(&InputMethodEvent::Done), None);
+        assert_eq!(
+            activate(&mut memory, ContentHint::None, ContentPurpose::Normal)
+                .as_deref(),
+            Some("pinyin")
+        );
+        assert_eq!(
+            activate(&mut memory, ContentHint::None, ContentPurpose::Url),
+            None
+        );
+    }
+
+    #[test]
+    fn forgotten_choice_is_not_recalled() {
+        let mut memory = EngineMemory::in_memory(Heuristic::ContentType);
+        memory.remember("pinyin");
+        memory.remember("bad\tchoice");
+        assert_eq!(memory.recall(), Some("pinyin"));
+        assert!(memory.forget());
+        assert!(!memory.forget());
+        assert_eq!(memory.recall(), None);
+    }
+
+    #[test]
+    fn export_and_import_round_trip() {
+        let mut memory = EngineMemory::in_memory(Heuristic::AppId);
+        memory.remember("pinyin");
+        memory.set_app_id(Some("org.gnome.Console".to_string()));
+        memory.remember("en");
+
+        let mut text = Vec::new();
+        memory.export(&mut text).unwrap();
+        assert_eq!(
+            String::from_utf8(text.clone()).unwrap(),
+            "app:org.gnome.Console\ten\ntype:normal/0\tpinyin\n"
+        );
+
+        let mut imported = EngineMemory::in_memory(Heuristic::AppId);
+        imported
+            .import(&[&text[..], b"broken\n\tempty key\n"].concat()[..])
+            .unwrap();
+        assert_eq!(imported.recall(), Some("pinyin"));
+        imported.set_app_id(Some("org.gnome.Console".to_string()));
+        assert_eq!(imported.recall(), Some("en"));
+        let mut exported = Vec::new();
+        imported.export(&mut exported).unwrap();
+        assert_eq!(exported, text);
+    }
+}
@@ -0,0 +1,219 @@
+//! Input method engines.
+//!
+//! An engine turns key presses and text input state into the requests an
+//! input method sends to the focused text input. Engines do not talk to the
+//! compositor themselves, they hand back [`ActionInner`]s that can be turned
+//! into a [`Command`] with [`command`].
+pub mod clipboard;
+pub mod emoji;
+pub mod handwriting;
+pub mod memory;
+pub mod prediction;
+pub mod snippets;
+pub mod store;
+pub mod swipe;
+
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use iced_futures::core::event::wayland::Privacy;
+use iced_runtime::command::platform_specific::wayland::input_method::ActionInner;
+use iced_runtime::Command;
+
+use crate::commands::input_method::input_method_action;
+
+/// A candidate offered by an engine, usually shown in the input method popup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    /// The text committed when the candidate is chosen.
+    pub text: String,
+    /// A short description shown next to the text.
+    pub label: String,
+}
+
+/// The outcome of feeding a key to an engine.
+#[derive(Debug, Default)]
+pub struct Response {
+    /// Requests for the text input, in the order they should be sent.
+    pub actions: Vec<ActionInner>,
+    /// Whether the engine consumed the key. Keys which are not consumed
+    /// should be forwarded to the client, e.g. through the virtual keyboard.
+    pub consumed: bool,
+}
+
+impl Response {
+    /// The engine did not handle the key.
+    pub fn ignored() -> Self {
+        Self::default()
+    }
+
+    /// The engine handled the key and wants the given requests sent.
+    pub fn consumed(actions: Vec<ActionInner>) -> Self {
+        Self {
+            actions,
+            consumed: true,
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    /// The watch engines and event loops share unless given another one.
+    static ref SHARED_PRIVACY: PrivacyWatch =
+        PrivacyWatch::new(Privacy::default());
+}
+
+/// The privacy of the active text input, kept current by the event loop.
+///
+/// Clones share the state. The event loop updates the watch in
+/// [`PrivacySettings::watch`] whenever the privacy changes. By default that
+/// is the same watch every engine starts with, so engines follow the active
+/// text input without further setup. Apps running several event loops give
+/// each its own watch and hand clones to its engines through their
+/// `watch_privacy`.
+///
+/// The `set_privacy` of an engine writes into the watch it follows, so it
+/// applies to every engine sharing that watch.
+///
+/// The watch also counts changes of the surrounding text made outside the
+/// input method. Composing engines drop their state when the count moves,
+/// the event loop already cleared the preedit by then.
+///
+/// [`PrivacySettings::watch`]: crate::settings::PrivacySettings::watch
+#[derive(Debug, Clone)]
+pub struct PrivacyWatch(Arc<Mutex<Watched>>);
+
+#[derive(Debug, Default)]
+struct Watched {
+    privacy: Privacy,
+    external_changes: u64,
+}
+
+impl Default for PrivacyWatch {
+    /// The watch shared with the event loops using the default
+    /// [`PrivacySettings`](crate::settings::PrivacySettings).
+    fn default() -> Self {
+        SHARED_PRIVACY.clone()
+    }
+}
+
+impl PrivacyWatch {
+    /// A watch holding `privacy`, not shared with any event loop.
+    pub fn new(privacy: Privacy) -> Self {
+        Self(Arc::new(Mutex::new(Watched {
+            privacy,
+            external_changes: 0,
+        })))
+    }
+
+    /// The current privacy.
+    pub fn get(&self) -> Privacy {
+        self.0.lock().unwrap().privacy
+    }
+
+    /// Replace the privacy for everyone sharing the watch, until the event
+    /// loop reports the next change.
+    pub fn set(&self, privacy: Privacy) {
+        self.0.lock().unwrap().privacy = privacy;
+    }
+
+    /// How many external changes of the surrounding text were seen.
+    pub fn external_changes(&self) -> u64 {
+        self.0.lock().unwrap().external_changes
+    }
+
+    pub(crate) fn external_change(&self) {
+        let mut watched = self.0.lock().unwrap();
+        watched.external_changes = watched.external_changes.wrapping_add(1);
+    }
+}
+
+impl PartialEq for PrivacyWatch {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for PrivacyWatch {}
+
+/// Batch input method requests into a single [`Command`].
+///
+/// The requests still have to be applied with a commit carrying the serial of
+/// the last `Done` event.
+pub fn command<Message>(actions: Vec<ActionInner>) -> Command<Message> {
+    Command::batch(actions.into_iter().map(input_method_action))
+}
+
+/// The directory engines keep their data in,
+/// `$XDG_DATA_HOME/iced_wayland_input_method` or its `$HOME` fallback.
+pub(crate) fn data_dir() -> Option<PathBuf> {
+    env::var_os("XDG_DATA_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME")
+                .filter(|v| !v.is_empty())
+                .map(|home| PathBuf::from(home).join(".local/share"))
+        })
+        .map(|dir| dir.join(env!("CARGO_PKG_NAME")))
+}
+
+/// The directory engines read their configuration from,
+/// `$XDG_CONFIG_HOME/iced_wayland_input_method` or its `$HOME` fallback.
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME")
+                .filter(|v| !v.is_empty())
+                .map(|home| PathBuf::from(home).join(".config"))
+        })
+        .map(|dir| dir.join(env!("CARGO_PKG_NAME")))
+}
+
+/// Write `contents` next to `path` and rename it into place, so a crash never
+/// leaves a truncated file behind.
+///
+/// The temporary file is named after the process and a counter, so
+/// concurrent writers never share it.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let parent = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(parent)?;
+    let Some(name) = path.file_name() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "path has no file name",
+        ));
+    };
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(name);
+    tmp_name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp = parent.join(tmp_name);
+
+    let result = fs::File::create(&tmp)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            // The data must be on disk before the rename is.
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
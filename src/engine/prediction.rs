@@ -0,0 +1,527 @@
+//! Word completion and next-word prediction.
+//!
+//! Predictions come from an n-gram model file and a user history which
+//! learns from accepted and committed text. The context is taken from the
+//! `SurroundingText` event: the word under the cursor is completed, after a
+//! space the next word is predicted.
+//!
+//! Model and history files hold one n-gram per line, a count followed by one
+//! or two words:
+//!
+//! ```text
+//! # count word [next word]
+//! 120 the
+//! 14 of the
+//! ```
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use iced_futures::core::event::wayland::{KeyEvent, Privacy, Profile};
+use iced_runtime::command::platform_specific::wayland::input_method::ActionInner;
+use sctk::seat::keyboard::Keysym;
+
+use super::{Candidate, PrivacyWatch, Response};
+
+/// Unigram and bigram counts.
+#[derive(Debug, Clone, Default)]
+pub struct NgramModel {
+    unigrams: HashMap<String, u32>,
+    bigrams: HashMap<String, HashMap<String, u32>>,
+}
+
+impl NgramModel {
+    /// Load a model file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// Parse the model format, malformed lines are skipped.
+    pub fn parse(s: &str) -> Self {
+        let mut model = Self::default();
+        for line in s.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let Some(count) = parts.next().and_then(|c| c.parse().ok()) else {
+                continue;
+            };
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(word), None, _) => model.add_unigram(word, count),
+                (Some(first), Some(second), None) => {
+                    model.add_bigram(first, second, count)
+                }
+                _ => {}
+            }
+        }
+        model
+    }
+
+    /// Serialize the model in the format read by [`NgramModel::parse`].
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let mut unigrams: Vec<_> = self.unigrams.iter().collect();
+        unigrams.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for (word, count) in unigrams {
+            out.push_str(&format!("{count} {word}\n"));
+        }
+        let mut bigrams: Vec<_> = self
+            .bigrams
+            .iter()
+            .flat_map(|(first, next)| {
+                next.iter()
+                    .map(move |(second, count)| (first, second, count))
+            })
+            .collect();
+        bigrams.sort_by(|a, b| b.2.cmp(a.2).then((a.0, a.1).cmp(&(b.0, b.1))));
+        for (first, second, count) in bigrams {
+            out.push_str(&format!("{count} {first} {second}\n"));
+        }
+        out
+    }
+
+    /// Count every word and word pair in `text`.
+    pub fn learn(&mut self, text: &str) {
+        let mut previous: Option<String> = None;
+        for word in words(text) {
+            let word = word.to_lowercase();
+            self.add_unigram(&word, 1);
+            if let Some(previous) = previous.as_ref() {
+                self.add_bigram(previous, &word, 1);
+            }
+            previous = Some(word);
+        }
+    }
+
+    /// Every word and how often it occurs.
+    pub fn words(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.unigrams.iter().map(|(w, c)| (w.as_str(), *c))
+    }
+
+    /// Whether the model has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.unigrams.is_empty()
+    }
+
+    fn add_unigram(&mut self, word: &str, count: u32) {
+        let entry = self.unigrams.entry(word.to_lowercase()).or_default();
+        *entry = entry.saturating_add(count);
+    }
+
+    fn add_bigram(&mut self, first: &str, second: &str, count: u32) {
+        let entry = self
+            .bigrams
+            .entry(first.to_lowercase())
+            .or_default()
+            .entry(second.to_lowercase())
+            .or_default();
+        *entry = entry.saturating_add(count);
+    }
+
+    fn unigram(&self, word: &str) -> u32 {
+        self.unigrams.get(word).copied().unwrap_or_default()
+    }
+
+    fn bigram(&self, first: &str, second: &str) -> u32 {
+        self.bigrams
+            .get(first)
+            .and_then(|next| next.get(second))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// The words of `text`, letters, digits and apostrophes.
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !is_word_char(c))
+        .filter(|w| !w.is_empty())
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '\''
+}
+
+/// The text before the cursor, split into the word being typed and the one
+/// before it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Context {
+    previous: Option<String>,
+    partial: String,
+}
+
+impl Context {
+    fn new(before_cursor: &str) -> Self {
+        let start = before_cursor
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_word_char(*c))
+            .last()
+            .map_or(before_cursor.len(), |(i, _)| i);
+        let (head, partial) = before_cursor.split_at(start);
+        // Only predict across spaces, not sentence or line breaks.
+        let previous = match head.trim_end_matches(' ').chars().last() {
+            Some(c) if is_word_char(c) => {
+                words(head).last().map(str::to_lowercase)
+            }
+            _ => None,
+        };
+        Self {
+            previous,
+            partial: partial.to_string(),
+        }
+    }
+}
+
+/// Word completion and next-word prediction.
+#[derive(Debug, Clone)]
+pub struct PredictionEngine {
+    model: NgramModel,
+    history: NgramModel,
+    history_path: Option<PathBuf>,
+    context: Context,
+    candidates: Vec<Candidate>,
+    max_candidates: usize,
+    privacy: PrivacyWatch,
+    profile: Profile,
+}
+
+impl Default for PredictionEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PredictionEngine {
+    /// An engine with the model and history from the user data directory,
+    /// `prediction/ngrams` and `prediction/history`.
+    pub fn new() -> Self {
+        let dir = super::data_dir().map(|dir| dir.join("prediction"));
+        let model = dir
+            .as_ref()
+            .and_then(|dir| NgramModel::load(dir.join("ngrams")).ok())
+            .unwrap_or_default();
+        let history_path = dir.map(|dir| dir.join("history"));
+        let history = history_path
+            .as_ref()
+            .and_then(|path| NgramModel::load(path).ok())
+            .unwrap_or_default();
+        Self::with_model(model, history, history_path)
+    }
+
+    /// An engine with the given model and history. The history is saved to
+    /// `history_path` by [`PredictionEngine::save_history`].
+    pub fn with_model(
+        model: NgramModel,
+        history: NgramModel,
+        history_path: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            model,
+            history,
+            history_path,
+            context: Context::default(),
+            candidates: Vec::new(),
+            max_candidates: 3,
+            privacy: PrivacyWatch::default(),
+            profile: Profile::default(),
+        }
+    }
+
+    /// Replace the model with the one in `path`.
+    pub fn load_model(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.model = NgramModel::load(path)?;
+        self.refresh();
+        Ok(())
+    }
+
+    /// Set how many candidates are offered.
+    pub fn set_max_candidates(&mut self, max_candidates: usize) {
+        self.max_candidates = max_candidates.max(1);
+        self.refresh();
+    }
+
+    /// Apply the privacy of the text input. Private inputs get no
+    /// predictions and are not learned from.
+    pub fn set_privacy(&mut self, privacy: Privacy) {
+        self.privacy.set(privacy);
+        self.refresh();
+    }
+
+    /// Follow `watch` instead of the watch shared with the default event
+    /// loop settings.
+    pub fn watch_privacy(&mut self, watch: PrivacyWatch) {
+        self.privacy = watch;
+    }
+
+    fn is_private(&self) -> bool {
+        self.privacy.get().private
+    }
+
+    /// Apply the profile of the text input. Only profiles which allow
+    /// auto-correction get predictions.
+    pub fn set_profile(&mut self, profile: Profile) {
+        self.profile = profile;
+        self.refresh();
+    }
+
+    /// Update the context from a `SurroundingText` event. `cursor` is a byte
+    /// offset into `text`.
+    pub fn surrounding_text(&mut self, text: &str, cursor: u32) {
+        let cursor = (cursor as usize).min(text.len());
+        let before = text.get(..cursor).unwrap_or_default();
+        self.context = Context::new(before);
+        self.refresh();
+    }
+
+    /// The current candidates, best first.
+    pub fn candidates(&self) -> &[Candidate] {
+        if self.is_private() {
+            return &[];
+        }
+        &self.candidates
+    }
+
+    /// Feed a key press to the engine, `Tab` accepts the first candidate.
+    pub fn handle_key(&mut self, key: &KeyEvent) -> Response {
+        if self.is_private() {
+            self.candidates.clear();
+            return Response::ignored();
+        }
+        match key.keysym {
+            Keysym::Tab if !self.candidates.is_empty() => self.accept(0),
+            _ => Response::ignored(),
+        }
+    }
+
+    /// Replace the word being typed with the candidate at `index`.
+    pub fn accept(&mut self, index: usize) -> Response {
+        // The watch may have turned private since the last refresh.
+        if self.is_private() {
+            self.candidates.clear();
+            return Response::ignored();
+        }
+        let Some(candidate) = self.candidates.get(index).cloned() else {
+            return Response::ignored();
+        };
+        let mut actions = Vec::with_capacity(2);
+        if !self.context.partial.is_empty() {
+            actions.push(ActionInner::DeleteSurroundingText {
+                before_length: self.context.partial.len() as u32,
+                after_length: 0,
+            });
+        }
+        actions.push(ActionInner::CommitString(format!("{} ", candidate.text)));
+
+        match self.context.previous.as_ref() {
+            Some(previous) => {
+                let text = format!("{previous} {}", candidate.text);
+                self.learn(&text);
+            }
+            None => self.learn(&candidate.text),
+        }
+        self.context = Context {
+            previous: Some(candidate.text.to_lowercase()),
+            partial: String::new(),
+        };
+        self.refresh();
+        Response::consumed(actions)
+    }
+
+    /// Learn from text committed to the client.
+    pub fn learn(&mut self, text: &str) {
+        if !self.is_private() {
+            self.history.learn(text);
+        }
+    }
+
+    /// Write the user history to disk.
+    pub fn save_history(&self) -> io::Result<()> {
+        match self.history_path.as_ref() {
+            Some(path) => {
+                super::write_atomic(path, self.history.to_text().as_bytes())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Forget the context, e.g. on `Deactivate`.
+    pub fn reset(&mut self) {
+        self.context = Context::default();
+        self.candidates.clear();
+    }
+
+    fn score(&self, word: &str) -> u64 {
+        // History counts are worth more than the model, they are the user's
+        // own words.
+        let unigram = self.model.unigram(word) as u64
+            + 4 * self.history.unigram(word) as u64;
+        let bigram = self.context.previous.as_ref().map_or(0, |previous| {
+            self.model.bigram(previous, word) as u64
+                + 4 * self.history.bigram(previous, word) as u64
+        });
+        unigram + 16 * bigram
+    }
+
+    fn refresh(&mut self) {
+        if self.is_private() || !self.profile.auto_correct() {
+            self.candidates.clear();
+            return;
+        }
+        let partial = self.context.partial.to_lowercase();
+        let mut words: Vec<&String> = if partial.is_empty() {
+            let Some(previous) = self.context.previous.as_ref() else {
+                self.candidates.clear();
+                return;
+            };
+            self.model
+                .bigrams
+                .get(previous)
+                .into_iter()
+                .chain(self.history.bigrams.get(previous))
+                .flat_map(|next| next.keys())
+                .collect()
+        } else {
+            self.model
+                .unigrams
+                .keys()
+                .chain(self.history.unigrams.keys())
+                .filter(|w| w.starts_with(&partial) && **w != partial)
+                .collect()
+        };
+        words.sort();
+        words.dedup();
+
+        let mut scored: Vec<_> =
+            words.into_iter().map(|w| (self.score(w), w)).collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(b.1)));
+
+        let capitalize = self
+            .context
+            .partial
+            .chars()
+            .next()
+            .is_some_and(char::is_uppercase);
+        self.candidates = scored
+            .into_iter()
+            .take(self.max_candidates)
+            .map(|(_, word)| Candidate {
+                text: if capitalize {
+                    capitalized(word)
+                } else {
+                    word.clone()
+                },
+                label: String::new(),
+            })
+            .collect();
+    }
+}
+
+fn capitalized(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine() -> PredictionEngine {
+        let model = NgramModel::parse(
+            "120 the\n40 there\n30 then\n14 of the\n9 of course\n",
+        );
+        let mut engine =
+            PredictionEngine::with_model(model, NgramModel::default(), None);
+        // Tests must not change the privacy shared by every engine.
+        engine.watch_privacy(PrivacyWatch::new(Privacy::default()));
+        engine
+    }
+
+    fn texts(engine: &PredictionEngine) -> Vec<&str> {
+        engine
+            .candidates()
+            .iter()
+            .map(|c| c.text.as_str())
+            .collect()
+    }
+
+    fn tab() -> KeyEvent {
+        KeyEvent {
+            time: 0,
+            raw_code: 15,
+            keysym: Keysym::Tab,
+            utf8: None,
+        }
+    }
+
+    #[test]
+    fn completes_the_word_under_the_cursor() {
+        let mut engine = engine();
+        engine.surrounding_text("go th", 5);
+        assert_eq!(texts(&engine), ["the", "there", "then"]);
+
+        engine.surrounding_text("Th", 2);
+        assert_eq!(texts(&engine), ["The", "There", "Then"]);
+    }
+
+    #[test]
+    fn predicts_the_next_word_after_a_space() {
+        let mut engine = engine();
+        engine.surrounding_text("one of ", 7);
+        assert_eq!(texts(&engine), ["the", "course"]);
+
+        // Not across sentences.
+        engine.surrounding_text("one of. ", 8);
+        assert!(texts(&engine).is_empty());
+    }
+
+    #[test]
+    fn tab_replaces_the_partial_word() {
+        let mut engine = engine();
+        engine.surrounding_text("déjà th", 9);
+        let response = engine.handle_key(&tab());
+
+        assert!(response.consumed);
+        assert!(matches!(
+            response.actions.as_slice(),
+            [
+                ActionInner::DeleteSurroundingText {
+                    before_length: 2,
+                    after_length: 0,
+                },
+                ActionInner::CommitString(text),
+            ] if text == "the "
+        ));
+    }
+
+    #[test]
+    fn private_inputs_get_no_predictions() {
+        let mut engine = engine();
+        let watch = PrivacyWatch::new(Privacy::default());
+        engine.watch_privacy(watch.clone());
+        engine.surrounding_text("go th", 5);
+        assert!(!texts(&engine).is_empty());
+
+        // Turned private through the watch, not through the engine.
+        watch.set(Privacy {
+            private: true,
+            bypass_composition: false,
+        });
+        assert!(texts(&engine).is_empty());
+        let response = engine.handle_key(&tab());
+        assert!(!response.consumed && response.actions.is_empty());
+        assert!(!engine.accept(0).consumed);
+        engine.learn("secret");
+
+        watch.set(Privacy::default());
+        assert!(texts(&engine).is_empty());
+        engine.surrounding_text("sec", 3);
+        assert!(texts(&engine).is_empty());
+    }
+}
@@ -0,0 +1,436 @@
+//! Text expansion.
+//!
+//! Snippets map an abbreviation to a text. When the abbreviation is in front
+//! of the cursor, starting a word, and `space` or `Tab` is pressed, it is
+//! deleted and the expansion committed in its place.
+//!
+//! Snippets are read from `snippets.toml` in the configuration directory.
+//! There is no file watcher, the file is re-read on the next `space` or `Tab`
+//! after it was modified, so edits apply from the next expansion on:
+//!
+//! ```toml
+//! # Optional, the format of `{date}`.
+//! date_format = "%d.%m.%Y"
+//!
+//! [snippets]
+//! ";addr" = "Jane Doe\n1 Main Street\nSpringfield"
+//! ";today" = "{date}"
+//! ";quote" = "> {clipboard}"
+//! ```
+//!
+//! Expansions may contain the placeholders `{date}`, `{date:<format>}`,
+//! `{time}` and `{clipboard}`. `{{` and `}}` are literal braces.
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use iced_futures::core::event::wayland::{KeyEvent, Privacy};
+use iced_runtime::command::platform_specific::wayland::input_method::ActionInner;
+use sctk::seat::keyboard::Keysym;
+use serde::Deserialize;
+
+use super::{PrivacyWatch, Response};
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_TIME_FORMAT: &str = "%H:%M";
+
+/// An error while loading a snippets file.
+#[derive(Debug, thiserror::Error)]
+pub enum SnippetError {
+    /// The file could not be read.
+    #[error("failed to read snippets: {0}")]
+    Io(#[from] io::Error),
+    /// The file is not valid.
+    #[error("invalid snippets file: {0}")]
+    Parse(#[from] toml::de::Error),
+}
+
+/// The contents of a snippets file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Snippets {
+    /// The `strftime` format of `{date}`.
+    pub date_format: Option<String>,
+    /// Expansions by abbreviation.
+    pub snippets: HashMap<String, String>,
+}
+
+impl Snippets {
+    /// Parse a snippets file.
+    pub fn parse(s: &str) -> Result<Self, SnippetError> {
+        Ok(toml::from_str(s)?)
+    }
+
+    /// Replace the placeholders in `template`.
+    pub fn expand(&self, template: &str, clipboard: Option<&str>) -> String {
+        let now = chrono::Local::now();
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(i) = rest.find(['{', '}']) {
+            out.push_str(&rest[..i]);
+            rest = &rest[i..];
+            if rest.starts_with("{{") || rest.starts_with("}}") {
+                out.push_str(&rest[..1]);
+                rest = &rest[2..];
+                continue;
+            }
+            let end = match rest.starts_with('{').then(|| rest.find('}')) {
+                Some(Some(end)) => end,
+                // A stray brace is kept as is.
+                _ => {
+                    out.push_str(&rest[..1]);
+                    rest = &rest[1..];
+                    continue;
+                }
+            };
+            let placeholder = &rest[1..end];
+            match placeholder.split_once(':') {
+                Some(("date", format)) => format_time(&mut out, &now, format),
+                None if placeholder == "date" => format_time(
+                    &mut out,
+                    &now,
+                    self.date_format.as_deref().unwrap_or(DEFAULT_DATE_FORMAT),
+                ),
+                None if placeholder == "time" => {
+                    format_time(&mut out, &now, DEFAULT_TIME_FORMAT)
+                }
+                None if placeholder == "clipboard" => {
+                    out.push_str(clipboard.unwrap_or_default())
+                }
+                _ => out.push_str(&rest[..=end]),
+            }
+            rest = &rest[end + 1..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+/// Expands abbreviations in front of the cursor.
+#[derive(Debug, Clone)]
+pub struct SnippetEngine {
+    path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    snippets: Snippets,
+    before_cursor: String,
+    clipboard: Option<String>,
+    privacy: PrivacyWatch,
+}
+
+impl Default for SnippetEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SnippetEngine {
+    /// An engine with `snippets.toml` from the configuration directory.
+    pub fn new() -> Self {
+        match super::config_dir() {
+            Some(dir) => Self::with_path(dir.join("snippets.toml")),
+            None => Self::with_snippets(Snippets::default()),
+        }
+    }
+
+    /// An engine reading snippets from `path`, which need not exist yet.
+    pub fn with_path(path: impl Into<PathBuf>) -> Self {
+        let mut engine = Self::with_snippets(Snippets::default());
+        engine.path = Some(path.into());
+        engine.reload_if_changed();
+        engine
+    }
+
+    /// An engine with fixed snippets.
+    pub fn with_snippets(snippets: Snippets) -> Self {
+        Self {
+            path: None,
+            modified: None,
+            snippets,
+            before_cursor: String::new(),
+            clipboard: None,
+            privacy: PrivacyWatch::default(),
+        }
+    }
+
+    /// The loaded snippets.
+    pub fn snippets(&self) -> &Snippets {
+        &self.snippets
+    }
+
+    /// Read the snippets file again.
+    pub fn reload(&mut self) -> Result<(), SnippetError> {
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+        self.modified = modified(&path);
+        self.snippets = Snippets::parse(&fs::read_to_string(path)?)?;
+        Ok(())
+    }
+
+    /// Read the snippets file again if it changed since it was last read. A
+    /// file which fails to load keeps the previous snippets.
+    pub fn reload_if_changed(&mut self) {
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        let modified = modified(&path);
+        if modified.is_none() || modified == self.modified {
+            return;
+        }
+        if let Err(err) = self.reload() {
+            log::warn!("Failed to load {}: {}", path.display(), err);
+        }
+    }
+
+    /// Set the text `{clipboard}` expands to, e.g. from [`crate::clipboard::read`].
+    pub fn set_clipboard_text(&mut self, text: Option<String>) {
+        self.clipboard = text;
+    }
+
+    /// Apply the privacy of the text input. `{clipboard}` expands to nothing
+    /// in private inputs.
+    pub fn set_privacy(&mut self, privacy: Privacy) {
+        self.privacy.set(privacy);
+    }
+
+    /// Follow `watch` instead of the watch shared with the default event
+    /// loop settings.
+    pub fn watch_privacy(&mut self, watch: PrivacyWatch) {
+        self.privacy = watch;
+    }
+
+    fn is_private(&self) -> bool {
+        self.privacy.get().private
+    }
+
+    /// Update the context from a `SurroundingText` event. `cursor` is a byte
+    /// offset into `text`.
+    pub fn surrounding_text(&mut self, text: &str, cursor: u32) {
+        let cursor = (cursor as usize).min(text.len());
+        self.before_cursor = text.get(..cursor).unwrap_or_default().to_string();
+    }
+
+    /// Feed a key press to the engine, `space` and `Tab` expand.
+    pub fn handle_key(&mut self, key: &KeyEvent) -> Response {
+        let suffix = match key.keysym {
+            Keysym::space => " ",
+            Keysym::Tab => "",
+            _ => return Response::ignored(),
+        };
+        self.reload_if_changed();
+
+        let Some((abbreviation, template)) = self
+            .snippets
+            .snippets
+            .iter()
+            .filter(|(abbreviation, _)| {
+                ends_with_word(&self.before_cursor, abbreviation)
+            })
+            // Prefer `;address` over `;ss` when both match.
+            .max_by_key(|(abbreviation, _)| abbreviation.len())
+        else {
+            return Response::ignored();
+        };
+        let clipboard =
+            self.clipboard.as_deref().filter(|_| !self.is_private());
+        let mut expansion = self.snippets.expand(template, clipboard);
+        expansion.push_str(suffix);
+        let before_length = abbreviation.len() as u32;
+        self.before_cursor.clear();
+
+        Response::consumed(vec![
+            ActionInner::DeleteSurroundingText {
+                before_length,
+                after_length: 0,
+            },
+            ActionInner::CommitString(expansion),
+        ])
+    }
+
+    /// Forget the context, e.g. on `Deactivate`.
+    pub fn reset(&mut self) {
+        self.before_cursor.clear();
+    }
+}
+
+fn format_time(
+    out: &mut String,
+    time: &chrono::DateTime<chrono::Local>,
+    format: &str,
+) {
+    let len = out.len();
+    // An invalid format fails to write instead of panicking like `to_string`.
+    if write!(out, "{}", time.format(format)).is_err() {
+        out.truncate(len);
+    }
+}
+
+/// Whether `text` ends with `word` and no word character comes before it, so
+/// `btw` does not expand inside `subtw`.
+fn ends_with_word(text: &str, word: &str) -> bool {
+    let Some(head) = text.strip_suffix(word) else {
+        return false;
+    };
+    !word.is_empty()
+        && !head
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = r#"
+date_format = "%d.%m.%Y"
+
+[snippets]
+btw = "by the way"
+";addr" = "1 Main Street"
+addr = "address"
+";quote" = "> {clipboard} {{braces}} {nope"
+"#;
+
+    fn engine(snippets: Snippets) -> SnippetEngine {
+        let mut engine = SnippetEngine::with_snippets(snippets);
+        // Tests must not change the privacy shared by every engine.
+        engine.watch_privacy(PrivacyWatch::new(Privacy::default()));
+        engine
+    }
+
+    fn key(keysym: Keysym) -> KeyEvent {
+        KeyEvent {
+            time: 0,
+            raw_code: 0,
+            keysym,
+            utf8: None,
+        }
+    }
+
+    /// The text `before_cursor` expands to on `space`, if any.
+    fn expand(
+        engine: &mut SnippetEngine,
+        before_cursor: &str,
+    ) -> Option<String> {
+        engine.surrounding_text(before_cursor, before_cursor.len() as u32);
+        match engine.handle_key(&key(Keysym::space)).actions.as_slice() {
+            [ActionInner::DeleteSurroundingText {
+                before_length,
+                after_length: 0,
+            }, ActionInner::CommitString(text)] => {
+                Some(format!("-{before_length} {text}"))
+            }
+            [] => None,
+            actions => panic!("unexpected actions {actions:?}"),
+        }
+    }
+
+    #[test]
+    fn snippets_file_is_parsed() {
+        let snippets = Snippets::parse(FILE).unwrap();
+        assert_eq!(snippets.date_format.as_deref(), Some("%d.%m.%Y"));
+        assert_eq!(snippets.snippets.len(), 4);
+        assert_eq!(snippets.snippets["btw"], "by the way");
+
+        assert!(Snippets::parse("").unwrap().snippets.is_empty());
+        assert!(matches!(
+            Snippets::parse("[snippets]\nbtw = 1"),
+            Err(SnippetError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn placeholders_are_replaced() {
+        let snippets = Snippets::parse(FILE).unwrap();
+        assert_eq!(
+            snippets.expand(&snippets.snippets[";quote"], Some("copied")),
+            "> copied {braces} {nope"
+        );
+        assert_eq!(snippets.expand("{date:%%}{unknown}", None), "%{unknown}");
+        let date = snippets.expand("{date}", None);
+        assert_eq!(date.len(), "01.01.2000".len());
+        assert_eq!(&date[2..3], ".");
+    }
+
+    #[test]
+    fn abbreviations_expand_at_word_starts() {
+        let mut engine = engine(Snippets::parse(FILE).unwrap());
+        assert_eq!(
+            expand(&mut engine, "btw").as_deref(),
+            Some("-3 by the way ")
+        );
+        assert_eq!(
+            expand(&mut engine, "so, btw").as_deref(),
+            Some("-3 by the way ")
+        );
+        assert_eq!(expand(&mut engine, "subtw"), None);
+        assert_eq!(expand(&mut engine, "x_btw"), None);
+        // The longest abbreviation wins.
+        assert_eq!(
+            expand(&mut engine, "at ;addr").as_deref(),
+            Some("-5 1 Main Street ")
+        );
+        assert!(!engine.handle_key(&key(Keysym::a)).consumed);
+    }
+
+    #[test]
+    fn clipboard_is_not_expanded_in_private_inputs() {
+        let mut engine = engine(Snippets::parse(FILE).unwrap());
+        engine.set_clipboard_text(Some("secret".to_string()));
+        engine.set_privacy(Privacy {
+            private: true,
+            bypass_composition: false,
+        });
+        assert_eq!(
+            expand(&mut engine, ";quote").as_deref(),
+            Some("-6 >  {braces} {nope ")
+        );
+    }
+
+    #[test]
+    fn modified_file_is_read_on_the_next_expansion() {
+        let dir = std::env::temp_dir().join(format!(
+            "iced_wayland_input_method-snippets-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("snippets.toml");
+        fs::write(&path, "[snippets]\nbtw = \"by the way\"").unwrap();
+        let mut engine = SnippetEngine::with_path(&path);
+        engine.watch_privacy(PrivacyWatch::new(Privacy::default()));
+        assert_eq!(
+            expand(&mut engine, "btw").as_deref(),
+            Some("-3 by the way ")
+        );
+
+        fs::write(&path, "[snippets]\nbtw = \"BTW\"").unwrap();
+        // Coarse file systems may not tell both writes apart.
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() + std::time::Duration::from_secs(2))
+            .unwrap();
+        assert_eq!(expand(&mut engine, "btw").as_deref(), Some("-3 BTW "));
+
+        // A broken file keeps the snippets.
+        fs::write(&path, "[snippets").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() + std::time::Duration::from_secs(4))
+            .unwrap();
+        assert_eq!(expand(&mut engine, "btw").as_deref(), Some("-3 BTW "));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,326 @@
+//! Persistent user dictionary.
+//!
+//! A [`UserStore`] counts how often words and phrases are chosen, so engines
+//! can rank the user's own vocabulary first. Stores live in
+//! `$XDG_DATA_HOME/iced_wayland_input_method/store/<name>` and are written
+//! atomically.
+//!
+//! The text format used on disk and for import and export has one entry per
+//! line, a count and the word or phrase separated by a tab:
+//!
+//! ```text
+//! 12	wayland
+//! 3	see you tomorrow
+//! ```
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufRead, Write},
+    path::PathBuf,
+};
+
+use iced_futures::core::event::wayland::Privacy;
+
+use super::PrivacyWatch;
+
+/// Entries longer than this many bytes are not stored.
+pub const MAX_ENTRY_LEN: usize = 256;
+
+/// Word and phrase counts kept across restarts.
+#[derive(Debug, Clone)]
+pub struct UserStore {
+    path: Option<PathBuf>,
+    entries: HashMap<String, u32>,
+    max_entries: usize,
+    privacy: PrivacyWatch,
+    dirty: bool,
+}
+
+impl UserStore {
+    /// Open the store called `name` in the user data directory. A missing or
+    /// unreadable file is an empty store.
+    pub fn open(name: &str) -> Self {
+        let path = super::data_dir().map(|dir| dir.join("store").join(name));
+        let mut store = Self {
+            path,
+            ..Self::in_memory()
+        };
+        if let Some(path) = store.path.as_ref() {
+            match fs::File::open(path) {
+                Ok(file) => {
+                    if let Err(err) = store.import(io::BufReader::new(file)) {
+                        log::warn!(
+                            "Failed to read user store {}: {}",
+                            path.display(),
+                            err
+                        );
+                    }
+                    store.dirty = false;
+                }
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => log::warn!(
+                    "Failed to open user store {}: {}",
+                    path.display(),
+                    err
+                ),
+            }
+        }
+        store
+    }
+
+    /// A store which is never written to disk.
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            entries: HashMap::new(),
+            max_entries: 10_000,
+            privacy: PrivacyWatch::default(),
+            dirty: false,
+        }
+    }
+
+    /// Limit the number of entries, the least used are dropped first.
+    pub fn set_max_entries(&mut self, max_entries: usize) {
+        self.max_entries = max_entries;
+        self.evict();
+    }
+
+    /// Apply the privacy of the text input. Nothing is recorded while the
+    /// input is private.
+    pub fn set_privacy(&mut self, privacy: Privacy) {
+        self.privacy.set(privacy);
+    }
+
+    /// Follow `watch` instead of the watch shared with the default event
+    /// loop settings.
+    pub fn watch_privacy(&mut self, watch: PrivacyWatch) {
+        self.privacy = watch;
+    }
+
+    /// Whether recording is currently disabled.
+    pub fn is_private(&self) -> bool {
+        self.privacy.get().private
+    }
+
+    /// Count a use of `entry`.
+    pub fn record(&mut self, entry: &str) {
+        self.add(entry, 1);
+    }
+
+    /// How often `entry` was used.
+    pub fn count(&self, entry: &str) -> u32 {
+        self.entries.get(entry.trim()).copied().unwrap_or_default()
+    }
+
+    /// The entries starting with `prefix`, most used first.
+    pub fn completions(&self, prefix: &str) -> Vec<(&str, u32)> {
+        let mut matches: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|(entry, _)| entry.starts_with(prefix))
+            .map(|(entry, count)| (entry.as_str(), *count))
+            .collect();
+        matches.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        matches
+    }
+
+    /// All entries, most used first.
+    pub fn entries(&self) -> Vec<(&str, u32)> {
+        self.completions("")
+    }
+
+    /// Remove `entry`, returns whether it was present.
+    pub fn forget(&mut self, entry: &str) -> bool {
+        let removed = self.entries.remove(entry.trim()).is_some();
+        self.dirty |= removed;
+        removed
+    }
+
+    /// Remove every entry.
+    pub fn clear(&mut self) {
+        self.dirty |= !self.entries.is_empty();
+        self.entries.clear();
+    }
+
+    /// Merge entries in the text format, counts of existing entries are
+    /// added up. Malformed lines are skipped.
+    pub fn import(&mut self, reader: impl BufRead) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            let Some((count, entry)) = line.split_once('\t') else {
+                continue;
+            };
+            if let Ok(count) = count.trim().parse() {
+                self.insert(entry, count);
+            }
+        }
+        self.evict();
+        Ok(())
+    }
+
+    /// Write all entries in the text format.
+    pub fn export(&self, mut writer: impl Write) -> io::Result<()> {
+        for (entry, count) in self.entries() {
+            writeln!(writer, "{count}\t{entry}")?;
+        }
+        Ok(())
+    }
+
+    /// Write the store to disk if it changed.
+    pub fn save(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+        let mut contents = Vec::new();
+        self.export(&mut contents)?;
+        super::write_atomic(path, &contents)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    fn add(&mut self, entry: &str, count: u32) {
+        if self.is_private() {
+            return;
+        }
+        self.insert(entry, count);
+        self.evict();
+    }
+
+    fn insert(&mut self, entry: &str, count: u32) {
+        let entry = entry.trim();
+        if entry.is_empty()
+            || entry.len() > MAX_ENTRY_LEN
+            || entry.contains(['\t', '\n'])
+        {
+            return;
+        }
+        let value = self.entries.entry(entry.to_string()).or_default();
+        *value = value.saturating_add(count);
+        self.dirty = true;
+    }
+
+    fn evict(&mut self) {
+        if self.entries.len() <= self.max_entries {
+            return;
+        }
+        let mut counts: Vec<u32> = self.entries.values().copied().collect();
+        let excess = self.entries.len() - self.max_entries;
+        let (_, threshold, _) = counts.select_nth_unstable(excess - 1);
+        let threshold = *threshold;
+        self.entries.retain(|_, count| *count >= threshold);
+        // Ties at the threshold are dropped until the limit is met.
+        let mut to_remove = self.entries.len().saturating_sub(self.max_entries);
+        self.entries.retain(|_, count| {
+            if to_remove > 0 && *count == threshold {
+                to_remove -= 1;
+                false
+            } else {
+                true
+            }
+        });
+        self.dirty = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> UserStore {
+        let mut store = UserStore::in_memory();
+        // Tests must not change the privacy shared by every engine.
+        store.watch_privacy(PrivacyWatch::new(Privacy::default()));
+        store
+    }
+
+    #[test]
+    fn export_and_import_round_trip() {
+        let mut store = store();
+        store.record("wayland");
+        store.record("wayland");
+        store.record(" see you tomorrow ");
+        store.record("tab\tseparated");
+
+        let mut text = Vec::new();
+        store.export(&mut text).unwrap();
+        assert_eq!(text, b"2\twayland\n1\tsee you tomorrow\n");
+
+        let mut imported = self::store();
+        imported.record("wayland");
+        imported
+            .import(&b"2\twayland\nbroken line\n1\tsee you tomorrow\n"[..])
+            .unwrap();
+        assert_eq!(
+            imported.entries(),
+            [("wayland", 3), ("see you tomorrow", 1)]
+        );
+    }
+
+    #[test]
+    fn forgotten_entries_are_gone() {
+        let mut store = store();
+        store.record("wayland");
+        store.record("wave");
+        assert!(store.forget("wayland"));
+        assert!(!store.forget("wayland"));
+        assert_eq!(store.count("wayland"), 0);
+        assert_eq!(store.completions("wa"), [("wave", 1)]);
+    }
+
+    #[test]
+    fn least_used_entries_are_evicted() {
+        let mut store = store();
+        for (entry, count) in [("a", 1), ("b", 4), ("c", 2), ("d", 3)] {
+            for _ in 0..count {
+                store.record(entry);
+            }
+        }
+        store.set_max_entries(2);
+        assert_eq!(store.entries(), [("b", 4), ("d", 3)]);
+
+        store.record("e");
+        assert_eq!(store.entries().len(), 2);
+        assert_eq!(store.count("e"), 0);
+    }
+
+    #[test]
+    fn nothing_is_recorded_while_private() {
+        let mut store = store();
+        let watch = PrivacyWatch::new(Privacy::default());
+        store.watch_privacy(watch.clone());
+        store.record("public");
+
+        watch.set(Privacy {
+            private: true,
+            bypass_composition: false,
+        });
+        store.record("hunter2");
+        store.record("public");
+        assert_eq!(store.entries(), [("public", 1)]);
+    }
+
+    #[test]
+    fn save_replaces_the_file() {
+        let dir = std::env::temp_dir().join(format!(
+            "iced_wayland_input_method-store-{}",
+            std::process::id()
+        ));
+        let path = dir.join("words");
+        let mut store = UserStore {
+            path: Some(path.clone()),
+            ..store()
+        };
+        store.record("wayland");
+        store.save().unwrap();
+        store.record("wayland");
+        store.save().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "2\twayland\n");
+        // No temporary files are left behind.
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,366 @@
+//! Gesture typing.
+//!
+//! A swipe is the path of a finger or the mouse across the keys of an
+//! on-screen keyboard, see [`Keys::on_swipe`](crate::osk::Keys::on_swipe).
+//! It is decoded by comparing it with the ideal path of every word of the
+//! lexicon, the polyline through the centers of its letters' keys. Paths are
+//! compared by location and by shape, after resampling both to the same
+//! number of points, and the best matches are weighted by word frequency.
+//!
+//! Positions are in key units: one unit is the width of a regular key
+//! horizontally and the height of a row vertically, so decoding does not
+//! depend on the size of the keyboard.
+use std::{collections::HashMap, io, path::Path};
+
+use iced_futures::core::event::wayland::Privacy;
+use iced_runtime::command::platform_specific::wayland::input_method::ActionInner;
+use iced_runtime::core::Point;
+
+use super::{prediction::NgramModel, Candidate, PrivacyWatch, Response};
+
+/// The number of points paths are resampled to.
+const SAMPLES: usize = 32;
+/// How far the start and end of a swipe may be from the first and last
+/// letter of a word, in key units.
+const END_TOLERANCE: f32 = 1.0;
+/// The weight of the shape distance relative to the location distance.
+const SHAPE_WEIGHT: f32 = 0.5;
+/// The weight of word frequency relative to the distances.
+const FREQUENCY_WEIGHT: f32 = 0.3;
+
+/// Decodes swipes into words.
+#[derive(Debug, Clone)]
+pub struct SwipeEngine {
+    lexicon: Vec<(String, u32)>,
+    max_count: u32,
+    keys: HashMap<char, Point>,
+    candidates: Vec<Candidate>,
+    max_candidates: usize,
+    privacy: PrivacyWatch,
+}
+
+impl Default for SwipeEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SwipeEngine {
+    /// An engine with the words of the prediction model in the user data
+    /// directory, `prediction/ngrams`.
+    pub fn new() -> Self {
+        let model = super::data_dir()
+            .and_then(|dir| {
+                NgramModel::load(dir.join("prediction/ngrams")).ok()
+            })
+            .unwrap_or_default();
+        Self::from_model(&model)
+    }
+
+    /// An engine with the words of `model`.
+    pub fn from_model(model: &NgramModel) -> Self {
+        Self::with_lexicon(model.words().map(|(w, c)| (w.to_string(), c)))
+    }
+
+    /// An engine with the words of a model file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::from_model(&NgramModel::load(path)?))
+    }
+
+    /// An engine with the given words and how often they occur.
+    pub fn with_lexicon(
+        words: impl IntoIterator<Item = (String, u32)>,
+    ) -> Self {
+        let lexicon: Vec<(String, u32)> = words
+            .into_iter()
+            .filter(|(w, _)| w.chars().count() > 1)
+            .map(|(w, c)| (w.to_lowercase(), c))
+            .collect();
+        Self {
+            max_count: lexicon.iter().map(|(_, c)| *c).max().unwrap_or(0),
+            lexicon,
+            keys: HashMap::new(),
+            candidates: Vec::new(),
+            max_candidates: 3,
+            privacy: PrivacyWatch::default(),
+        }
+    }
+
+    /// Set the center of the key of every letter, e.g. from
+    /// [`View::letters`](crate::osk::View::letters).
+    pub fn set_keys(&mut self, keys: impl IntoIterator<Item = (char, Point)>) {
+        self.keys = keys
+            .into_iter()
+            .flat_map(|(c, p)| c.to_lowercase().map(move |c| (c, p)))
+            .collect();
+    }
+
+    /// Set how many candidates are offered.
+    pub fn set_max_candidates(&mut self, max_candidates: usize) {
+        self.max_candidates = max_candidates.max(1);
+    }
+
+    /// Private text inputs get no candidates, swiped words may be
+    /// passwords.
+    pub fn set_privacy(&mut self, privacy: Privacy) {
+        self.privacy.set(privacy);
+        if privacy.private {
+            self.candidates.clear();
+        }
+    }
+
+    /// Follow `watch` instead of the watch shared with the default event
+    /// loop settings.
+    pub fn watch_privacy(&mut self, watch: PrivacyWatch) {
+        self.privacy = watch;
+    }
+
+    fn is_private(&self) -> bool {
+        self.privacy.get().private
+    }
+
+    /// The ideal path of `word`, or `None` if a letter has no key.
+    pub fn ideal_path(&self, word: &str) -> Option<Vec<Point>> {
+        let mut path: Vec<Point> = Vec::new();
+        for c in word.chars().flat_map(char::to_lowercase) {
+            let p = *self.keys.get(&c)?;
+            // Double letters are a single key.
+            if path.last() != Some(&p) {
+                path.push(p);
+            }
+        }
+        Some(path)
+    }
+
+    /// Decode `path` and offer the best words as candidates.
+    pub fn decode(&mut self, path: &[Point]) -> &[Candidate] {
+        self.candidates.clear();
+        if self.is_private() || path.len() < 2 || length(path) < END_TOLERANCE {
+            return &self.candidates;
+        }
+        let input = resample(path);
+        let input_shape = normalize(&input);
+        let (start, end) = (input[0], input[SAMPLES - 1]);
+
+        let mut scored: Vec<(f32, &str)> = self
+            .lexicon
+            .iter()
+            .filter_map(|(word, count)| {
+                let ideal = self.ideal_path(word)?;
+                if ideal[0].distance(start) > END_TOLERANCE
+                    || ideal[ideal.len() - 1].distance(end) > END_TOLERANCE
+                {
+                    return None;
+                }
+                let ideal = resample(&ideal);
+                let location = mean_distance(&input, &ideal);
+                let shape = mean_distance(&input_shape, &normalize(&ideal));
+                let frequency = (*count as f32 + 1.0).ln()
+                    / (self.max_count as f32 + 2.0).ln();
+                let cost = location + SHAPE_WEIGHT * shape
+                    - FREQUENCY_WEIGHT * frequency;
+                Some((cost, word.as_str()))
+            })
+            .collect();
+        scored.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(b.1)));
+
+        self.candidates = scored
+            .into_iter()
+            .take(self.max_candidates)
+            .map(|(_, word)| Candidate {
+                text: word.to_string(),
+                label: String::new(),
+            })
+            .collect();
+        &self.candidates
+    }
+
+    /// The candidates of the last swipe, best first.
+    pub fn candidates(&self) -> &[Candidate] {
+        if self.is_private() {
+            return &[];
+        }
+        &self.candidates
+    }
+
+    /// Commit the candidate at `index` followed by a space.
+    pub fn accept(&mut self, index: usize) -> Response {
+        // The watch may have turned private since the swipe was decoded.
+        if self.is_private() {
+            self.candidates.clear();
+            return Response::ignored();
+        }
+        let Some(candidate) = self.candidates.get(index) else {
+            return Response::ignored();
+        };
+        let text = format!("{} ", candidate.text);
+        self.candidates.clear();
+        Response::consumed(vec![ActionInner::CommitString(text)])
+    }
+
+    /// Drop the candidates, e.g. on `Deactivate`.
+    pub fn reset(&mut self) {
+        self.candidates.clear();
+    }
+}
+
+fn length(path: &[Point]) -> f32 {
+    path.windows(2).map(|w| w[0].distance(w[1])).sum()
+}
+
+/// `path` as [`SAMPLES`] points evenly spaced along it.
+fn resample(path: &[Point]) -> Vec<Point> {
+    let total = length(path);
+    if path.len() < 2 || total == 0.0 {
+        return vec![path.first().copied().unwrap_or(Point::ORIGIN); SAMPLES];
+    }
+    let step = total / (SAMPLES - 1) as f32;
+    let mut out = Vec::with_capacity(SAMPLES);
+    out.push(path[0]);
+    let mut target = step;
+    let mut walked = 0.0;
+    for w in path.windows(2) {
+        let segment = w[0].distance(w[1]);
+        if segment == 0.0 {
+            continue;
+        }
+        while out.len() < SAMPLES && walked + segment >= target {
+            let t = (target - walked) / segment;
+            out.push(Point::new(
+                w[0].x + t * (w[1].x - w[0].x),
+                w[0].y + t * (w[1].y - w[0].y),
+            ));
+            target += step;
+        }
+        walked += segment;
+    }
+    // Rounding may leave the last point out.
+    out.resize(SAMPLES, path[path.len() - 1]);
+    out
+}
+
+/// `path` moved to be centered on the origin and scaled to fit a unit box,
+/// leaving only its shape.
+fn normalize(path: &[Point]) -> Vec<Point> {
+    let (min, max) = path.iter().fold(
+        (
+            Point::new(f32::MAX, f32::MAX),
+            Point::new(f32::MIN, f32::MIN),
+        ),
+        |(min, max), p| {
+            (
+                Point::new(min.x.min(p.x), min.y.min(p.y)),
+                Point::new(max.x.max(p.x), max.y.max(p.y)),
+            )
+        },
+    );
+    let scale = (max.x - min.x).max(max.y - min.y).max(f32::EPSILON);
+    let n = path.len().max(1) as f32;
+    let center = Point::new(
+        path.iter().map(|p| p.x).sum::<f32>() / n,
+        path.iter().map(|p| p.y).sum::<f32>() / n,
+    );
+    path.iter()
+        .map(|p| Point::new((p.x - center.x) / scale, (p.y - center.y) / scale))
+        .collect()
+}
+
+fn mean_distance(a: &[Point], b: &[Point]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a.distance(*b)).sum::<f32>()
+        / a.len().max(1) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine() -> SwipeEngine {
+        let mut engine = SwipeEngine::with_lexicon([
+            ("cat".to_string(), 10),
+            ("car".to_string(), 20),
+            ("act".to_string(), 5),
+        ]);
+        // Tests must not change the privacy shared by every engine.
+        engine.watch_privacy(PrivacyWatch::new(Privacy::default()));
+        engine.set_keys([
+            ('a', Point::new(0.5, 1.5)),
+            ('c', Point::new(2.5, 2.5)),
+            ('r', Point::new(3.5, 0.5)),
+            ('t', Point::new(4.5, 0.5)),
+        ]);
+        engine
+    }
+
+    /// A slightly wobbly path through `points`, recorded every 0.1 units.
+    fn swipe(points: &[Point]) -> Vec<Point> {
+        let mut path = vec![points[0]];
+        for w in points.windows(2) {
+            let steps = (w[0].distance(w[1]) / 0.1).ceil() as usize;
+            for i in 1..=steps {
+                let t = i as f32 / steps as f32;
+                let wobble = if i % 2 == 0 { 0.05 } else { -0.05 };
+                path.push(Point::new(
+                    w[0].x + t * (w[1].x - w[0].x),
+                    w[0].y + t * (w[1].y - w[0].y) + wobble,
+                ));
+            }
+        }
+        path
+    }
+
+    #[test]
+    fn decodes_the_closest_word() {
+        let mut engine = engine();
+        let path = swipe(&[
+            Point::new(2.4, 2.6),
+            Point::new(0.6, 1.4),
+            Point::new(4.4, 0.6),
+        ]);
+        let words: Vec<_> = engine
+            .decode(&path)
+            .iter()
+            .map(|c| c.text.as_str())
+            .collect();
+        assert_eq!(words.first(), Some(&"cat"));
+        assert!(!words.contains(&"act"));
+    }
+
+    #[test]
+    fn short_and_private_swipes_are_not_decoded() {
+        let mut engine = engine();
+        let tap = swipe(&[Point::new(2.5, 2.5), Point::new(2.7, 2.5)]);
+        assert!(engine.decode(&tap).is_empty());
+
+        engine.watch_privacy(PrivacyWatch::new(Privacy {
+            private: true,
+            bypass_composition: false,
+        }));
+        let path = swipe(&[
+            Point::new(2.5, 2.5),
+            Point::new(0.5, 1.5),
+            Point::new(4.5, 0.5),
+        ]);
+        assert!(engine.decode(&path).is_empty());
+    }
+
+    #[test]
+    fn candidates_are_not_accepted_once_private() {
+        let mut engine = engine();
+        let watch = PrivacyWatch::new(Privacy::default());
+        engine.watch_privacy(watch.clone());
+        let path = swipe(&[
+            Point::new(2.5, 2.5),
+            Point::new(0.5, 1.5),
+            Point::new(4.5, 0.5),
+        ]);
+        assert!(!engine.decode(&path).is_empty());
+
+        watch.set(Privacy {
+            private: true,
+            bypass_composition: false,
+        });
+        let response = engine.accept(0);
+        assert!(!response.consumed && response.actions.is_empty());
+        assert!(engine.candidates().is_empty());
+    }
+}
@@ -11,15 +11,21 @@ use crate::{
     conversion,
     dpi::LogicalSize,
     handlers::{
+        data_device::{
+            data_control::DataControlManager, pipe,
+            primary_selection::PrimarySelectionManager,
+        },
+        foreign_toplevel::ForeignToplevelManager,
         input_method::InputMethodManager,
+        seat::tablet::TabletManager,
         virtual_keyboard::VirtualKeyboardManager,
         wp_fractional_scaling::FractionalScalingManager,
         wp_viewporter::ViewporterState,
     },
     sctk_event::{
         DndOfferEvent, IcedSctkEvent, LayerSurfaceEventVariant,
-        PopupEventVariant, SctkEvent, SelectionOfferEvent, StartCause,
-        WindowEventVariant, InputMethodPopupEventVariant,
+        PopupEventVariant, SctkEvent, SelectionKind, SelectionOfferEvent,
+        StartCause, WindowEventVariant, InputMethodPopupEventVariant,
     },
     settings,
 };
@@ -95,9 +101,18 @@ where
     T: 'static + Debug,
 {
     pub(crate) fn new<F: Sized>(
-        _settings: &settings::Settings<F>,
+        settings: &settings::Settings<F>,
     ) -> Result<Self, ConnectError> {
         let connection = Connection::connect_to_env()?;
+        Ok(Self::with_connection(connection, settings))
+    }
+
+    /// Creates an event loop on an existing connection, e.g. to a compositor
+    /// running in the same process.
+    pub fn with_connection<F: Sized>(
+        connection: Connection,
+        settings: &settings::Settings<F>,
+    ) -> Self {
         let _display = connection.display();
         let (globals, event_queue) = registry_queue_init(&connection).unwrap();
         let event_loop = calloop::EventLoop::<SctkState<T>>::try_new().unwrap();
@@ -183,7 +198,51 @@ where
                 }
             };
 
-        Ok(Self {
+        let data_control_manager =
+            match DataControlManager::new(&globals, &qh) {
+                Ok(m) => Some(m),
+                Err(e) => {
+                    error!(
+                        "Failed to initialize data control manager: {}",
+                        e
+                    );
+                    None
+                }
+            };
+
+        let primary_selection_manager =
+            match PrimarySelectionManager::new(&globals, &qh) {
+                Ok(m) => Some(m),
+                Err(e) => {
+                    error!(
+                        "Failed to initialize primary selection manager: {}",
+                        e
+                    );
+                    None
+                }
+            };
+
+        let tablet_manager = match TabletManager::new(&globals, &qh) {
+            Ok(m) => Some(m),
+            Err(e) => {
+                error!("Failed to initialize tablet manager: {}", e);
+                None
+            }
+        };
+
+        let foreign_toplevel_manager =
+            match ForeignToplevelManager::new(&globals, &qh) {
+                Ok(m) => Some(m),
+                Err(e) => {
+                    error!(
+                        "Failed to initialize foreign toplevel manager: {}",
+                        e
+                    );
+                    None
+                }
+            };
+
+        Self {
             event_loop,
             wayland_dispatcher,
             state: SctkState {
@@ -206,7 +265,9 @@ where
                 loop_handle,
 
                 _cursor_surface: None,
-                _multipool: None,
+                multipool: None,
+                #[cfg(feature = "software")]
+                software_frames: Default::default(),
                 outputs: Vec::new(),
                 seats: Vec::new(),
                 windows: Vec::new(),
@@ -223,18 +284,39 @@ where
                 _accept_counter: 0,
                 dnd_offer: None,
                 selection_offer: None,
+                primary_selection_source: None,
+                primary_selection_offer: None,
                 fractional_scaling_manager,
                 viewporter_state,
                 compositor_updates: Default::default(),
                 input_method_manager,
                 virtual_keyboard_manager,
+                data_control_manager,
+                primary_selection_manager,
+                tablet_manager,
+                foreign_toplevel_manager,
+                foreign_toplevels: Vec::new(),
+                compose_settings: settings.compose.clone(),
+                privacy_settings: settings.privacy.clone(),
+                virtual_keyboard_settings: settings.virtual_keyboard.clone(),
+                pending_content_type: None,
+                privacy: Default::default(),
+                profile: Default::default(),
+                pending_change_cause: None,
+                outstanding_commits: 0,
+                uncommitted_edits: false,
+                preedit_shown: false,
+                done_count: 0,
+                pending_activation: None,
+                input_method_active: false,
+                auto_show: None,
             },
             _features: Default::default(),
             event_loop_awakener: ping,
             user_events_sender,
             #[cfg(feature = "a11y")]
             a11y_events: Arc::new(Mutex::new(Vec::new())),
-        })
+        }
     }
 
     pub fn proxy(&self) -> proxy::Proxy<Event<T>> {
@@ -256,6 +338,16 @@ where
         self.state.get_window(settings)
     }
 
+    /// Make a software compositor created with `settings` present on the
+    /// surfaces of this event loop, as [`run`](crate::run) does.
+    #[cfg(feature = "software")]
+    pub fn connect_software(
+        &self,
+        settings: crate::software::Settings,
+    ) -> crate::software::Settings {
+        crate::software::connect(settings, &self.state.software_frames)
+    }
+
     // TODO Ashley provide users a reasonable method of setting the role for the surface
     #[cfg(feature = "a11y")]
     pub fn init_a11y_adapter(
@@ -725,6 +817,12 @@ where
                                 );
                             }
                         },
+                        platform_specific::wayland::layer_surface::Action::AutoShow { builder, debounce } => {
+                            self.state.set_auto_show(builder, debounce);
+                        }
+                        platform_specific::wayland::layer_surface::Action::AutoShowVisible(visible) => {
+                            self.state.set_auto_show_visible(visible);
+                        }
                         platform_specific::wayland::layer_surface::Action::Anchor { id, anchor } => {
                             if let Some(layer_surface) = self.state.layer_surfaces.iter_mut().find(|l| l.id == id) {
                                 layer_surface.anchor = anchor;
@@ -1239,46 +1337,9 @@ where
                                         Ok(p) => p,
                                         Err(_) => continue, // TODO error handling
                                     };
-                                    let loop_handle = self.event_loop.handle();
-                                    match self.event_loop.handle().insert_source(read_pipe, move |_, f, state| {
-                                        let selection_offer = match state.selection_offer.as_mut() {
-                                            Some(s) => s,
-                                            None => return,
-                                        };
-                                        let (mime_type, data, token) = match selection_offer.cur_read.take() {
-                                            Some(s) => s,
-                                            None => return,
-                                        };
-                                        let mut reader = BufReader::new(f.as_ref());
-                                        let consumed = match reader.fill_buf() {
-                                            Ok(buf) => {
-                                                if buf.is_empty() {
-                                                    loop_handle.remove(token);
-                                                    state.sctk_events.push(SctkEvent::SelectionOffer(SelectionOfferEvent::Data {mime_type, data }));
-                                                } else {
-                                                    let mut data = data;
-                                                    data.extend_from_slice(buf);
-                                                    selection_offer.cur_read = Some((mime_type, data, token));
-                                                }
-                                                buf.len()
-                                            },
-                                            Err(e) if matches!(e.kind(), std::io::ErrorKind::Interrupted) => {
-                                                selection_offer.cur_read = Some((mime_type, data, token));
-                                                return;
-                                            },
-                                            Err(e) => {
-                                                error!("Error reading selection data: {}", e);
-                                                loop_handle.remove(token);
-                                                return;
-                                            },
-                                        };
-                                        reader.consume(consumed);
-                                    }) {
-                                        Ok(token) => {
-                                            selection_offer.cur_read = Some((mime_type.clone(), Vec::new(), token));
-                                        },
-                                        Err(_) => continue,
-                                    };
+                                    pipe::read_pipe(&self.state.loop_handle, read_pipe, usize::MAX, move |state, data| {
+                                        state.sctk_events.push(SctkEvent::SelectionOffer(SelectionOfferEvent::Data { mime_type, data }, SelectionKind::Clipboard));
+                                    });
                                 }
                             }
                             platform_specific::wayland::data_device::ActionInner::SetSelection { mime_types, data } => {
@@ -1301,7 +1362,6 @@ where
                                 source.set_selection(&seat.data_device, serial);
                                 self.state.selection_source = Some(SctkCopyPasteSource {
                                     source,
-                                    cur_write: None,
                                     accepted_mime_types: Vec::new(),
                                     pipe: None,
                                     data,
@@ -1319,6 +1379,15 @@ where
                                 self.state.selection_source = None;
                                 seat.data_device.unset_selection(serial);
                             }
+                            platform_specific::wayland::data_device::ActionInner::SetPrimarySelection { mime_types, data } => {
+                                self.state.set_primary_selection(mime_types, data);
+                            }
+                            platform_specific::wayland::data_device::ActionInner::UnsetPrimarySelection => {
+                                self.state.unset_primary_selection();
+                            }
+                            platform_specific::wayland::data_device::ActionInner::RequestPrimarySelectionData { mime_type } => {
+                                self.state.request_primary_selection(mime_type);
+                            }
                             platform_specific::wayland::data_device::ActionInner::SetActions { preferred, accepted } => {
                                 if let Some(offer) = self.state.dnd_offer.as_ref() {
                                     offer.offer.set_actions(accepted, preferred);
@@ -1326,11 +1395,20 @@ where
                             }
                         }
                     }
+                    Event::ForeignToplevel(action) => {
+                        match action.inner {
+                            platform_specific::wayland::foreign_toplevel::ActionInner::RequestToplevels => self.state.request_foreign_toplevels(),
+                        }
+                    },
                     Event::VirtualKeyboard(action) => {
                         match action.inner {
                             platform_specific::wayland::virtual_keyboard::ActionInner::KeyPressed(key_event) => self.state.press_key(key_event),
                             platform_specific::wayland::virtual_keyboard::ActionInner::KeyReleased(key_event) => self.state.release_key(key_event),
                             platform_specific::wayland::virtual_keyboard::ActionInner::Modifiers(raw_modifiers) => self.state.update_modifiers(raw_modifiers.into()),
+                            platform_specific::wayland::virtual_keyboard::ActionInner::TapModifiers(mask) => self.state.tap_modifiers(mask),
+                            platform_specific::wayland::virtual_keyboard::ActionInner::ClearModifiers => self.state.clear_modifiers(),
+                            platform_specific::wayland::virtual_keyboard::ActionInner::SetGroup(group) => self.state.set_group(group),
+                            platform_specific::wayland::virtual_keyboard::ActionInner::Keymap(keymap) => self.state.set_virtual_keyboard_keymap(keymap),
                         }
                     },
                     Event::InputMethod(action) => {
@@ -1341,6 +1419,7 @@ where
                                 self.state.set_preedit_string(string, cursor_begin, cursor_end),
                             platform_specific::wayland::input_method::ActionInner::DeleteSurroundingText { before_length, after_length } => 
                                 self.state.delete_surrounding_text(before_length, after_length),
+                            platform_specific::wayland::input_method::ActionInner::ReloadCompose => self.state.reload_compose(),
                         }
                     },
                     Event::InputMethodPopup(action) => {
@@ -1401,6 +1480,11 @@ where
                 s.1.commit();
             }
 
+            #[cfg(feature = "software")]
+            self.state.present_software_frames(
+                self.wayland_dispatcher.as_source_ref().connection(),
+            );
+
             // Send RedrawEventCleared.
             sticky_exit_callback(
                 IcedSctkEvent::RedrawEventsCleared,
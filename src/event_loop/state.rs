@@ -1,13 +1,24 @@
 use std::{
+    collections::HashMap,
     fmt::{Debug, Formatter},
     num::NonZeroU32,
+    time::Instant,
 };
 
 use crate::{
     application::Event,
-    dpi::LogicalSize,
+    dpi::{LogicalPosition, LogicalSize},
     handlers::{
-        input_method::{InputMethodManager, InputMethodPopup},
+        data_device::{
+            data_control::DataControlManager,
+            primary_selection::PrimarySelectionManager,
+        },
+        foreign_toplevel::ForeignToplevelManager,
+        input_method::{
+            auto_show::AutoShow, keyboard::RawModifiers, InputMethodManager,
+            InputMethodPopup, Privacy, Profile,
+        },
+        seat::tablet::TabletManager,
         virtual_keyboard::VirtualKeyboardManager,
         wp_fractional_scaling::FractionalScalingManager,
         wp_viewporter::ViewporterState,
@@ -16,6 +27,7 @@ use crate::{
         LayerSurfaceEventVariant, PopupEventVariant, SctkEvent,
         WindowEventVariant, InputMethodPopupEventVariant,
     },
+    settings::{ComposeSettings, PrivacySettings, VirtualKeyboardSettings},
 };
 
 use iced_runtime::{
@@ -28,6 +40,7 @@ use iced_runtime::{
             window::SctkWindowSettings,
         },
     },
+    core::event::wayland::ForeignToplevel,
     keyboard::Modifiers,
     window,
 };
@@ -44,6 +57,7 @@ use sctk::{
     reexports::{
         calloop::{LoopHandle, RegistrationToken},
         client::{
+            backend::ObjectId,
             protocol::{
                 wl_keyboard::WlKeyboard,
                 wl_output::WlOutput,
@@ -76,13 +90,35 @@ use sctk::{
 };
 use wayland_protocols::wp::{
     fractional_scale::v1::client::wp_fractional_scale_v1::WpFractionalScaleV1,
+    primary_selection::zv1::client::{
+        zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1,
+        zwp_primary_selection_offer_v1::ZwpPrimarySelectionOfferV1,
+        zwp_primary_selection_source_v1::ZwpPrimarySelectionSourceV1,
+    },
+    tablet::zv2::client::zwp_tablet_seat_v2::ZwpTabletSeatV2,
     viewporter::client::wp_viewport::WpViewport,
 };
+use wayland_protocols::wp::text_input::zv3::client::zwp_text_input_v3::{
+    ChangeCause, ContentHint, ContentPurpose,
+};
+use wayland_protocols_wlr::data_control::v1::client::zwlr_data_control_device_v1::ZwlrDataControlDeviceV1;
 use wayland_protocols_misc::{
-    zwp_input_method_v2::client::zwp_input_method_v2::ZwpInputMethodV2,
+    zwp_input_method_v2::client::{
+        zwp_input_method_keyboard_grab_v2::ZwpInputMethodKeyboardGrabV2,
+        zwp_input_method_v2::ZwpInputMethodV2,
+    },
     zwp_virtual_keyboard_v1::client::zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1,
 };
 
+/// A point of contact on a touch device.
+#[derive(Debug, Clone)]
+pub(crate) struct TouchPoint {
+    /// The surface the point went down on, it receives all its events.
+    pub(crate) surface: WlSurface,
+    /// The last position, relative to `surface`.
+    pub(crate) position: LogicalPosition<f64>,
+}
+
 #[derive(Debug)]
 pub(crate) struct SctkSeat {
     pub(crate) seat: WlSeat,
@@ -92,12 +128,26 @@ pub(crate) struct SctkSeat {
     pub(crate) ptr: Option<ThemedPointer>,
     pub(crate) ptr_focus: Option<WlSurface>,
     pub(crate) last_ptr_press: Option<(u32, u32, u32)>, // (time, button, serial)
-    pub(crate) _touch: Option<WlTouch>,
+    /// The serial of the latest button press, touch down or key press. The
+    /// primary selection may only be set in response to user input.
+    pub(crate) last_input_serial: Option<u32>,
+    pub(crate) touch: Option<WlTouch>,
+    pub(crate) touch_points: HashMap<i32, TouchPoint>,
     pub(crate) _modifiers: Modifiers,
     pub(crate) data_device: DataDevice,
     pub(crate) icon: Option<CursorIcon>,
     pub(crate) virtual_keyboard: Option<ZwpVirtualKeyboardV1>,
+    /// The keymap last uploaded to the virtual keyboard.
+    pub(crate) virtual_keyboard_keymap: Option<String>,
+    /// The modifiers last set on the virtual keyboard.
+    pub(crate) virtual_keyboard_modifiers: RawModifiers,
+    /// The modifiers last tapped on the virtual keyboard and when.
+    pub(crate) virtual_keyboard_last_tap: Option<(u32, Instant)>,
     pub(crate) input_method: Option<ZwpInputMethodV2>,
+    pub(crate) input_method_keyboard: Option<ZwpInputMethodKeyboardGrabV2>,
+    pub(crate) data_control_device: Option<ZwlrDataControlDeviceV1>,
+    pub(crate) primary_selection_device: Option<ZwpPrimarySelectionDeviceV1>,
+    pub(crate) tablet_seat: Option<ZwpTabletSeatV2>,
 }
 
 #[derive(Debug, Clone)]
@@ -243,7 +293,6 @@ impl<T> Debug for Dnd<T> {
 #[derive(Debug)]
 pub struct SctkSelectionOffer {
     pub(crate) offer: SelectionOffer,
-    pub(crate) cur_read: Option<(String, Vec<u8>, RegistrationToken)>,
 }
 
 #[derive(Debug)]
@@ -266,7 +315,6 @@ pub struct SctkCopyPasteSource {
     pub source: CopyPasteSource,
     pub data: Box<dyn DataFromMimeType>,
     pub(crate) pipe: Option<WritePipe>,
-    pub(crate) cur_write: Option<(Vec<u8>, usize, RegistrationToken)>,
 }
 
 impl Debug for SctkCopyPasteSource {
@@ -275,7 +323,19 @@ impl Debug for SctkCopyPasteSource {
             .field(&self.accepted_mime_types)
             .field(&self.source)
             .field(&self.pipe)
-            .field(&self.cur_write)
+            .finish()
+    }
+}
+
+pub struct SctkPrimarySelectionSource {
+    pub(crate) source: ZwpPrimarySelectionSourceV1,
+    pub(crate) data: Box<dyn DataFromMimeType>,
+}
+
+impl Debug for SctkPrimarySelectionSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SctkPrimarySelectionSource")
+            .field(&self.source)
             .finish()
     }
 }
@@ -284,8 +344,12 @@ impl Debug for SctkCopyPasteSource {
 pub struct SctkState<T> {
     /// the cursor wl_surface
     pub(crate) _cursor_surface: Option<wl_surface::WlSurface>,
-    /// a memory pool
-    pub(crate) _multipool: Option<MultiPool<WlSurface>>,
+    /// a memory pool for software rendered frames, two buffers per surface
+    #[cfg_attr(not(feature = "software"), allow(dead_code))]
+    pub(crate) multipool: Option<MultiPool<(ObjectId, usize)>>,
+    /// frames presented by the software compositor of this event loop
+    #[cfg(feature = "software")]
+    pub(crate) software_frames: crate::software::Frames,
 
     // all present outputs
     pub(crate) outputs: Vec<WlOutput>,
@@ -313,6 +377,8 @@ pub struct SctkState<T> {
     pub(crate) selection_source: Option<SctkCopyPasteSource>,
     pub(crate) dnd_offer: Option<SctkDragOffer>,
     pub(crate) selection_offer: Option<SctkSelectionOffer>,
+    pub(crate) primary_selection_source: Option<SctkPrimarySelectionSource>,
+    pub(crate) primary_selection_offer: Option<ZwpPrimarySelectionOfferV1>,
     pub(crate) _accept_counter: u32,
     /// A sink for window and device events that is being filled during dispatching
     /// event loop and forwarded downstream afterwards.
@@ -341,6 +407,33 @@ pub struct SctkState<T> {
     pub(crate) token_ctr: u32,
     pub(crate) input_method_manager: Option<InputMethodManager<T>>,
     pub(crate) virtual_keyboard_manager: Option<VirtualKeyboardManager<T>>,
+    pub(crate) data_control_manager: Option<DataControlManager<T>>,
+    pub(crate) primary_selection_manager: Option<PrimarySelectionManager<T>>,
+    pub(crate) tablet_manager: Option<TabletManager<T>>,
+    pub(crate) foreign_toplevel_manager: Option<ForeignToplevelManager<T>>,
+    /// Toplevels of all clients, in the order they were opened.
+    pub(crate) foreign_toplevels: Vec<ForeignToplevel>,
+    pub(crate) compose_settings: ComposeSettings,
+    pub(crate) privacy_settings: PrivacySettings,
+    pub(crate) virtual_keyboard_settings: VirtualKeyboardSettings,
+    /// Text input state received since the last `Done`.
+    pub(crate) pending_content_type: Option<(ContentHint, ContentPurpose)>,
+    pub(crate) privacy: Privacy,
+    pub(crate) profile: Profile,
+    pub(crate) pending_change_cause: Option<ChangeCause>,
+    /// Commits of text requests whose echo has not arrived yet.
+    pub(crate) outstanding_commits: u32,
+    /// Whether text requests were sent since the last commit.
+    pub(crate) uncommitted_edits: bool,
+    /// Whether the last preedit sent was not empty.
+    pub(crate) preedit_shown: bool,
+    /// The number of `Done` events, the serial of the next commit.
+    pub(crate) done_count: u32,
+    /// Activation received since the last `Done`.
+    pub(crate) pending_activation: Option<bool>,
+    /// Whether a text input is active.
+    pub(crate) input_method_active: bool,
+    pub(crate) auto_show: Option<AutoShow>,
 }
 
 /// An error that occurred while running an application.
@@ -0,0 +1,218 @@
+//! Selection watching through `zwlr_data_control_manager_v1`.
+//!
+//! Unlike `wl_data_device`, data control devices report the selection
+//! without keyboard focus, which is what an input method needs to keep a
+//! clipboard history.
+use std::{
+    fmt::Debug, fs::File, marker::PhantomData, os::fd::AsFd, sync::Mutex,
+};
+
+use rustix::pipe::{pipe_with, PipeFlags};
+use sctk::{
+    data_device_manager::ReadPipe,
+    globals::GlobalData,
+    reexports::client::{
+        delegate_dispatch, event_created_child,
+        globals::{BindError, GlobalList},
+        protocol::wl_seat::WlSeat,
+        Connection, Dispatch, Proxy, QueueHandle,
+    },
+};
+use tracing::error;
+use wayland_protocols_wlr::data_control::v1::client::{
+    zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1},
+    zwlr_data_control_manager_v1::ZwlrDataControlManagerV1,
+    zwlr_data_control_offer_v1::{self, ZwlrDataControlOfferV1},
+};
+
+use super::pipe::{read_pipe, MAX_SELECTION_LEN};
+use crate::{
+    event_loop::state::SctkState,
+    sctk_event::{DataControlEvent, SctkEvent},
+};
+
+/// Password managers mark their selections with this mime type, they are
+/// never read.
+pub const PASSWORD_MANAGER_HINT: &str = "x-kde-passwordManagerHint";
+
+/// Text mime types in order of preference.
+pub const TEXT_MIME_TYPES: [&str; 5] = [
+    "text/plain;charset=utf-8",
+    "UTF8_STRING",
+    "text/plain",
+    "TEXT",
+    "STRING",
+];
+
+#[derive(Debug)]
+pub struct DataControlManager<T> {
+    manager: ZwlrDataControlManagerV1,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: 'static> DataControlManager<T> {
+    pub fn new(
+        globals: &GlobalList,
+        queue_handle: &QueueHandle<SctkState<T>>,
+    ) -> Result<Self, BindError> {
+        let manager = globals.bind(queue_handle, 1..=2, GlobalData)?;
+        Ok(Self {
+            manager,
+            _phantom: PhantomData,
+        })
+    }
+
+    pub fn data_device(
+        &self,
+        seat: &WlSeat,
+        queue_handle: &QueueHandle<SctkState<T>>,
+    ) -> ZwlrDataControlDeviceV1 {
+        self.manager
+            .get_data_device(seat, queue_handle, DataControlDevice {})
+    }
+}
+
+#[derive(Debug)]
+pub struct DataControlDevice {}
+
+#[derive(Debug, Default)]
+pub struct DataControlOffer {
+    mime_types: Mutex<Vec<String>>,
+}
+
+impl DataControlOffer {
+    pub fn mime_types(&self) -> Vec<String> {
+        self.mime_types.lock().unwrap().clone()
+    }
+}
+
+impl<T: 'static> Dispatch<ZwlrDataControlManagerV1, GlobalData, SctkState<T>>
+    for DataControlManager<T>
+{
+    fn event(
+        _: &mut SctkState<T>,
+        _: &ZwlrDataControlManagerV1,
+        _: <ZwlrDataControlManagerV1 as Proxy>::Event,
+        _: &GlobalData,
+        _: &Connection,
+        _: &QueueHandle<SctkState<T>>,
+    ) {
+        // No events.
+    }
+}
+
+impl<T: 'static>
+    Dispatch<ZwlrDataControlDeviceV1, DataControlDevice, SctkState<T>>
+    for DataControlManager<T>
+{
+    fn event(
+        state: &mut SctkState<T>,
+        device: &ZwlrDataControlDeviceV1,
+        event: <ZwlrDataControlDeviceV1 as Proxy>::Event,
+        _: &DataControlDevice,
+        _: &Connection,
+        _: &QueueHandle<SctkState<T>>,
+    ) {
+        match event {
+            zwlr_data_control_device_v1::Event::DataOffer { .. } => {
+                // Mime types arrive on the offer before it is announced.
+            }
+            zwlr_data_control_device_v1::Event::Selection { id } => {
+                if let Some(offer) = id {
+                    state.receive_data_control_offer(offer);
+                }
+            }
+            zwlr_data_control_device_v1::Event::PrimarySelection { id } => {
+                if let Some(offer) = id {
+                    offer.destroy();
+                }
+            }
+            zwlr_data_control_device_v1::Event::Finished => {
+                for seat in &mut state.seats {
+                    if seat.data_control_device.as_ref() == Some(device) {
+                        seat.data_control_device = None;
+                    }
+                }
+                device.destroy();
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    event_created_child!(SctkState<T>, ZwlrDataControlDeviceV1, [
+        zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ZwlrDataControlOfferV1, DataControlOffer::default())
+    ]);
+}
+
+impl<T: 'static>
+    Dispatch<ZwlrDataControlOfferV1, DataControlOffer, SctkState<T>>
+    for DataControlManager<T>
+{
+    fn event(
+        _: &mut SctkState<T>,
+        _: &ZwlrDataControlOfferV1,
+        event: <ZwlrDataControlOfferV1 as Proxy>::Event,
+        data: &DataControlOffer,
+        _: &Connection,
+        _: &QueueHandle<SctkState<T>>,
+    ) {
+        match event {
+            zwlr_data_control_offer_v1::Event::Offer { mime_type } => {
+                data.mime_types.lock().unwrap().push(mime_type);
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+delegate_dispatch!(@<T: 'static> SctkState<T>: [ZwlrDataControlManagerV1: GlobalData] => DataControlManager<T>);
+delegate_dispatch!(@<T: 'static> SctkState<T>: [ZwlrDataControlDeviceV1: DataControlDevice] => DataControlManager<T>);
+delegate_dispatch!(@<T: 'static> SctkState<T>: [ZwlrDataControlOfferV1: DataControlOffer] => DataControlManager<T>);
+
+impl<T> SctkState<T>
+where
+    T: 'static + Debug,
+{
+    /// Read the text of a new selection and report it once complete.
+    fn receive_data_control_offer(&mut self, offer: ZwlrDataControlOfferV1) {
+        let mime_types = offer
+            .data::<DataControlOffer>()
+            .map(DataControlOffer::mime_types)
+            .unwrap_or_default();
+        let mime_type = TEXT_MIME_TYPES
+            .iter()
+            .find(|m| mime_types.iter().any(|offered| offered == *m));
+        let mime_type = match mime_type {
+            Some(m) if !mime_types.iter().any(|m| m == PASSWORD_MANAGER_HINT) => {
+                m.to_string()
+            }
+            _ => {
+                offer.destroy();
+                return;
+            }
+        };
+
+        let (read, write) = match pipe_with(PipeFlags::CLOEXEC) {
+            Ok(pipe) => pipe,
+            Err(err) => {
+                error!("Failed to create pipe for selection: {}", err);
+                offer.destroy();
+                return;
+            }
+        };
+        offer.receive(mime_type.clone(), write.as_fd());
+        offer.destroy();
+        drop(write);
+
+        read_pipe(
+            &self.loop_handle,
+            ReadPipe::from(File::from(read)),
+            MAX_SELECTION_LEN,
+            move |state, data| {
+                state.sctk_events.push(SctkEvent::DataControl(
+                    DataControlEvent::Selection { mime_type, data },
+                ));
+            },
+        );
+    }
+}
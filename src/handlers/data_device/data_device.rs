@@ -7,7 +7,7 @@ use sctk::{
 
 use crate::{
     event_loop::state::{SctkDragOffer, SctkSelectionOffer, SctkState},
-    sctk_event::{DndOfferEvent, SctkEvent},
+    sctk_event::{DndOfferEvent, SctkEvent, SelectionKind},
 };
 
 impl<T> DataDeviceHandler for SctkState<T> {
@@ -116,10 +116,10 @@ impl<T> DataDeviceHandler for SctkState<T> {
             let mime_types = offer.with_mime_types(|types| types.to_vec());
             self.sctk_events.push(SctkEvent::SelectionOffer(
                 crate::sctk_event::SelectionOfferEvent::Offer(mime_types),
+                SelectionKind::Clipboard,
             ));
             self.selection_offer = Some(SctkSelectionOffer {
                 offer: offer.clone(),
-                cur_read: None,
             });
         }
     }
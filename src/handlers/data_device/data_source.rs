@@ -1,3 +1,4 @@
+use super::pipe::write_pipe;
 use crate::event_loop::state::SctkState;
 use crate::sctk_event::{DataSourceEvent, SctkEvent};
 use sctk::data_device_manager::WritePipe;
@@ -13,7 +14,7 @@ use sctk::{
 use std::io::{BufWriter, Write};
 use tracing::error;
 
-impl<T> DataSourceHandler for SctkState<T> {
+impl<T: 'static> DataSourceHandler for SctkState<T> {
     fn accept_mime(
         &mut self,
         _conn: &Connection,
@@ -59,67 +60,11 @@ impl<T> DataSourceHandler for SctkState<T> {
 
         if let Some(my_source) = self
             .selection_source
-            .as_mut()
+            .as_ref()
             .filter(|s| s.source.inner() == source)
         {
-            match self.loop_handle.insert_source(pipe, move |_, f, state| {
-                let loop_handle = &state.loop_handle;
-                let selection_source = match state.selection_source.as_mut() {
-                    Some(s) => s,
-                    None => return,
-                };
-                let (data, mut cur_index, token) =
-                    match selection_source.cur_write.take() {
-                        Some(s) => s,
-                        None => return,
-                    };
-                let mut writer = BufWriter::new(f.as_ref());
-                let slice = &data.as_slice()[cur_index
-                    ..(cur_index + writer.capacity()).min(data.len())];
-                match writer.write(slice) {
-                    Ok(num_written) => {
-                        cur_index += num_written;
-                        if cur_index == data.len() {
-                            loop_handle.remove(token);
-                        } else {
-                            selection_source.cur_write =
-                                Some((data, cur_index, token));
-                        }
-                        if let Err(err) = writer.flush() {
-                            loop_handle.remove(token);
-                            error!("Failed to flush pipe: {}", err);
-                        }
-                    }
-                    Err(e)
-                        if matches!(
-                            e.kind(),
-                            std::io::ErrorKind::Interrupted
-                        ) =>
-                    {
-                        // try again
-                        selection_source.cur_write =
-                            Some((data, cur_index, token));
-                    }
-                    Err(_) => {
-                        loop_handle.remove(token);
-                        error!("Failed to write to pipe");
-                    }
-                };
-            }) {
-                Ok(s) => {
-                    my_source.cur_write = Some((
-                        my_source
-                            .data
-                            .from_mime_type(&mime)
-                            .unwrap_or_default(),
-                        0,
-                        s,
-                    ));
-                }
-                Err(err) => {
-                    error!("Failed to insert source: {}", err);
-                }
-            }
+            let data = my_source.data.from_mime_type(&mime).unwrap_or_default();
+            write_pipe(&self.loop_handle, pipe, data);
         } else if let Some(source) = self.dnd_source.as_mut().filter(|s| {
             s.source
                 .as_ref()
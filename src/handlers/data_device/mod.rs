@@ -2,8 +2,11 @@ use crate::handlers::SctkState;
 use sctk::delegate_data_device;
 use std::fmt::Debug;
 
+pub mod data_control;
 pub mod data_device;
 pub mod data_offer;
 pub mod data_source;
+pub(crate) mod pipe;
+pub mod primary_selection;
 
 delegate_data_device!(@<T: 'static + Debug> SctkState<T>);
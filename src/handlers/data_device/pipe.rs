@@ -0,0 +1,114 @@
+//! Non-blocking transfers through selection pipes.
+//!
+//! Pipes are made non-blocking and registered with the event loop, a chunk
+//! is read or written whenever they are ready. Large payloads, e.g. images,
+//! never block the loop.
+use std::{
+    cell::Cell,
+    io::{self, Read, Write},
+    os::fd::AsFd,
+    rc::Rc,
+};
+
+use sctk::{
+    data_device_manager::{ReadPipe, WritePipe},
+    reexports::calloop::{LoopHandle, RegistrationToken},
+};
+use tracing::error;
+
+use crate::event_loop::state::SctkState;
+
+const CHUNK_SIZE: usize = 1 << 16;
+
+/// Selections larger than this are not read.
+pub(crate) const MAX_SELECTION_LEN: usize = 1 << 20;
+
+/// Read `pipe` to the end and hand the data to `done`. Reading stops without
+/// calling `done` if the data is longer than `max_len` or fails.
+pub(crate) fn read_pipe<T: 'static>(
+    loop_handle: &LoopHandle<'static, SctkState<T>>,
+    pipe: ReadPipe,
+    max_len: usize,
+    done: impl FnOnce(&mut SctkState<T>, Vec<u8>) + 'static,
+) {
+    if let Err(err) = rustix::io::ioctl_fionbio(pipe.as_fd(), true) {
+        error!("Failed to make pipe non-blocking: {}", err);
+    }
+    let token: Rc<Cell<Option<RegistrationToken>>> = Rc::new(Cell::new(None));
+    let source_token = token.clone();
+    let mut data = Vec::new();
+    let mut done = Some(done);
+    match loop_handle.insert_source(pipe, move |_, f, state| {
+        let mut file = f.as_ref();
+        let mut buf = [0; CHUNK_SIZE];
+        loop {
+            match file.read(&mut buf) {
+                Ok(0) => {
+                    remove(state, &source_token);
+                    if let Some(done) = done.take() {
+                        done(state, std::mem::take(&mut data));
+                    }
+                    return;
+                }
+                Ok(n) if data.len() + n > max_len => {
+                    remove(state, &source_token);
+                    return;
+                }
+                Ok(n) => data.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return,
+                Err(e) => {
+                    error!("Error reading selection data: {}", e);
+                    remove(state, &source_token);
+                    return;
+                }
+            }
+        }
+    }) {
+        Ok(t) => token.set(Some(t)),
+        Err(err) => error!("Failed to read from pipe: {}", err.error),
+    }
+}
+
+/// Write `data` to `pipe` and close it.
+pub(crate) fn write_pipe<T: 'static>(
+    loop_handle: &LoopHandle<'static, SctkState<T>>,
+    pipe: WritePipe,
+    data: Vec<u8>,
+) {
+    if let Err(err) = rustix::io::ioctl_fionbio(pipe.as_fd(), true) {
+        error!("Failed to make pipe non-blocking: {}", err);
+    }
+    let token: Rc<Cell<Option<RegistrationToken>>> = Rc::new(Cell::new(None));
+    let source_token = token.clone();
+    let mut cur_index = 0;
+    match loop_handle.insert_source(pipe, move |_, f, state| {
+        let mut file = f.as_ref();
+        while cur_index < data.len() {
+            let end = (cur_index + CHUNK_SIZE).min(data.len());
+            match file.write(&data[cur_index..end]) {
+                Ok(n) => cur_index += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return,
+                Err(e) => {
+                    error!("Failed to write to pipe: {}", e);
+                    break;
+                }
+            }
+        }
+        // Dropping the source closes the pipe, which ends the transfer.
+        remove(state, &source_token);
+    }) {
+        Ok(t) => token.set(Some(t)),
+        Err(err) => error!("Failed to write to pipe: {}", err.error),
+    }
+}
+
+fn remove<T>(
+    state: &mut SctkState<T>,
+    token: &Cell<Option<RegistrationToken>>,
+) {
+    if let Some(token) = token.take() {
+        state.loop_handle.remove(token);
+    }
+}
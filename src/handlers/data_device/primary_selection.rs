@@ -0,0 +1,291 @@
+//! The primary selection through `zwp_primary_selection_device_manager_v1`.
+//!
+//! The primary selection holds the last selected text and is usually pasted
+//! with a middle click. Offers are reported as
+//! [`SctkEvent::SelectionOffer`] with [`SelectionKind::Primary`].
+use std::{
+    fmt::Debug, fs::File, marker::PhantomData, os::fd::AsFd, sync::Mutex,
+};
+
+use iced_runtime::command::platform_specific::wayland::data_device::DataFromMimeType;
+use rustix::pipe::{pipe_with, PipeFlags};
+use sctk::{
+    data_device_manager::{ReadPipe, WritePipe},
+    globals::GlobalData,
+    reexports::client::{
+        delegate_dispatch, event_created_child,
+        globals::{BindError, GlobalList},
+        protocol::wl_seat::WlSeat,
+        Connection, Dispatch, Proxy, QueueHandle,
+    },
+};
+use tracing::error;
+use wayland_protocols::wp::primary_selection::zv1::client::{
+    zwp_primary_selection_device_manager_v1::ZwpPrimarySelectionDeviceManagerV1,
+    zwp_primary_selection_device_v1::{self, ZwpPrimarySelectionDeviceV1},
+    zwp_primary_selection_offer_v1::{self, ZwpPrimarySelectionOfferV1},
+    zwp_primary_selection_source_v1::{self, ZwpPrimarySelectionSourceV1},
+};
+
+use super::pipe::{read_pipe, write_pipe, MAX_SELECTION_LEN};
+use crate::{
+    event_loop::state::{SctkPrimarySelectionSource, SctkState},
+    sctk_event::{SctkEvent, SelectionKind, SelectionOfferEvent},
+};
+
+#[derive(Debug)]
+pub struct PrimarySelectionManager<T> {
+    manager: ZwpPrimarySelectionDeviceManagerV1,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: 'static> PrimarySelectionManager<T> {
+    pub fn new(
+        globals: &GlobalList,
+        queue_handle: &QueueHandle<SctkState<T>>,
+    ) -> Result<Self, BindError> {
+        let manager = globals.bind(queue_handle, 1..=1, GlobalData)?;
+        Ok(Self {
+            manager,
+            _phantom: PhantomData,
+        })
+    }
+
+    pub fn primary_selection_device(
+        &self,
+        seat: &WlSeat,
+        queue_handle: &QueueHandle<SctkState<T>>,
+    ) -> ZwpPrimarySelectionDeviceV1 {
+        self.manager
+            .get_device(seat, queue_handle, PrimarySelectionDevice {})
+    }
+
+    pub fn create_source(
+        &self,
+        mime_types: &[String],
+        queue_handle: &QueueHandle<SctkState<T>>,
+    ) -> ZwpPrimarySelectionSourceV1 {
+        let source = self.manager.create_source(queue_handle, GlobalData);
+        for mime_type in mime_types {
+            source.offer(mime_type.clone());
+        }
+        source
+    }
+}
+
+#[derive(Debug)]
+pub struct PrimarySelectionDevice {}
+
+#[derive(Debug, Default)]
+pub struct PrimarySelectionOffer {
+    mime_types: Mutex<Vec<String>>,
+}
+
+impl PrimarySelectionOffer {
+    pub fn mime_types(&self) -> Vec<String> {
+        self.mime_types.lock().unwrap().clone()
+    }
+}
+
+impl<T: 'static>
+    Dispatch<ZwpPrimarySelectionDeviceManagerV1, GlobalData, SctkState<T>>
+    for PrimarySelectionManager<T>
+{
+    fn event(
+        _: &mut SctkState<T>,
+        _: &ZwpPrimarySelectionDeviceManagerV1,
+        _: <ZwpPrimarySelectionDeviceManagerV1 as Proxy>::Event,
+        _: &GlobalData,
+        _: &Connection,
+        _: &QueueHandle<SctkState<T>>,
+    ) {
+        // No events.
+    }
+}
+
+impl<T: 'static>
+    Dispatch<ZwpPrimarySelectionDeviceV1, PrimarySelectionDevice, SctkState<T>>
+    for PrimarySelectionManager<T>
+{
+    fn event(
+        state: &mut SctkState<T>,
+        _: &ZwpPrimarySelectionDeviceV1,
+        event: <ZwpPrimarySelectionDeviceV1 as Proxy>::Event,
+        _: &PrimarySelectionDevice,
+        _: &Connection,
+        _: &QueueHandle<SctkState<T>>,
+    ) {
+        match event {
+            zwp_primary_selection_device_v1::Event::DataOffer { .. } => {
+                // Mime types arrive on the offer before it is announced.
+            }
+            zwp_primary_selection_device_v1::Event::Selection { id } => {
+                if let Some(old) = state.primary_selection_offer.take() {
+                    if Some(&old) != id.as_ref() {
+                        old.destroy();
+                    }
+                }
+                if let Some(offer) = id {
+                    let mime_types = offer
+                        .data::<PrimarySelectionOffer>()
+                        .map(PrimarySelectionOffer::mime_types)
+                        .unwrap_or_default();
+                    state.sctk_events.push(SctkEvent::SelectionOffer(
+                        SelectionOfferEvent::Offer(mime_types),
+                        SelectionKind::Primary,
+                    ));
+                    state.primary_selection_offer = Some(offer);
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    event_created_child!(SctkState<T>, ZwpPrimarySelectionDeviceV1, [
+        zwp_primary_selection_device_v1::EVT_DATA_OFFER_OPCODE => (ZwpPrimarySelectionOfferV1, PrimarySelectionOffer::default())
+    ]);
+}
+
+impl<T: 'static>
+    Dispatch<ZwpPrimarySelectionOfferV1, PrimarySelectionOffer, SctkState<T>>
+    for PrimarySelectionManager<T>
+{
+    fn event(
+        _: &mut SctkState<T>,
+        _: &ZwpPrimarySelectionOfferV1,
+        event: <ZwpPrimarySelectionOfferV1 as Proxy>::Event,
+        data: &PrimarySelectionOffer,
+        _: &Connection,
+        _: &QueueHandle<SctkState<T>>,
+    ) {
+        match event {
+            zwp_primary_selection_offer_v1::Event::Offer { mime_type } => {
+                data.mime_types.lock().unwrap().push(mime_type);
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<T: 'static>
+    Dispatch<ZwpPrimarySelectionSourceV1, GlobalData, SctkState<T>>
+    for PrimarySelectionManager<T>
+{
+    fn event(
+        state: &mut SctkState<T>,
+        source: &ZwpPrimarySelectionSourceV1,
+        event: <ZwpPrimarySelectionSourceV1 as Proxy>::Event,
+        _: &GlobalData,
+        _: &Connection,
+        _: &QueueHandle<SctkState<T>>,
+    ) {
+        let is_active_source = state
+            .primary_selection_source
+            .as_ref()
+            .map(|s| &s.source == source)
+            .unwrap_or(false);
+        match event {
+            zwp_primary_selection_source_v1::Event::Send { mime_type, fd } => {
+                let data = match state.primary_selection_source.as_ref() {
+                    Some(s) if is_active_source => {
+                        s.data.from_mime_type(&mime_type).unwrap_or_default()
+                    }
+                    _ => return,
+                };
+                write_pipe(
+                    &state.loop_handle,
+                    WritePipe::from(File::from(fd)),
+                    data,
+                );
+            }
+            zwp_primary_selection_source_v1::Event::Cancelled => {
+                if is_active_source {
+                    state.primary_selection_source = None;
+                }
+                source.destroy();
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+delegate_dispatch!(@<T: 'static> SctkState<T>: [ZwpPrimarySelectionDeviceManagerV1: GlobalData] => PrimarySelectionManager<T>);
+delegate_dispatch!(@<T: 'static> SctkState<T>: [ZwpPrimarySelectionDeviceV1: PrimarySelectionDevice] => PrimarySelectionManager<T>);
+delegate_dispatch!(@<T: 'static> SctkState<T>: [ZwpPrimarySelectionOfferV1: PrimarySelectionOffer] => PrimarySelectionManager<T>);
+delegate_dispatch!(@<T: 'static> SctkState<T>: [ZwpPrimarySelectionSourceV1: GlobalData] => PrimarySelectionManager<T>);
+
+impl<T> SctkState<T>
+where
+    T: 'static + Debug,
+{
+    /// Offer `data` as the primary selection of the first seat.
+    pub(crate) fn set_primary_selection(
+        &mut self,
+        mime_types: Vec<String>,
+        data: Box<dyn DataFromMimeType + Send + Sync>,
+    ) {
+        let Some(manager) = self.primary_selection_manager.as_ref() else {
+            return;
+        };
+        let Some(seat) = self.seats.first() else {
+            return;
+        };
+        let (Some(device), Some(serial)) =
+            (seat.primary_selection_device.as_ref(), seat.last_input_serial)
+        else {
+            return;
+        };
+        let source = manager.create_source(&mime_types, &self.queue_handle);
+        device.set_selection(Some(&source), serial);
+        if let Some(old) = self.primary_selection_source.replace(
+            SctkPrimarySelectionSource { source, data },
+        ) {
+            old.source.destroy();
+        }
+    }
+
+    /// Clear the primary selection of the first seat.
+    pub(crate) fn unset_primary_selection(&mut self) {
+        let Some(seat) = self.seats.first() else {
+            return;
+        };
+        let (Some(device), Some(serial)) =
+            (seat.primary_selection_device.as_ref(), seat.last_input_serial)
+        else {
+            return;
+        };
+        device.set_selection(None, serial);
+        if let Some(old) = self.primary_selection_source.take() {
+            old.source.destroy();
+        }
+    }
+
+    /// Read the primary selection as `mime_type`. The data is reported once
+    /// complete, selections larger than [`MAX_SELECTION_LEN`] are dropped.
+    pub(crate) fn request_primary_selection(&mut self, mime_type: String) {
+        let Some(offer) = self.primary_selection_offer.as_ref() else {
+            return;
+        };
+        let (read, write) = match pipe_with(PipeFlags::CLOEXEC) {
+            Ok(pipe) => pipe,
+            Err(err) => {
+                error!("Failed to create pipe for primary selection: {}", err);
+                return;
+            }
+        };
+        offer.receive(mime_type.clone(), write.as_fd());
+        drop(write);
+
+        read_pipe(
+            &self.loop_handle,
+            ReadPipe::from(File::from(read)),
+            MAX_SELECTION_LEN,
+            move |state, data| {
+                state.sctk_events.push(SctkEvent::SelectionOffer(
+                    SelectionOfferEvent::Data { mime_type, data },
+                    SelectionKind::Primary,
+                ));
+            },
+        );
+    }
+}
@@ -0,0 +1,287 @@
+//! Toplevel tracking through `zwlr_foreign_toplevel_manager_v1` or
+//! `ext_foreign_toplevel_list_v1`.
+//!
+//! The input method protocol does not say which client a text input belongs
+//! to, the focused toplevel is the closest hint. The wlr protocol is
+//! preferred since only it reports which toplevel is activated, the ext list
+//! is bound when it is missing.
+use std::{marker::PhantomData, sync::Mutex};
+
+use iced_futures::core::event::wayland::ForeignToplevel;
+use sctk::{
+    globals::GlobalData,
+    reexports::client::{
+        delegate_dispatch, event_created_child,
+        globals::{BindError, GlobalList},
+        Connection, Dispatch, Proxy, QueueHandle,
+    },
+};
+use wayland_protocols::ext::foreign_toplevel_list::v1::client::{
+    ext_foreign_toplevel_handle_v1::{self, ExtForeignToplevelHandleV1},
+    ext_foreign_toplevel_list_v1::{self, ExtForeignToplevelListV1},
+};
+use wayland_protocols_wlr::foreign_toplevel::v1::client::{
+    zwlr_foreign_toplevel_handle_v1::{
+        self, State as ToplevelState, ZwlrForeignToplevelHandleV1,
+    },
+    zwlr_foreign_toplevel_manager_v1::{self, ZwlrForeignToplevelManagerV1},
+};
+
+use crate::{
+    event_loop::state::SctkState,
+    sctk_event::{ForeignToplevelEvent, SctkEvent},
+};
+
+#[derive(Debug)]
+pub struct ForeignToplevelManager<T> {
+    list: Option<ExtForeignToplevelListV1>,
+    manager: Option<ZwlrForeignToplevelManagerV1>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: 'static> ForeignToplevelManager<T> {
+    pub fn new(
+        globals: &GlobalList,
+        queue_handle: &QueueHandle<SctkState<T>>,
+    ) -> Result<Self, BindError> {
+        let (list, manager) =
+            match globals.bind(queue_handle, 1..=3, GlobalData) {
+                Ok(manager) => (None, Some(manager)),
+                Err(_) => {
+                    (Some(globals.bind(queue_handle, 1..=1, GlobalData)?), None)
+                }
+            };
+        Ok(Self {
+            list,
+            manager,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Whether toplevels are still reported.
+    pub fn is_tracking(&self) -> bool {
+        self.list.is_some() || self.manager.is_some()
+    }
+}
+
+/// The state of a toplevel received since the last `done`.
+#[derive(Debug, Default)]
+pub struct ForeignToplevelData {
+    pending: Mutex<ForeignToplevel>,
+}
+
+impl<T: 'static> SctkState<T> {
+    fn foreign_toplevel_done(&mut self, toplevel: ForeignToplevel) {
+        match self
+            .foreign_toplevels
+            .iter_mut()
+            .find(|t| t.id == toplevel.id)
+        {
+            Some(known) => {
+                if *known != toplevel {
+                    *known = toplevel.clone();
+                    self.sctk_events.push(SctkEvent::ForeignToplevel(
+                        ForeignToplevelEvent::Changed(toplevel),
+                    ));
+                }
+            }
+            None => {
+                self.foreign_toplevels.push(toplevel.clone());
+                self.sctk_events.push(SctkEvent::ForeignToplevel(
+                    ForeignToplevelEvent::Opened(toplevel),
+                ));
+            }
+        }
+    }
+
+    fn foreign_toplevel_closed(&mut self, id: u32) {
+        if let Some(i) = self.foreign_toplevels.iter().position(|t| t.id == id)
+        {
+            let toplevel = self.foreign_toplevels.remove(i);
+            self.sctk_events.push(SctkEvent::ForeignToplevel(
+                ForeignToplevelEvent::Closed(toplevel),
+            ));
+        }
+    }
+
+    /// Deliver the open toplevels as `ForeignToplevelEvent::List`.
+    pub fn request_foreign_toplevels(&mut self) {
+        if !self
+            .foreign_toplevel_manager
+            .as_ref()
+            .map_or(false, |m| m.is_tracking())
+        {
+            return;
+        }
+        self.sctk_events.push(SctkEvent::ForeignToplevel(
+            ForeignToplevelEvent::List(self.foreign_toplevels.clone()),
+        ));
+    }
+}
+
+impl<T: 'static> Dispatch<ExtForeignToplevelListV1, GlobalData, SctkState<T>>
+    for ForeignToplevelManager<T>
+{
+    fn event(
+        state: &mut SctkState<T>,
+        list: &ExtForeignToplevelListV1,
+        event: <ExtForeignToplevelListV1 as Proxy>::Event,
+        _: &GlobalData,
+        _: &Connection,
+        _: &QueueHandle<SctkState<T>>,
+    ) {
+        match event {
+            ext_foreign_toplevel_list_v1::Event::Toplevel { .. } => {
+                // The handle announces itself with `done`.
+            }
+            ext_foreign_toplevel_list_v1::Event::Finished => {
+                if let Some(manager) = state.foreign_toplevel_manager.as_mut() {
+                    manager.list = None;
+                }
+                list.destroy();
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    event_created_child!(SctkState<T>, ExtForeignToplevelListV1, [
+        ext_foreign_toplevel_list_v1::EVT_TOPLEVEL_OPCODE => (ExtForeignToplevelHandleV1, ForeignToplevelData::default())
+    ]);
+}
+
+impl<T: 'static>
+    Dispatch<ExtForeignToplevelHandleV1, ForeignToplevelData, SctkState<T>>
+    for ForeignToplevelManager<T>
+{
+    fn event(
+        state: &mut SctkState<T>,
+        handle: &ExtForeignToplevelHandleV1,
+        event: <ExtForeignToplevelHandleV1 as Proxy>::Event,
+        data: &ForeignToplevelData,
+        _: &Connection,
+        _: &QueueHandle<SctkState<T>>,
+    ) {
+        let mut pending = data.pending.lock().unwrap();
+        match event {
+            ext_foreign_toplevel_handle_v1::Event::Title { title } => {
+                pending.title = title;
+            }
+            ext_foreign_toplevel_handle_v1::Event::AppId { app_id } => {
+                pending.app_id = app_id;
+            }
+            ext_foreign_toplevel_handle_v1::Event::Identifier {
+                identifier,
+            } => {
+                pending.identifier = Some(identifier);
+            }
+            ext_foreign_toplevel_handle_v1::Event::Done => {
+                pending.id = handle.id().protocol_id();
+                state.foreign_toplevel_done(pending.clone());
+            }
+            ext_foreign_toplevel_handle_v1::Event::Closed => {
+                state.foreign_toplevel_closed(handle.id().protocol_id());
+                handle.destroy();
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<T: 'static>
+    Dispatch<ZwlrForeignToplevelManagerV1, GlobalData, SctkState<T>>
+    for ForeignToplevelManager<T>
+{
+    fn event(
+        state: &mut SctkState<T>,
+        _: &ZwlrForeignToplevelManagerV1,
+        event: <ZwlrForeignToplevelManagerV1 as Proxy>::Event,
+        _: &GlobalData,
+        _: &Connection,
+        _: &QueueHandle<SctkState<T>>,
+    ) {
+        match event {
+            zwlr_foreign_toplevel_manager_v1::Event::Toplevel { .. } => {
+                // The handle announces itself with `done`.
+            }
+            zwlr_foreign_toplevel_manager_v1::Event::Finished => {
+                // The compositor destroys the manager.
+                if let Some(manager) = state.foreign_toplevel_manager.as_mut() {
+                    manager.manager = None;
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    event_created_child!(SctkState<T>, ZwlrForeignToplevelManagerV1, [
+        zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE => (ZwlrForeignToplevelHandleV1, ForeignToplevelData::default())
+    ]);
+}
+
+impl<T: 'static>
+    Dispatch<ZwlrForeignToplevelHandleV1, ForeignToplevelData, SctkState<T>>
+    for ForeignToplevelManager<T>
+{
+    fn event(
+        state: &mut SctkState<T>,
+        handle: &ZwlrForeignToplevelHandleV1,
+        event: <ZwlrForeignToplevelHandleV1 as Proxy>::Event,
+        data: &ForeignToplevelData,
+        _: &Connection,
+        _: &QueueHandle<SctkState<T>>,
+    ) {
+        let mut pending = data.pending.lock().unwrap();
+        match event {
+            zwlr_foreign_toplevel_handle_v1::Event::Title { title } => {
+                pending.title = title;
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::AppId { app_id } => {
+                pending.app_id = app_id;
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::State { state } => {
+                pending.activated = false;
+                pending.maximized = false;
+                pending.minimized = false;
+                pending.fullscreen = false;
+                // An array of native endian u32 states.
+                for value in state.chunks_exact(4) {
+                    let value = u32::from_ne_bytes([
+                        value[0], value[1], value[2], value[3],
+                    ]);
+                    match ToplevelState::try_from(value) {
+                        Ok(ToplevelState::Activated) => {
+                            pending.activated = true
+                        }
+                        Ok(ToplevelState::Maximized) => {
+                            pending.maximized = true
+                        }
+                        Ok(ToplevelState::Minimized) => {
+                            pending.minimized = true
+                        }
+                        Ok(ToplevelState::Fullscreen) => {
+                            pending.fullscreen = true
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::Done => {
+                pending.id = handle.id().protocol_id();
+                state.foreign_toplevel_done(pending.clone());
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::Closed => {
+                state.foreign_toplevel_closed(handle.id().protocol_id());
+                handle.destroy();
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::OutputEnter { .. }
+            | zwlr_foreign_toplevel_handle_v1::Event::OutputLeave { .. }
+            | zwlr_foreign_toplevel_handle_v1::Event::Parent { .. } => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+delegate_dispatch!(@<T: 'static> SctkState<T>: [ExtForeignToplevelListV1: GlobalData] => ForeignToplevelManager<T>);
+delegate_dispatch!(@<T: 'static> SctkState<T>: [ExtForeignToplevelHandleV1: ForeignToplevelData] => ForeignToplevelManager<T>);
+delegate_dispatch!(@<T: 'static> SctkState<T>: [ZwlrForeignToplevelManagerV1: GlobalData] => ForeignToplevelManager<T>);
+delegate_dispatch!(@<T: 'static> SctkState<T>: [ZwlrForeignToplevelHandleV1: ForeignToplevelData] => ForeignToplevelManager<T>);
@@ -0,0 +1,137 @@
+//! Showing a layer surface while a text input is active.
+//!
+//! The surface is created and destroyed through the same user events as the
+//! layer surface commands, so the application sees the usual `Created` and
+//! `Done` events for it.
+use std::{fmt::Debug, marker::PhantomData, time::Duration};
+
+use iced_runtime::command::platform_specific::wayland::layer_surface::{
+    self, SctkLayerSurfaceSettings,
+};
+use sctk::reexports::calloop::{
+    timer::{TimeoutAction, Timer},
+    RegistrationToken,
+};
+use tracing::error;
+
+use crate::{application::Event, event_loop::state::SctkState};
+
+/// A layer surface shown while a text input is active.
+#[derive(Debug)]
+pub(crate) struct AutoShow {
+    builder: SctkLayerSurfaceSettings,
+    debounce: Duration,
+    /// Visibility forced by the application.
+    visible: Option<bool>,
+    /// The pending debounce timer.
+    timer: Option<RegistrationToken>,
+}
+
+impl<T> SctkState<T>
+where
+    T: 'static + Debug,
+{
+    /// Start managing `builder`, or stop if `None`.
+    pub(crate) fn set_auto_show(
+        &mut self,
+        builder: Option<SctkLayerSurfaceSettings>,
+        debounce: Duration,
+    ) {
+        if let Some(old) = self.auto_show.take() {
+            if let Some(token) = old.timer {
+                self.loop_handle.remove(token);
+            }
+            if self.is_layer_surface_mapped(&old.builder) {
+                self.pending_user_events.push(Event::LayerSurface(
+                    layer_surface::Action::Destroy(old.builder.id),
+                ));
+            }
+        }
+        let Some(mut builder) = builder else {
+            return;
+        };
+        if builder.exclusive_zone == 0 {
+            if let Some((_, Some(height))) = builder.size {
+                builder.exclusive_zone = height as i32;
+            }
+        }
+        self.auto_show = Some(AutoShow {
+            builder,
+            debounce,
+            visible: None,
+            timer: None,
+        });
+        self.schedule_auto_show(Duration::ZERO);
+    }
+
+    /// Force the visibility of the managed surface, `None` follows the text
+    /// input again. Takes effect without debouncing.
+    pub(crate) fn set_auto_show_visible(&mut self, visible: Option<bool>) {
+        if let Some(auto_show) = self.auto_show.as_mut() {
+            auto_show.visible = visible;
+            self.schedule_auto_show(Duration::ZERO);
+        }
+    }
+
+    /// The text input was activated or deactivated.
+    pub(crate) fn input_method_activation_changed(&mut self) {
+        if let Some(debounce) = self.auto_show.as_ref().map(|a| a.debounce) {
+            self.schedule_auto_show(debounce);
+        }
+    }
+
+    /// Update the surface once `delay` passed without another change.
+    fn schedule_auto_show(&mut self, delay: Duration) {
+        let Some(auto_show) = self.auto_show.as_mut() else {
+            return;
+        };
+        if let Some(token) = auto_show.timer.take() {
+            self.loop_handle.remove(token);
+        }
+        // The timer also wakes the event loop, which then handles the
+        // pending user events.
+        match self.loop_handle.insert_source(
+            Timer::from_duration(delay),
+            |_, _, state| {
+                if let Some(auto_show) = state.auto_show.as_mut() {
+                    auto_show.timer = None;
+                }
+                state.apply_auto_show();
+                TimeoutAction::Drop
+            },
+        ) {
+            Ok(token) => auto_show.timer = Some(token),
+            Err(err) => {
+                error!("Failed to schedule auto show: {}", err.error)
+            }
+        }
+    }
+
+    fn apply_auto_show(&mut self) {
+        let Some(auto_show) = self.auto_show.as_ref() else {
+            return;
+        };
+        let show = auto_show.visible.unwrap_or(self.input_method_active);
+        // The application may have destroyed the surface itself.
+        let mapped = self.is_layer_surface_mapped(&auto_show.builder);
+        if show && !mapped {
+            self.pending_user_events.push(Event::LayerSurface(
+                layer_surface::Action::LayerSurface {
+                    builder: auto_show.builder.clone(),
+                    _phantom: PhantomData,
+                },
+            ));
+        } else if !show && mapped {
+            self.pending_user_events.push(Event::LayerSurface(
+                layer_surface::Action::Destroy(auto_show.builder.id),
+            ));
+        }
+    }
+
+    fn is_layer_surface_mapped(
+        &self,
+        builder: &SctkLayerSurfaceSettings,
+    ) -> bool {
+        self.layer_surfaces.iter().any(|l| l.id == builder.id)
+    }
+}
@@ -1,6 +1,6 @@
 use std::{
-    env, fmt::Debug, marker::PhantomData, num::NonZeroU32,
-    os::unix::io::AsRawFd, sync::Mutex,
+    env, ffi::OsString, fmt::Debug, fs::File, marker::PhantomData,
+    num::NonZeroU32, os::unix::io::AsRawFd, sync::Mutex,
 };
 #[cfg(feature = "calloop")]
 use std::{sync::Arc, time::Duration};
@@ -27,6 +27,7 @@ use wayland_protocols_misc::zwp_input_method_v2::client::{
 };
 
 use crate::handlers::input_method::InputMethod;
+use crate::settings::ComposeSettings;
 
 #[cfg(feature = "calloop")]
 pub(crate) struct RepeatedKey {
@@ -141,7 +142,7 @@ impl InputMethod {
             callback,
             repeat_token: None,
         });
-        kbd_data.init_compose();
+        kbd_data.ensure_compose();
 
         input_method.grab_keyboard(qh, udata)
     }
@@ -189,6 +190,8 @@ impl InputMethod {
             + 'static,
         U: InputMethodKeyboardDataExt + 'static,
     {
+        udata.keyboard_data().ensure_compose();
+
         input_method.grab_keyboard(qh, udata)
     }
 }
@@ -202,6 +205,17 @@ impl<'a> Keymap<'a> {
     pub fn as_string(&self) -> String {
         self.0.get_as_string(xkb::KEYMAP_FORMAT_TEXT_V1)
     }
+
+    /// The names of the layouts, indexed by XKB group.
+    pub fn layouts(&self) -> Vec<String> {
+        layout_names(self.0)
+    }
+}
+
+fn layout_names(keymap: &xkb::Keymap) -> Vec<String> {
+    (0..keymap.num_layouts())
+        .map(|layout| keymap.layout_get_name(layout).to_string())
+        .collect()
 }
 
 /// Handler trait for keyboard input.
@@ -259,6 +273,43 @@ pub trait InputMethodKeyboardHandler: Sized {
     ) {
     }
 
+    /// A key press advanced, completed or cancelled a compose sequence.
+    ///
+    /// This is called before the corresponding [`press_key`](Self::press_key)
+    /// and does nothing by default.
+    fn update_compose(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _keyboard: &ZwpInputMethodKeyboardGrabV2,
+        _status: ComposeStatus,
+    ) {
+    }
+
+    /// Whether keys should pass through without composing, e.g. in a
+    /// password field. A compose sequence in progress is dropped.
+    ///
+    /// Returns `false` by default.
+    fn bypass_composition(&self) -> bool {
+        false
+    }
+
+    /// The effective layout, the XKB group, has changed.
+    ///
+    /// `name` is the name of the layout in the keymap, see
+    /// [`Keymap::layouts`]. This is called after
+    /// [`update_modifiers`](Self::update_modifiers) and does nothing by
+    /// default.
+    fn update_layout(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _keyboard: &ZwpInputMethodKeyboardGrabV2,
+        _index: u32,
+        _name: String,
+    ) {
+    }
+
     /// Keyboard keymap has been updated.
     ///
     /// `keymap.as_string()` can be used get the keymap as a string. It cannot be exposed directly
@@ -281,7 +332,12 @@ pub struct InputMethodKeyboardData<T> {
     /// If the user manually specified the RMLVO to use.
     user_specified_rmlvo: bool,
     xkb_state: Mutex<Option<xkb::State>>,
+    /// The effective layout last reported to the handler.
+    layout: Mutex<Option<u32>>,
     xkb_compose: Mutex<Option<xkb::compose::State>>,
+    /// Keysyms fed into the compose state since the last finished sequence.
+    compose_sequence: Mutex<Vec<Keysym>>,
+    compose_settings: Mutex<ComposeSettings>,
     #[cfg(feature = "calloop")]
     repeat_data: Arc<Mutex<Option<RepeatData<T>>>>,
     _phantom_data: PhantomData<T>,
@@ -322,19 +378,18 @@ unsafe impl<T> Sync for InputMethodKeyboardData<T> {}
 impl<T> InputMethodKeyboardData<T> {
     pub fn new() -> Self {
         let xkb_context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
-        let udata = InputMethodKeyboardData {
+        InputMethodKeyboardData {
             xkb_context: Mutex::new(xkb_context),
             xkb_state: Mutex::new(None),
             user_specified_rmlvo: false,
+            layout: Mutex::new(None),
             xkb_compose: Mutex::new(None),
+            compose_sequence: Mutex::new(Vec::new()),
+            compose_settings: Mutex::new(ComposeSettings::default()),
             #[cfg(feature = "calloop")]
             repeat_data: Arc::new(Mutex::new(None)),
             _phantom_data: PhantomData,
-        };
-
-        udata.init_compose();
-
-        udata
+        }
     }
 
     pub fn from_rmlvo(rmlvo: RMLVO) -> Result<Self, KeyboardError> {
@@ -359,41 +414,94 @@ impl<T> InputMethodKeyboardData<T> {
             xkb_context: Mutex::new(xkb_context),
             xkb_state: Mutex::new(xkb_state),
             user_specified_rmlvo: true,
+            layout: Mutex::new(None),
             xkb_compose: Mutex::new(None),
+            compose_sequence: Mutex::new(Vec::new()),
+            compose_settings: Mutex::new(ComposeSettings::default()),
             #[cfg(feature = "calloop")]
             repeat_data: Arc::new(Mutex::new(None)),
             _phantom_data: PhantomData,
         };
 
-        udata.init_compose();
-
         Ok(udata)
     }
 
+    /// Use `settings` for the compose table, which is compiled once the
+    /// keyboard is grabbed.
+    pub fn with_compose_settings(self, settings: ComposeSettings) -> Self {
+        *self.compose_settings.lock().unwrap() = settings;
+        self
+    }
+
+    /// Replace the compose table source and reload the table.
+    pub fn set_compose_settings(&self, settings: ComposeSettings) {
+        *self.compose_settings.lock().unwrap() = settings;
+        self.init_compose();
+    }
+
+    /// Compile the compose table unless it already is.
+    fn ensure_compose(&self) {
+        let compiled = self.xkb_compose.lock().unwrap().is_some();
+        if !compiled {
+            self.init_compose();
+        }
+    }
+
     fn init_compose(&self) {
         let xkb_context = self.xkb_context.lock().unwrap();
-
-        if let Some(locale) = env::var_os("LC_ALL")
+        let settings = self.compose_settings.lock().unwrap();
+
+        let locale = settings
+            .locale
+            .as_ref()
+            .map(OsString::from)
+            .filter(|v| !v.is_empty())
+            .or_else(|| env::var_os("LC_ALL"))
             .and_then(|v| if v.is_empty() { None } else { Some(v) })
             .or_else(|| env::var_os("LC_CTYPE"))
             .and_then(|v| if v.is_empty() { None } else { Some(v) })
             .or_else(|| env::var_os("LANG"))
             .and_then(|v| if v.is_empty() { None } else { Some(v) })
-            .unwrap_or_else(|| "C".into())
-            .to_str()
-        {
-            // TODO: Pending new release of xkbcommon to use new_from_locale with OsStr
-            if let Ok(table) = xkb::compose::Table::new_from_locale(
+            .unwrap_or_else(|| "C".into());
+        let Some(locale) = locale.to_str() else {
+            return;
+        };
+
+        let from_file = settings.file.as_ref().and_then(|path| {
+            let file = match File::open(path) {
+                Ok(file) => file,
+                Err(err) => {
+                    log::warn!(target: "sctk", "failed to open compose file {}: {}", path.display(), err);
+                    return None;
+                }
+            };
+            let table = xkb::compose::Table::new_from_file(
                 &xkb_context,
+                file,
                 locale.as_ref(),
+                xkb::compose::FORMAT_TEXT_V1,
                 xkb::compose::COMPILE_NO_FLAGS,
-            ) {
-                let compose_state = xkb::compose::State::new(
-                    &table,
-                    xkb::compose::COMPILE_NO_FLAGS,
-                );
-                *self.xkb_compose.lock().unwrap() = Some(compose_state);
+            );
+            if table.is_err() {
+                log::warn!(target: "sctk", "invalid compose file {}", path.display());
             }
+            table.ok()
+        });
+
+        // TODO: Pending new release of xkbcommon to use new_from_locale with OsStr
+        let table = from_file.or_else(|| {
+            xkb::compose::Table::new_from_locale(
+                &xkb_context,
+                locale.as_ref(),
+                xkb::compose::COMPILE_NO_FLAGS,
+            )
+            .ok()
+        });
+        if let Some(table) = table {
+            let compose_state =
+                xkb::compose::State::new(&table, xkb::compose::COMPILE_NO_FLAGS);
+            *self.xkb_compose.lock().unwrap() = Some(compose_state);
+            self.compose_sequence.lock().unwrap().clear();
         }
     }
 
@@ -504,6 +612,9 @@ where
                                             udata.xkb_state.lock().unwrap();
                                         *state_guard = Some(state);
                                     }
+                                    // Report the layout with the next
+                                    // modifiers, the names may differ.
+                                    *udata.layout.lock().unwrap() = None;
                                     data.update_keymap(
                                         conn,
                                         qh,
@@ -544,22 +655,69 @@ where
                         // We must add 8 to the keycode for any functions we pass the raw keycode into per
                         // wl_keyboard protocol.
                         let keysym = guard.key_get_one_sym((key + 8).into());
+                        let mut compose_status = None;
+                        let bypass = data.bypass_composition();
                         let utf8 = if state == wl_keyboard::KeyState::Pressed {
                             let mut compose = udata.xkb_compose.lock().unwrap();
+                            if bypass {
+                                if let Some(compose) = compose.as_mut() {
+                                    compose.reset();
+                                }
+                                let mut sequence =
+                                    udata.compose_sequence.lock().unwrap();
+                                // The app may still show the sequence.
+                                if !sequence.is_empty() {
+                                    compose_status =
+                                        Some(ComposeStatus::Cancelled(
+                                            std::mem::take(&mut *sequence),
+                                        ));
+                                }
+                            }
 
-                            match compose.as_mut() {
+                            match compose.as_mut().filter(|_| !bypass) {
                                 Some(compose) => match compose.feed(keysym) {
                                     xkb::FeedResult::Ignored => None,
-                                    xkb::FeedResult::Accepted => match compose
-                                        .status()
-                                    {
-                                        xkb::Status::Composed => compose.utf8(),
-                                        xkb::Status::Nothing => Some(
-                                            guard
-                                                .key_get_utf8((key + 8).into()),
-                                        ),
-                                        _ => None,
-                                    },
+                                    xkb::FeedResult::Accepted => {
+                                        let mut sequence = udata
+                                            .compose_sequence
+                                            .lock()
+                                            .unwrap();
+                                        match compose.status() {
+                                            xkb::Status::Composing => {
+                                                sequence.push(keysym.into());
+                                                compose_status =
+                                                    Some(ComposeStatus::Composing(
+                                                        sequence.clone(),
+                                                    ));
+                                                None
+                                            }
+                                            xkb::Status::Composed => {
+                                                sequence.push(keysym.into());
+                                                compose_status =
+                                                    Some(ComposeStatus::Composed(
+                                                        std::mem::take(
+                                                            &mut *sequence,
+                                                        ),
+                                                    ));
+                                                compose.utf8()
+                                            }
+                                            xkb::Status::Cancelled => {
+                                                sequence.push(keysym.into());
+                                                compose_status =
+                                                    Some(ComposeStatus::Cancelled(
+                                                        std::mem::take(
+                                                            &mut *sequence,
+                                                        ),
+                                                    ));
+                                                None
+                                            }
+                                            xkb::Status::Nothing => Some(
+                                                guard.key_get_utf8(
+                                                    (key + 8).into(),
+                                                ),
+                                            ),
+                                        }
+                                    }
                                 },
 
                                 // No compose
@@ -574,6 +732,12 @@ where
                         // Drop guard before calling user code.
                         drop(state_guard);
 
+                        // Report compose progress before the key itself, so
+                        // the preedit is up to date when the press arrives.
+                        if let Some(status) = compose_status {
+                            data.update_compose(conn, qh, keyboard, status);
+                        }
+
                         let event = KeyEvent {
                             time,
                             raw_code: key,
@@ -741,6 +905,18 @@ where
                     0,
                     group,
                 );
+                let effective =
+                    state.serialize_layout(xkb::STATE_LAYOUT_EFFECTIVE);
+                let layout = {
+                    let mut layout = udata.layout.lock().unwrap();
+                    (layout.replace(effective) != Some(effective)).then(|| {
+                        let name = state
+                            .get_keymap()
+                            .layout_get_name(effective)
+                            .to_string();
+                        (effective, name)
+                    })
+                };
 
                 // Update the currently repeating key if any.
                 #[cfg(feature = "calloop")]
@@ -752,7 +928,10 @@ where
                         event.key.utf8 = {
                             let mut compose = udata.xkb_compose.lock().unwrap();
 
-                            match compose.as_mut() {
+                            match compose
+                                .as_mut()
+                                .filter(|_| !data.bypass_composition())
+                            {
                                 Some(compose) => match compose
                                     .feed(event.key.keysym.into())
                                 {
@@ -795,6 +974,9 @@ where
                     modifiers,
                     raw_modifiers,
                 );
+                if let Some((index, name)) = layout {
+                    data.update_layout(conn, qh, keyboard, index, name);
+                }
             }
 
             zwp_input_method_keyboard_grab_v2::Event::RepeatInfo {
@@ -826,7 +1008,7 @@ where
     }
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct RawModifiers {
     pub mods_depressed: u32,
     pub mods_latched: u32,
@@ -855,3 +1037,37 @@ impl From<RawModifiers> for iced_futures::core::event::wayland::RawModifiers {
         }
     }
 }
+
+/// Progress of a compose sequence, e.g. a dead key or `Multi_key` sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComposeStatus {
+    /// The sequence is in progress, with the keysyms typed so far.
+    Composing(Vec<Keysym>),
+    /// The sequence is complete, the composed text is the `utf8` of the
+    /// following key press.
+    Composed(Vec<Keysym>),
+    /// The sequence does not match any entry and was dropped.
+    Cancelled(Vec<Keysym>),
+}
+
+impl From<ComposeStatus> for iced_futures::core::event::wayland::ComposeStatus {
+    fn from(value: ComposeStatus) -> Self {
+        match value {
+            ComposeStatus::Composing(keysyms) => {
+                iced_futures::core::event::wayland::ComposeStatus::Composing(
+                    keysyms,
+                )
+            }
+            ComposeStatus::Composed(keysyms) => {
+                iced_futures::core::event::wayland::ComposeStatus::Composed(
+                    keysyms,
+                )
+            }
+            ComposeStatus::Cancelled(keysyms) => {
+                iced_futures::core::event::wayland::ComposeStatus::Cancelled(
+                    keysyms,
+                )
+            }
+        }
+    }
+}
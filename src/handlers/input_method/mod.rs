@@ -1,3 +1,4 @@
+pub(crate) mod auto_show;
 pub mod keyboard;
 use std::fmt::Debug;
 use std::marker::PhantomData;
@@ -13,6 +14,9 @@ use sctk::reexports::client::{
     delegate_dispatch, Connection, Proxy, QueueHandle,
 };
 use sctk::seat::keyboard::{KeyEvent, Modifiers};
+use wayland_protocols::wp::text_input::zv3::client::zwp_text_input_v3::{
+    ChangeCause, ContentHint, ContentPurpose,
+};
 use wayland_protocols_misc::zwp_input_method_v2::client::zwp_input_method_v2;
 use wayland_protocols_misc::zwp_input_method_v2::client::zwp_input_popup_surface_v2;
 use wayland_protocols_misc::zwp_input_method_v2::client::{
@@ -26,11 +30,15 @@ use sctk::globals::GlobalData;
 
 use crate::delegate_input_method_keyboard;
 use crate::event_loop::state::SctkState;
+use crate::settings::{ComposeSettings, PrivacySettings};
 use crate::sctk_event::{
     InputMethodEventVariant, InputMethodKeyboardEventVariant, SctkEvent,
 };
 
-use self::keyboard::{InputMethodKeyboardHandler, RawModifiers};
+use self::keyboard::{
+    ComposeStatus, InputMethodKeyboardData, InputMethodKeyboardHandler,
+    Keymap, RawModifiers,
+};
 
 #[derive(Debug)]
 pub struct InputMethodManager<T> {
@@ -55,24 +63,26 @@ impl<T: 'static> InputMethodManager<T> {
         seat: &WlSeat,
         queue_handle: &QueueHandle<SctkState<T>>,
         loop_handle: LoopHandle<'static, SctkState<T>>,
-    ) -> ZwpInputMethodV2 {
+        compose_settings: &ComposeSettings,
+    ) -> (ZwpInputMethodV2, ZwpInputMethodKeyboardGrabV2) {
         let mut data = InputMethod {};
         let im =
             self.manager
                 .get_input_method(seat, queue_handle, data.clone());
-        data.grab_keyboard_with_repeat(
+        let kbd_data = InputMethodKeyboardData::new()
+            .with_compose_settings(compose_settings.clone());
+        let grab = data.grab_keyboard_with_repeat_with_data(
             queue_handle,
             &im,
-            None,
+            kbd_data,
             loop_handle,
             Box::new(move |state, _kbd: &ZwpInputMethodKeyboardGrabV2, e| {
                 state.sctk_events.push(SctkEvent::InputMethodKeyboardEvent {
                     variant: InputMethodKeyboardEventVariant::Repeat(e),
                 })
             }),
-        )
-        .expect("Input method keyboard grab failed");
-        im
+        );
+        (im, grab)
     }
 }
 
@@ -94,12 +104,108 @@ impl<T: 'static> Dispatch<ZwpInputMethodManagerV2, GlobalData, SctkState<T>>
 #[derive(Clone)]
 pub struct InputMethod {}
 
+/// Privacy of the active text input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Privacy {
+    /// Set for passwords, PINs and sensitive or hidden inputs.
+    pub private: bool,
+    /// Set for private inputs if [`PrivacySettings::bypass_composition`]
+    /// is, the compose table of the keyboard grab is skipped.
+    pub bypass_composition: bool,
+}
+
+impl Privacy {
+    /// Passwords, PINs and inputs hinted as sensitive or hidden are private.
+    pub fn new(
+        hint: ContentHint,
+        purpose: ContentPurpose,
+        settings: &PrivacySettings,
+    ) -> Self {
+        let private = matches!(
+            purpose,
+            ContentPurpose::Password | ContentPurpose::Pin
+        ) || hint
+            .intersects(ContentHint::SensitiveData | ContentHint::HiddenText);
+        Self {
+            private,
+            bypass_composition: private && settings.bypass_composition,
+        }
+    }
+}
+
+/// How input is offered for the active text input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Profile {
+    #[default]
+    Text,
+    Numeric,
+    Phone,
+    Email,
+    Url,
+    Terminal,
+}
+
+impl From<ContentPurpose> for Profile {
+    fn from(purpose: ContentPurpose) -> Self {
+        match purpose {
+            ContentPurpose::Digits
+            | ContentPurpose::Number
+            | ContentPurpose::Date
+            | ContentPurpose::Time
+            | ContentPurpose::Datetime => Profile::Numeric,
+            ContentPurpose::Phone => Profile::Phone,
+            ContentPurpose::Email => Profile::Email,
+            ContentPurpose::Url => Profile::Url,
+            ContentPurpose::Terminal => Profile::Terminal,
+            _ => Profile::Text,
+        }
+    }
+}
+
+/// Where a change of the surrounding text came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextChange {
+    Echo,
+    External,
+}
+
+impl From<TextChange> for iced_futures::core::event::wayland::TextChange {
+    fn from(value: TextChange) -> Self {
+        match value {
+            TextChange::Echo => Self::Echo,
+            TextChange::External => Self::External,
+        }
+    }
+}
+
+impl From<Profile> for iced_futures::core::event::wayland::Profile {
+    fn from(value: Profile) -> Self {
+        match value {
+            Profile::Text => Self::Text,
+            Profile::Numeric => Self::Numeric,
+            Profile::Phone => Self::Phone,
+            Profile::Email => Self::Email,
+            Profile::Url => Self::Url,
+            Profile::Terminal => Self::Terminal,
+        }
+    }
+}
+
+impl From<Privacy> for iced_futures::core::event::wayland::Privacy {
+    fn from(value: Privacy) -> Self {
+        iced_futures::core::event::wayland::Privacy {
+            private: value.private,
+            bypass_composition: value.bypass_composition,
+        }
+    }
+}
+
 impl<T: 'static> Dispatch<ZwpInputMethodV2, InputMethod, SctkState<T>>
     for InputMethodManager<T>
 {
     fn event(
         state: &mut SctkState<T>,
-        _: &ZwpInputMethodV2,
+        input_method: &ZwpInputMethodV2,
         event: <ZwpInputMethodV2 as Proxy>::Event,
         _: &InputMethod,
         _: &Connection,
@@ -107,11 +213,20 @@ impl<T: 'static> Dispatch<ZwpInputMethodV2, InputMethod, SctkState<T>>
     ) {
         match event {
             zwp_input_method_v2::Event::Activate => {
+                // A new text input starts out with no hints and no preedit.
+                state.outstanding_commits = 0;
+                state.preedit_shown = false;
+                state.pending_content_type =
+                    Some((ContentHint::None, ContentPurpose::Normal));
+                state.pending_activation = Some(true);
                 state.sctk_events.push(SctkEvent::InputMethodEvent {
                     variant: InputMethodEventVariant::Activate,
                 })
             }
             zwp_input_method_v2::Event::Deactivate => {
+                state.pending_content_type =
+                    Some((ContentHint::None, ContentPurpose::Normal));
+                state.pending_activation = Some(false);
                 state.sctk_events.push(SctkEvent::InputMethodEvent {
                     variant: InputMethodEventVariant::Deactivate,
                 })
@@ -128,11 +243,16 @@ impl<T: 'static> Dispatch<ZwpInputMethodV2, InputMethod, SctkState<T>>
                 },
             }),
             zwp_input_method_v2::Event::TextChangeCause { cause } => {
+                state.pending_change_cause = cause.into_result().ok();
                 state.sctk_events.push(SctkEvent::InputMethodEvent {
                     variant: InputMethodEventVariant::TextChangeCause(cause),
                 })
             }
             zwp_input_method_v2::Event::ContentType { hint, purpose } => {
+                state.pending_content_type = Some((
+                    hint.into_result().unwrap_or(ContentHint::None),
+                    purpose.into_result().unwrap_or(ContentPurpose::Normal),
+                ));
                 state.sctk_events.push(SctkEvent::InputMethodEvent {
                     variant: InputMethodEventVariant::ContentType(
                         hint, purpose,
@@ -140,6 +260,67 @@ impl<T: 'static> Dispatch<ZwpInputMethodV2, InputMethod, SctkState<T>>
                 })
             }
             zwp_input_method_v2::Event::Done => {
+                state.done_count = state.done_count.wrapping_add(1);
+                if let Some(active) = state.pending_activation.take() {
+                    if active != state.input_method_active {
+                        state.input_method_active = active;
+                        state.input_method_activation_changed();
+                    }
+                }
+                if let Some(cause) = state.pending_change_cause.take() {
+                    // Each commit is echoed once, external changes may come
+                    // in between.
+                    let change = match cause {
+                        ChangeCause::InputMethod
+                            if state.outstanding_commits > 0 =>
+                        {
+                            state.outstanding_commits -= 1;
+                            TextChange::Echo
+                        }
+                        _ => TextChange::External,
+                    };
+                    if change == TextChange::External {
+                        state.privacy_settings.watch.external_change();
+                        if state.preedit_shown {
+                            // The preedit no longer belongs where the cursor
+                            // is. Requests the app sent are committed along.
+                            input_method.set_preedit_string(
+                                String::new(),
+                                0,
+                                0,
+                            );
+                            input_method.commit(state.done_count);
+                            state.preedit_shown = false;
+                            state.uncommitted_edits = false;
+                            state.outstanding_commits += 1;
+                        }
+                    }
+                    state.sctk_events.push(SctkEvent::InputMethodEvent {
+                        variant: InputMethodEventVariant::TextChanged(change),
+                    })
+                }
+                if let Some((hint, purpose)) = state.pending_content_type.take()
+                {
+                    let privacy = Privacy::new(
+                        hint,
+                        purpose,
+                        &state.privacy_settings,
+                    );
+                    if privacy != state.privacy {
+                        state.privacy = privacy;
+                        state.privacy_settings.watch.set(privacy.into());
+                        state.sctk_events.push(SctkEvent::InputMethodEvent {
+                            variant: InputMethodEventVariant::Privacy(privacy),
+                        })
+                    }
+                    let profile = Profile::from(purpose);
+                    if profile != state.profile {
+                        state.profile = profile;
+                        state.sctk_events.push(SctkEvent::InputMethodEvent {
+                            variant: InputMethodEventVariant::Profile(profile),
+                        })
+                    }
+                }
                 state.sctk_events.push(SctkEvent::InputMethodEvent {
                     variant: InputMethodEventVariant::Done,
                 })
@@ -234,6 +415,52 @@ impl<T: 'static> InputMethodKeyboardHandler for SctkState<T> {
             ),
         });
     }
+
+    fn update_compose(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _keyboard: &ZwpInputMethodKeyboardGrabV2,
+        status: ComposeStatus,
+    ) {
+        self.sctk_events.push(SctkEvent::InputMethodKeyboardEvent {
+            variant: InputMethodKeyboardEventVariant::Compose(status),
+        });
+    }
+
+    fn bypass_composition(&self) -> bool {
+        self.privacy.bypass_composition
+    }
+
+    fn update_layout(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _keyboard: &ZwpInputMethodKeyboardGrabV2,
+        index: u32,
+        name: String,
+    ) {
+        self.sctk_events.push(SctkEvent::InputMethodKeyboardEvent {
+            variant: InputMethodKeyboardEventVariant::LayoutChanged {
+                index,
+                name,
+            },
+        });
+    }
+
+    fn update_keymap(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _keyboard: &ZwpInputMethodKeyboardGrabV2,
+        keymap: Keymap<'_>,
+    ) {
+        self.sctk_events.push(SctkEvent::InputMethodKeyboardEvent {
+            variant: InputMethodKeyboardEventVariant::Layouts(
+                keymap.layouts(),
+            ),
+        });
+    }
 }
 
 delegate_input_method_keyboard!(@<T: 'static> SctkState<T>);
@@ -245,11 +472,16 @@ where
     pub fn commit(&mut self, serial: u32) {
         let seat = self.seats.first().expect("seat not present");
         if let Some(im) = seat.input_method.as_ref() {
-            im.commit(serial)
+            im.commit(serial);
+            if self.uncommitted_edits {
+                self.outstanding_commits += 1;
+            }
+            self.uncommitted_edits = false;
         }
     }
 
     pub fn commit_string(&mut self, string: String) {
+        self.uncommitted_edits = true;
         let seat = self.seats.first().expect("seat not present");
         if let Some(im) = seat.input_method.as_ref() {
             im.commit_string(string)
@@ -262,6 +494,8 @@ where
         cursor_begin: i32,
         cursor_end: i32,
     ) {
+        self.uncommitted_edits = true;
+        self.preedit_shown = !string.is_empty();
         let seat = self.seats.first().expect("seat not present");
         if let Some(im) = seat.input_method.as_ref() {
             im.set_preedit_string(string, cursor_begin, cursor_end)
@@ -273,12 +507,23 @@ where
         before_length: u32,
         after_length: u32,
     ) {
+        self.uncommitted_edits = true;
         let seat = self.seats.first().expect("seat not present");
         if let Some(im) = seat.input_method.as_ref() {
             im.delete_surrounding_text(before_length, after_length)
         }
     }
 
+    pub fn reload_compose(&mut self) {
+        for seat in &self.seats {
+            if let Some(data) = seat.input_method_keyboard.as_ref().and_then(
+                |kbd| kbd.data::<InputMethodKeyboardData<SctkState<T>>>(),
+            ) {
+                data.set_compose_settings(self.compose_settings.clone());
+            }
+        }
+    }
+
     pub fn get_input_method_popup(
         &mut self,
         settings: InputMethodPopupSettings,
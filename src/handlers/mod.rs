@@ -1,6 +1,7 @@
 // handlers
 pub mod compositor;
 pub mod data_device;
+pub mod foreign_toplevel;
 pub mod input_method;
 pub mod output;
 pub mod seat;
@@ -125,6 +125,7 @@ impl<T: Debug> KeyboardHandler for SctkState<T> {
         let seat_id = my_seat.seat.clone();
         let kbd_id = keyboard.clone();
         my_seat.last_kbd_press.replace((event.clone(), serial));
+        my_seat.last_input_serial.replace(serial);
         if is_active {
             self.sctk_events.push(SctkEvent::KeyboardEvent {
                 variant: KeyboardEventVariant::Press(event),
@@ -2,4 +2,5 @@
 pub mod keyboard;
 pub mod pointer;
 pub mod seat;
+pub mod tablet;
 pub mod touch;
@@ -86,6 +86,7 @@ impl<T: Debug> PointerHandler for SctkState<T> {
                         serial,
                     } if button == BTN_LEFT => {
                         my_seat.last_ptr_press.replace((time, button, serial));
+                        my_seat.last_input_serial.replace(serial);
                         window.window.resize(
                             &my_seat.seat,
                             serial,
@@ -152,6 +153,7 @@ impl<T: Debug> PointerHandler for SctkState<T> {
                     serial,
                 } => {
                     my_seat.last_ptr_press.replace((time, button, serial));
+                    my_seat.last_input_serial.replace(serial);
                 }
                 // TODO revisit events that ought to be handled and change internal state
                 _ => {}
@@ -1,5 +1,6 @@
 use crate::{
     event_loop::{state::SctkSeat, state::SctkState},
+    handlers::input_method::keyboard::RawModifiers,
     sctk_event::{KeyboardEventVariant, SctkEvent, SeatEventVariant},
 };
 use iced_runtime::keyboard::Modifiers;
@@ -8,7 +9,7 @@ use sctk::{
     reexports::client::{protocol::wl_keyboard::WlKeyboard, Proxy},
     seat::{pointer::ThemeSpec, SeatHandler},
 };
-use std::fmt::Debug;
+use std::{collections::HashMap, fmt::Debug};
 
 impl<T: Debug> SeatHandler for SctkState<T>
 where
@@ -30,29 +31,53 @@ where
         });
         let data_device =
             self.data_device_manager_state.get_data_device(qh, &seat);
+        let (input_method, input_method_keyboard) = self
+            .input_method_manager
+            .as_ref()
+            .map(|im| {
+                im.input_method(
+                    &seat,
+                    qh,
+                    self.loop_handle.clone(),
+                    &self.compose_settings,
+                )
+            })
+            .unzip();
         self.seats.push(SctkSeat {
             seat: seat.clone(),
             kbd: None,
             ptr: None,
-            _touch: None,
+            touch: None,
+            touch_points: HashMap::new(),
             data_device,
             _modifiers: Modifiers::default(),
             kbd_focus: None,
             ptr_focus: None,
             last_ptr_press: None,
+            last_input_serial: None,
             last_kbd_press: None,
             icon: None,
             virtual_keyboard: self
                 .virtual_keyboard_manager
                 .as_ref()
                 .map(|vk| vk.virtual_keyboard(&seat, qh)),
-            input_method: self.input_method_manager.as_ref().map(|im| {
-                im.input_method(
-                    &seat, 
-                    qh, 
-                    self.loop_handle.clone(), 
-                )
-            }),
+            virtual_keyboard_keymap: None,
+            virtual_keyboard_modifiers: RawModifiers::default(),
+            virtual_keyboard_last_tap: None,
+            input_method,
+            input_method_keyboard,
+            data_control_device: self
+                .data_control_manager
+                .as_ref()
+                .map(|dc| dc.data_device(&seat, qh)),
+            primary_selection_device: self
+                .primary_selection_manager
+                .as_ref()
+                .map(|ps| ps.primary_selection_device(&seat, qh)),
+            tablet_seat: self
+                .tablet_manager
+                .as_ref()
+                .map(|t| t.tablet_seat(&seat, qh)),
         });
     }
 
@@ -66,11 +91,24 @@ where
         let my_seat = match self.seats.iter_mut().find(|s| s.seat == seat) {
             Some(s) => s,
             None => {
+                let (input_method, input_method_keyboard) = self
+                    .input_method_manager
+                    .as_ref()
+                    .map(|im| {
+                        im.input_method(
+                            &seat,
+                            qh,
+                            self.loop_handle.clone(),
+                            &self.compose_settings,
+                        )
+                    })
+                    .unzip();
                 self.seats.push(SctkSeat {
                     seat: seat.clone(),
                     kbd: None,
                     ptr: None,
-                    _touch: None,
+                    touch: None,
+                    touch_points: HashMap::new(),
                     data_device: self
                         .data_device_manager_state
                         .get_data_device(qh, &seat),
@@ -78,21 +116,30 @@ where
                     kbd_focus: None,
                     ptr_focus: None,
                     last_ptr_press: None,
+                    last_input_serial: None,
                     last_kbd_press: None,
                     icon: None,
                     virtual_keyboard: self
                         .virtual_keyboard_manager
                         .as_ref()
                         .map(|vk| vk.virtual_keyboard(&seat.clone(), qh)),
-                    input_method: self.input_method_manager.as_ref().map(
-                        |im| {
-                            im.input_method(
-                                &seat,
-                                qh,
-                                self.loop_handle.clone(),
-                            )
-                        },
-                    ),
+                    virtual_keyboard_keymap: None,
+                    virtual_keyboard_modifiers: RawModifiers::default(),
+                    virtual_keyboard_last_tap: None,
+                    input_method,
+                    input_method_keyboard,
+                    data_control_device: self
+                        .data_control_manager
+                        .as_ref()
+                        .map(|dc| dc.data_device(&seat, qh)),
+                    primary_selection_device: self
+                        .primary_selection_manager
+                        .as_ref()
+                        .map(|ps| ps.primary_selection_device(&seat, qh)),
+                    tablet_seat: self
+                        .tablet_manager
+                        .as_ref()
+                        .map(|t| t.tablet_seat(&seat, qh)),
                 });
                 self.seats.last_mut().unwrap()
             }
@@ -145,7 +192,16 @@ where
                 }
             }
             sctk::seat::Capability::Touch => {
-                // TODO touch
+                if let Ok(touch) = self.seat_state.get_touch(qh, &seat) {
+                    self.sctk_events.push(SctkEvent::SeatEvent {
+                        variant: SeatEventVariant::NewCapability(
+                            capability,
+                            touch.id(),
+                        ),
+                        id: seat.clone(),
+                    });
+                    my_seat.touch.replace(touch);
+                }
             }
             _ => unimplemented!(),
         }
@@ -175,6 +231,9 @@ where
                         ),
                         id: seat.clone(),
                     });
+                    if kbd.version() >= 3 {
+                        kbd.release();
+                    }
                 }
             }
             sctk::seat::Capability::Pointer => {
@@ -186,11 +245,25 @@ where
                         ),
                         id: seat.clone(),
                     });
+                    if ptr.pointer().version() >= 3 {
+                        ptr.pointer().release();
+                    }
                 }
             }
             sctk::seat::Capability::Touch => {
-                // TODO touch
-                // my_seat.touch = self.seat_state.get_touch(qh, &seat).ok();
+                if let Some(touch) = my_seat.touch.take() {
+                    self.sctk_events.push(SctkEvent::SeatEvent {
+                        variant: SeatEventVariant::RemoveCapability(
+                            capability,
+                            touch.id(),
+                        ),
+                        id: seat.clone(),
+                    });
+                    if touch.version() >= 3 {
+                        touch.release();
+                    }
+                }
+                my_seat.touch_points.clear();
             }
             _ => unimplemented!(),
         }
@@ -207,7 +280,16 @@ where
             id: seat.clone(),
         });
         if let Some(i) = self.seats.iter().position(|s| s.seat == seat) {
-            self.seats.remove(i);
+            let removed = self.seats.remove(i);
+            if let Some(device) = removed.data_control_device {
+                device.destroy();
+            }
+            if let Some(device) = removed.primary_selection_device {
+                device.destroy();
+            }
+            if let Some(tablet_seat) = removed.tablet_seat {
+                tablet_seat.destroy();
+            }
         }
     }
 }
@@ -0,0 +1,494 @@
+//! Tablet input through `zwp_tablet_manager_v2`.
+//!
+//! Tool events are accumulated until `frame` and reported together with the
+//! state of every axis. Once the manager is bound, the compositor stops
+//! emulating a pointer for the tablets of the seat.
+//!
+//! Of the pads only the buttons are reported. Their groups, rings and strips
+//! are bound as the protocol requires, but mode switches and ring and strip
+//! motion are ignored.
+use std::{fmt::Debug, marker::PhantomData, sync::Mutex};
+
+use iced_runtime::core::event::wayland::{
+    TabletAxes, TabletTool, TabletToolKind,
+};
+use sctk::{
+    globals::GlobalData,
+    reexports::client::{
+        delegate_dispatch, event_created_child,
+        globals::{BindError, GlobalList},
+        protocol::{wl_seat::WlSeat, wl_surface::WlSurface},
+        Connection, Dispatch, Proxy, QueueHandle, WEnum,
+    },
+};
+use wayland_protocols::wp::tablet::zv2::client::{
+    zwp_tablet_manager_v2::ZwpTabletManagerV2,
+    zwp_tablet_pad_group_v2::{self, ZwpTabletPadGroupV2},
+    zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2,
+    zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2,
+    zwp_tablet_pad_v2::{self, ZwpTabletPadV2},
+    zwp_tablet_seat_v2::{self, ZwpTabletSeatV2},
+    zwp_tablet_tool_v2::{self, ZwpTabletToolV2},
+    zwp_tablet_v2::{self, ZwpTabletV2},
+};
+
+use crate::{
+    event_loop::state::SctkState,
+    sctk_event::{SctkEvent, TabletEventVariant},
+};
+
+#[derive(Debug)]
+pub struct TabletManager<T> {
+    manager: ZwpTabletManagerV2,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: 'static> TabletManager<T> {
+    pub fn new(
+        globals: &GlobalList,
+        queue_handle: &QueueHandle<SctkState<T>>,
+    ) -> Result<Self, BindError> {
+        let manager = globals.bind(queue_handle, 1..=1, GlobalData)?;
+        Ok(Self {
+            manager,
+            _phantom: PhantomData,
+        })
+    }
+
+    pub fn tablet_seat(
+        &self,
+        seat: &WlSeat,
+        queue_handle: &QueueHandle<SctkState<T>>,
+    ) -> ZwpTabletSeatV2 {
+        self.manager.get_tablet_seat(
+            seat,
+            queue_handle,
+            TabletSeatData { seat: seat.clone() },
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct TabletSeatData {
+    seat: WlSeat,
+}
+
+#[derive(Debug, Default)]
+pub struct TabletData {}
+
+/// A tool, its seat is set when it is announced.
+#[derive(Debug, Default)]
+pub struct TabletToolData {
+    state: Mutex<ToolState>,
+}
+
+#[derive(Debug)]
+struct ToolState {
+    seat: Option<WlSeat>,
+    tool: TabletTool,
+    /// The surface the tool is in proximity of.
+    focus: Option<WlSurface>,
+    axes: TabletAxes,
+    /// Changes since the last frame.
+    frame: ToolFrame,
+}
+
+impl Default for ToolState {
+    fn default() -> Self {
+        Self {
+            seat: None,
+            tool: TabletTool {
+                id: 0,
+                kind: TabletToolKind::Pen,
+                hardware_serial: None,
+                hardware_id: None,
+            },
+            focus: None,
+            axes: TabletAxes::default(),
+            frame: ToolFrame::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ToolFrame {
+    proximity_in: bool,
+    proximity_out: Option<WlSurface>,
+    down: bool,
+    up: bool,
+    moved: bool,
+    buttons: Vec<(u32, bool)>,
+}
+
+/// A pad, its seat is set when it is announced.
+#[derive(Debug, Default)]
+pub struct TabletPadData {
+    state: Mutex<PadState>,
+}
+
+#[derive(Debug, Default)]
+struct PadState {
+    seat: Option<WlSeat>,
+    /// The surface the pad is focused on.
+    focus: Option<WlSurface>,
+}
+
+#[derive(Debug, Default)]
+pub struct TabletPadGroupData {}
+
+#[derive(Debug, Default)]
+pub struct TabletPadRingData {}
+
+#[derive(Debug, Default)]
+pub struct TabletPadStripData {}
+
+impl<T: 'static> Dispatch<ZwpTabletManagerV2, GlobalData, SctkState<T>>
+    for TabletManager<T>
+{
+    fn event(
+        _: &mut SctkState<T>,
+        _: &ZwpTabletManagerV2,
+        _: <ZwpTabletManagerV2 as Proxy>::Event,
+        _: &GlobalData,
+        _: &Connection,
+        _: &QueueHandle<SctkState<T>>,
+    ) {
+        // No events.
+    }
+}
+
+impl<T: 'static> Dispatch<ZwpTabletSeatV2, TabletSeatData, SctkState<T>>
+    for TabletManager<T>
+{
+    fn event(
+        _: &mut SctkState<T>,
+        _: &ZwpTabletSeatV2,
+        event: <ZwpTabletSeatV2 as Proxy>::Event,
+        data: &TabletSeatData,
+        _: &Connection,
+        _: &QueueHandle<SctkState<T>>,
+    ) {
+        match event {
+            zwp_tablet_seat_v2::Event::TabletAdded { .. } => {
+                // Tablets only describe the device, tools and pads report
+                // which one they are used with.
+            }
+            zwp_tablet_seat_v2::Event::ToolAdded { id } => {
+                if let Some(tool) = id.data::<TabletToolData>() {
+                    let mut state = tool.state.lock().unwrap();
+                    state.seat = Some(data.seat.clone());
+                    state.tool.id = id.id().protocol_id();
+                }
+            }
+            zwp_tablet_seat_v2::Event::PadAdded { id } => {
+                if let Some(pad) = id.data::<TabletPadData>() {
+                    pad.state.lock().unwrap().seat = Some(data.seat.clone());
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    event_created_child!(SctkState<T>, ZwpTabletSeatV2, [
+        zwp_tablet_seat_v2::EVT_TABLET_ADDED_OPCODE => (ZwpTabletV2, TabletData::default()),
+        zwp_tablet_seat_v2::EVT_TOOL_ADDED_OPCODE => (ZwpTabletToolV2, TabletToolData::default()),
+        zwp_tablet_seat_v2::EVT_PAD_ADDED_OPCODE => (ZwpTabletPadV2, TabletPadData::default())
+    ]);
+}
+
+impl<T: 'static> Dispatch<ZwpTabletV2, TabletData, SctkState<T>>
+    for TabletManager<T>
+{
+    fn event(
+        _: &mut SctkState<T>,
+        tablet: &ZwpTabletV2,
+        event: <ZwpTabletV2 as Proxy>::Event,
+        _: &TabletData,
+        _: &Connection,
+        _: &QueueHandle<SctkState<T>>,
+    ) {
+        if let zwp_tablet_v2::Event::Removed = event {
+            tablet.destroy();
+        }
+    }
+}
+
+impl<T: 'static> Dispatch<ZwpTabletToolV2, TabletToolData, SctkState<T>>
+    for TabletManager<T>
+{
+    fn event(
+        state: &mut SctkState<T>,
+        tool: &ZwpTabletToolV2,
+        event: <ZwpTabletToolV2 as Proxy>::Event,
+        data: &TabletToolData,
+        _: &Connection,
+        _: &QueueHandle<SctkState<T>>,
+    ) {
+        let mut tool_state = data.state.lock().unwrap();
+        match event {
+            zwp_tablet_tool_v2::Event::Type { tool_type } => {
+                if let WEnum::Value(tool_type) = tool_type {
+                    tool_state.tool.kind = tool_kind(tool_type);
+                }
+            }
+            zwp_tablet_tool_v2::Event::HardwareSerial {
+                hardware_serial_hi,
+                hardware_serial_lo,
+            } => {
+                tool_state.tool.hardware_serial = Some(
+                    u64::from(hardware_serial_hi) << 32
+                        | u64::from(hardware_serial_lo),
+                );
+            }
+            zwp_tablet_tool_v2::Event::HardwareIdWacom {
+                hardware_id_hi,
+                hardware_id_lo,
+            } => {
+                tool_state.tool.hardware_id = Some(
+                    u64::from(hardware_id_hi) << 32 | u64::from(hardware_id_lo),
+                );
+            }
+            zwp_tablet_tool_v2::Event::Capability { .. }
+            | zwp_tablet_tool_v2::Event::Done => {}
+            zwp_tablet_tool_v2::Event::Removed => {
+                drop(tool_state);
+                tool.destroy();
+            }
+            zwp_tablet_tool_v2::Event::ProximityIn { surface, .. } => {
+                tool_state.focus = Some(surface);
+                tool_state.frame.proximity_in = true;
+            }
+            zwp_tablet_tool_v2::Event::ProximityOut => {
+                tool_state.frame.proximity_out = tool_state.focus.take();
+            }
+            zwp_tablet_tool_v2::Event::Down { .. } => {
+                tool_state.axes.down = true;
+                tool_state.frame.down = true;
+            }
+            zwp_tablet_tool_v2::Event::Up => {
+                tool_state.axes.down = false;
+                tool_state.frame.up = true;
+            }
+            zwp_tablet_tool_v2::Event::Motion { x, y } => {
+                tool_state.axes.x = x;
+                tool_state.axes.y = y;
+                tool_state.frame.moved = true;
+            }
+            zwp_tablet_tool_v2::Event::Pressure { pressure } => {
+                tool_state.axes.pressure = pressure as f32 / 65535.0;
+                tool_state.frame.moved = true;
+            }
+            zwp_tablet_tool_v2::Event::Distance { distance } => {
+                tool_state.axes.distance = distance as f32 / 65535.0;
+                tool_state.frame.moved = true;
+            }
+            zwp_tablet_tool_v2::Event::Tilt { tilt_x, tilt_y } => {
+                tool_state.axes.tilt_x = tilt_x as f32;
+                tool_state.axes.tilt_y = tilt_y as f32;
+                tool_state.frame.moved = true;
+            }
+            zwp_tablet_tool_v2::Event::Rotation { degrees } => {
+                tool_state.axes.rotation = degrees as f32;
+                tool_state.frame.moved = true;
+            }
+            zwp_tablet_tool_v2::Event::Slider { position } => {
+                tool_state.axes.slider = position as f32 / 65535.0;
+                tool_state.frame.moved = true;
+            }
+            zwp_tablet_tool_v2::Event::Wheel { degrees, .. } => {
+                tool_state.axes.wheel += degrees as f32;
+                tool_state.frame.moved = true;
+            }
+            zwp_tablet_tool_v2::Event::Button {
+                button,
+                state: button_state,
+                ..
+            } => {
+                let pressed = matches!(
+                    button_state,
+                    WEnum::Value(zwp_tablet_tool_v2::ButtonState::Pressed)
+                );
+                tool_state.frame.buttons.push((button, pressed));
+            }
+            zwp_tablet_tool_v2::Event::Frame { .. } => {
+                let frame = std::mem::take(&mut tool_state.frame);
+                let axes = tool_state.axes;
+                tool_state.axes.wheel = 0.0;
+                let Some(seat_id) = tool_state.seat.clone() else {
+                    return;
+                };
+                let tool = tool_state.tool;
+                let mut push =
+                    |variant: TabletEventVariant, surface: &WlSurface| {
+                        state.sctk_events.push(SctkEvent::TabletEvent {
+                            variant,
+                            seat_id: seat_id.clone(),
+                            surface: surface.clone(),
+                        });
+                    };
+                if let Some(surface) = tool_state.focus.as_ref() {
+                    if frame.proximity_in {
+                        push(
+                            TabletEventVariant::ProximityIn { tool, axes },
+                            surface,
+                        );
+                    }
+                    if frame.down {
+                        push(TabletEventVariant::Down { tool, axes }, surface);
+                    }
+                    if frame.moved && !frame.proximity_in && !frame.down {
+                        push(
+                            TabletEventVariant::Motion { tool, axes },
+                            surface,
+                        );
+                    }
+                    for (button, pressed) in frame.buttons {
+                        push(
+                            TabletEventVariant::Button {
+                                tool,
+                                button,
+                                pressed,
+                            },
+                            surface,
+                        );
+                    }
+                    if frame.up {
+                        push(TabletEventVariant::Up { tool, axes }, surface);
+                    }
+                } else if let Some(surface) = frame.proximity_out {
+                    // The tool is lifted before it leaves.
+                    if frame.up {
+                        push(TabletEventVariant::Up { tool, axes }, &surface);
+                    }
+                    push(TabletEventVariant::ProximityOut { tool }, &surface);
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<T: 'static> Dispatch<ZwpTabletPadV2, TabletPadData, SctkState<T>>
+    for TabletManager<T>
+{
+    fn event(
+        state: &mut SctkState<T>,
+        pad: &ZwpTabletPadV2,
+        event: <ZwpTabletPadV2 as Proxy>::Event,
+        data: &TabletPadData,
+        _: &Connection,
+        _: &QueueHandle<SctkState<T>>,
+    ) {
+        let mut pad_state = data.state.lock().unwrap();
+        match event {
+            zwp_tablet_pad_v2::Event::Enter { surface, .. } => {
+                pad_state.focus = Some(surface);
+            }
+            zwp_tablet_pad_v2::Event::Leave { .. } => {
+                pad_state.focus = None;
+            }
+            zwp_tablet_pad_v2::Event::Button {
+                button,
+                state: button_state,
+                ..
+            } => {
+                let (Some(seat_id), Some(surface)) =
+                    (pad_state.seat.clone(), pad_state.focus.clone())
+                else {
+                    return;
+                };
+                let pressed = matches!(
+                    button_state,
+                    WEnum::Value(zwp_tablet_pad_v2::ButtonState::Pressed)
+                );
+                state.sctk_events.push(SctkEvent::TabletEvent {
+                    variant: TabletEventVariant::PadButton { button, pressed },
+                    seat_id,
+                    surface,
+                });
+            }
+            zwp_tablet_pad_v2::Event::Removed => {
+                drop(pad_state);
+                pad.destroy();
+            }
+            zwp_tablet_pad_v2::Event::Group { .. }
+            | zwp_tablet_pad_v2::Event::Path { .. }
+            | zwp_tablet_pad_v2::Event::Buttons { .. }
+            | zwp_tablet_pad_v2::Event::Done => {}
+            _ => unreachable!(),
+        }
+    }
+
+    event_created_child!(SctkState<T>, ZwpTabletPadV2, [
+        zwp_tablet_pad_v2::EVT_GROUP_OPCODE => (ZwpTabletPadGroupV2, TabletPadGroupData::default())
+    ]);
+}
+
+impl<T: 'static> Dispatch<ZwpTabletPadGroupV2, TabletPadGroupData, SctkState<T>>
+    for TabletManager<T>
+{
+    fn event(
+        _: &mut SctkState<T>,
+        _: &ZwpTabletPadGroupV2,
+        _: <ZwpTabletPadGroupV2 as Proxy>::Event,
+        _: &TabletPadGroupData,
+        _: &Connection,
+        _: &QueueHandle<SctkState<T>>,
+    ) {
+    }
+
+    event_created_child!(SctkState<T>, ZwpTabletPadGroupV2, [
+        zwp_tablet_pad_group_v2::EVT_RING_OPCODE => (ZwpTabletPadRingV2, TabletPadRingData::default()),
+        zwp_tablet_pad_group_v2::EVT_STRIP_OPCODE => (ZwpTabletPadStripV2, TabletPadStripData::default())
+    ]);
+}
+
+impl<T: 'static> Dispatch<ZwpTabletPadRingV2, TabletPadRingData, SctkState<T>>
+    for TabletManager<T>
+{
+    fn event(
+        _: &mut SctkState<T>,
+        _: &ZwpTabletPadRingV2,
+        _: <ZwpTabletPadRingV2 as Proxy>::Event,
+        _: &TabletPadRingData,
+        _: &Connection,
+        _: &QueueHandle<SctkState<T>>,
+    ) {
+    }
+}
+
+impl<T: 'static> Dispatch<ZwpTabletPadStripV2, TabletPadStripData, SctkState<T>>
+    for TabletManager<T>
+{
+    fn event(
+        _: &mut SctkState<T>,
+        _: &ZwpTabletPadStripV2,
+        _: <ZwpTabletPadStripV2 as Proxy>::Event,
+        _: &TabletPadStripData,
+        _: &Connection,
+        _: &QueueHandle<SctkState<T>>,
+    ) {
+    }
+}
+
+fn tool_kind(tool_type: zwp_tablet_tool_v2::Type) -> TabletToolKind {
+    match tool_type {
+        zwp_tablet_tool_v2::Type::Eraser => TabletToolKind::Eraser,
+        zwp_tablet_tool_v2::Type::Brush => TabletToolKind::Brush,
+        zwp_tablet_tool_v2::Type::Pencil => TabletToolKind::Pencil,
+        zwp_tablet_tool_v2::Type::Airbrush => TabletToolKind::Airbrush,
+        zwp_tablet_tool_v2::Type::Finger => TabletToolKind::Finger,
+        zwp_tablet_tool_v2::Type::Mouse => TabletToolKind::Mouse,
+        zwp_tablet_tool_v2::Type::Lens => TabletToolKind::Lens,
+        _ => TabletToolKind::Pen,
+    }
+}
+
+delegate_dispatch!(@<T: 'static> SctkState<T>: [ZwpTabletManagerV2: GlobalData] => TabletManager<T>);
+delegate_dispatch!(@<T: 'static> SctkState<T>: [ZwpTabletSeatV2: TabletSeatData] => TabletManager<T>);
+delegate_dispatch!(@<T: 'static> SctkState<T>: [ZwpTabletV2: TabletData] => TabletManager<T>);
+delegate_dispatch!(@<T: 'static> SctkState<T>: [ZwpTabletToolV2: TabletToolData] => TabletManager<T>);
+delegate_dispatch!(@<T: 'static> SctkState<T>: [ZwpTabletPadV2: TabletPadData] => TabletManager<T>);
+delegate_dispatch!(@<T: 'static> SctkState<T>: [ZwpTabletPadGroupV2: TabletPadGroupData] => TabletManager<T>);
+delegate_dispatch!(@<T: 'static> SctkState<T>: [ZwpTabletPadRingV2: TabletPadRingData] => TabletManager<T>);
+delegate_dispatch!(@<T: 'static> SctkState<T>: [ZwpTabletPadStripV2: TabletPadStripData] => TabletManager<T>);
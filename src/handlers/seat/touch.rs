@@ -1 +1,170 @@
-// TODO
+use crate::{
+    dpi::LogicalPosition,
+    event_loop::state::{SctkState, TouchPoint},
+    sctk_event::{SctkEvent, TouchEventVariant},
+};
+use sctk::{
+    delegate_touch,
+    reexports::client::{
+        protocol::{wl_surface::WlSurface, wl_touch::WlTouch},
+        Connection, QueueHandle,
+    },
+    seat::touch::TouchHandler,
+};
+use std::fmt::Debug;
+
+// sctk buffers touch events until `wl_touch.frame`, the handlers below are
+// called once the frame is complete.
+impl<T: Debug> TouchHandler for SctkState<T> {
+    fn down(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        touch: &WlTouch,
+        serial: u32,
+        _time: u32,
+        surface: WlSurface,
+        id: i32,
+        position: (f64, f64),
+    ) {
+        let my_seat = match self
+            .seats
+            .iter_mut()
+            .find(|s| s.touch.as_ref() == Some(touch))
+        {
+            Some(s) => s,
+            None => return,
+        };
+        let position = LogicalPosition::new(position.0, position.1);
+        my_seat.last_input_serial.replace(serial);
+        my_seat.touch_points.insert(
+            id,
+            TouchPoint {
+                surface: surface.clone(),
+                position,
+            },
+        );
+        self.sctk_events.push(SctkEvent::TouchEvent {
+            variant: TouchEventVariant::Down { id, position },
+            touch_id: touch.clone(),
+            seat_id: my_seat.seat.clone(),
+            surface,
+        });
+    }
+
+    fn up(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        touch: &WlTouch,
+        _serial: u32,
+        _time: u32,
+        id: i32,
+    ) {
+        let my_seat = match self
+            .seats
+            .iter_mut()
+            .find(|s| s.touch.as_ref() == Some(touch))
+        {
+            Some(s) => s,
+            None => return,
+        };
+        // The point is lifted where it last moved to, on the surface it
+        // went down on.
+        if let Some(point) = my_seat.touch_points.remove(&id) {
+            self.sctk_events.push(SctkEvent::TouchEvent {
+                variant: TouchEventVariant::Up {
+                    id,
+                    position: point.position,
+                },
+                touch_id: touch.clone(),
+                seat_id: my_seat.seat.clone(),
+                surface: point.surface,
+            });
+        }
+    }
+
+    fn motion(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        touch: &WlTouch,
+        _time: u32,
+        id: i32,
+        position: (f64, f64),
+    ) {
+        let my_seat = match self
+            .seats
+            .iter_mut()
+            .find(|s| s.touch.as_ref() == Some(touch))
+        {
+            Some(s) => s,
+            None => return,
+        };
+        if let Some(point) = my_seat.touch_points.get_mut(&id) {
+            // Motion is relative to the surface the point went down on.
+            point.position = LogicalPosition::new(position.0, position.1);
+            self.sctk_events.push(SctkEvent::TouchEvent {
+                variant: TouchEventVariant::Motion {
+                    id,
+                    position: point.position,
+                },
+                touch_id: touch.clone(),
+                seat_id: my_seat.seat.clone(),
+                surface: point.surface.clone(),
+            });
+        }
+    }
+
+    fn shape(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _touch: &WlTouch,
+        _id: i32,
+        _major: f64,
+        _minor: f64,
+    ) {
+        // TODO expose contact shapes if a widget needs them
+    }
+
+    fn orientation(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _touch: &WlTouch,
+        _id: i32,
+        _orientation: f64,
+    ) {
+    }
+
+    fn cancel(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        touch: &WlTouch,
+    ) {
+        let my_seat = match self
+            .seats
+            .iter_mut()
+            .find(|s| s.touch.as_ref() == Some(touch))
+        {
+            Some(s) => s,
+            None => return,
+        };
+        // The compositor took over the sequence, every point is lost.
+        for (id, point) in my_seat.touch_points.drain() {
+            self.sctk_events.push(SctkEvent::TouchEvent {
+                variant: TouchEventVariant::Cancel {
+                    id,
+                    position: point.position,
+                },
+                touch_id: touch.clone(),
+                seat_id: my_seat.seat.clone(),
+                surface: point.surface,
+            });
+        }
+    }
+}
+
+delegate_touch!(@<T: 'static + Debug> SctkState<T>);
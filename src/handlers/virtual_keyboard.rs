@@ -1,4 +1,11 @@
-use std::{fmt::Debug, marker::PhantomData};
+use std::{
+    fmt::Debug,
+    fs::File,
+    io::{self, Write},
+    marker::PhantomData,
+    os::fd::AsFd,
+    time::Instant,
+};
 
 use iced_futures::core::event::wayland::KeyEvent;
 use sctk::reexports::client::{
@@ -13,9 +20,14 @@ use wayland_protocols_misc::zwp_virtual_keyboard_v1::client::{
     zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1,
 };
 
+use rustix::fs::{fcntl_add_seals, memfd_create, MemfdFlags, SealFlags};
 use sctk::globals::GlobalData;
+use tracing::error;
 
-use crate::event_loop::state::SctkState;
+use crate::{
+    event_loop::state::SctkState,
+    sctk_event::{SctkEvent, VirtualKeyboardEvent},
+};
 
 use super::input_method::keyboard::RawModifiers;
 
@@ -83,6 +95,30 @@ impl<T: 'static> Dispatch<ZwpVirtualKeyboardV1, VirtualKeyboard, SctkState<T>>
 delegate_dispatch!(@<T: 'static> SctkState<T>: [ZwpVirtualKeyboardManagerV1: GlobalData] => VirtualKeyboardManager<T>);
 delegate_dispatch!(@<T: 'static> SctkState<T>: [ZwpVirtualKeyboardV1: VirtualKeyboard] => VirtualKeyboardManager<T>);
 
+/// `wl_keyboard.keymap_format.xkb_v1`
+const KEYMAP_FORMAT_XKB_V1: u32 = 1;
+
+/// Copy `keymap` into a sealed-off memory file, NUL terminated as the
+/// protocol requires. The compositor maps it, so it must not change size or
+/// contents afterwards.
+fn keymap_file(keymap: &str) -> io::Result<File> {
+    let fd = memfd_create(
+        "iced-virtual-keyboard-keymap",
+        MemfdFlags::CLOEXEC | MemfdFlags::ALLOW_SEALING,
+    )?;
+    let mut file = File::from(fd);
+    file.write_all(keymap.as_bytes())?;
+    file.write_all(&[0])?;
+    fcntl_add_seals(
+        &file,
+        SealFlags::SHRINK
+            | SealFlags::GROW
+            | SealFlags::WRITE
+            | SealFlags::SEAL,
+    )?;
+    Ok(file)
+}
+
 impl<T> SctkState<T>
 where
     T: 'static + Debug,
@@ -107,11 +143,120 @@ where
                 wl_keyboard::KeyState::Released.into(),
             );
         }
+        // A latch applies to a single key.
+        let modifiers = seat.virtual_keyboard_modifiers;
+        if modifiers.mods_latched != 0 {
+            self.set_virtual_keyboard_modifiers(RawModifiers {
+                mods_latched: 0,
+                ..modifiers
+            });
+        }
+    }
+
+    /// Upload `keymap` to the virtual keyboard unless it is already in use.
+    pub fn set_virtual_keyboard_keymap(&mut self, keymap: String) {
+        let seat = self.seats.first_mut().expect("seat not present"); //TODO: Handle this better
+        let Some(vk) = seat.virtual_keyboard.as_ref() else {
+            return;
+        };
+        if seat.virtual_keyboard_keymap.as_ref() == Some(&keymap) {
+            return;
+        }
+        match keymap_file(&keymap) {
+            Ok(file) => {
+                vk.keymap(
+                    KEYMAP_FORMAT_XKB_V1,
+                    file.as_fd(),
+                    keymap.len() as u32 + 1,
+                );
+                seat.virtual_keyboard_keymap = Some(keymap);
+                // Modifiers could not be sent before there was a keymap.
+                let modifiers = seat.virtual_keyboard_modifiers;
+                vk.modifiers(
+                    modifiers.mods_depressed,
+                    modifiers.mods_latched,
+                    modifiers.mods_locked,
+                    modifiers.group,
+                );
+            }
+            Err(err) => {
+                error!("Failed to share virtual keyboard keymap: {}", err);
+            }
+        }
     }
 
     pub fn update_modifiers(&mut self, modifiers: RawModifiers) {
-        let seat = self.seats.first().expect("seat not present"); //TODO: Handle this better
-        if let Some(vk) = seat.virtual_keyboard.as_ref() {
+        self.set_virtual_keyboard_modifiers(modifiers);
+    }
+
+    /// Latch the released modifiers in `mask` and release the locked ones.
+    /// Latched ones are locked by a double tap, a tap after the double-tap
+    /// interval releases them.
+    pub fn tap_modifiers(&mut self, mask: u32) {
+        let interval = self.virtual_keyboard_settings.double_tap_interval;
+        let Some(seat) = self.seats.first_mut() else {
+            return;
+        };
+        let now = Instant::now();
+        let double_tap =
+            seat.virtual_keyboard_last_tap
+                .is_some_and(|(last_mask, time)| {
+                    last_mask == mask && now.duration_since(time) <= interval
+                });
+        seat.virtual_keyboard_last_tap = Some((mask, now));
+
+        let modifiers = seat.virtual_keyboard_modifiers;
+        let locked = modifiers.mods_locked & mask;
+        let latched = modifiers.mods_latched & mask & !locked;
+        let released = mask & !locked & !latched;
+        let to_lock = if double_tap { latched } else { 0 };
+        self.set_virtual_keyboard_modifiers(RawModifiers {
+            mods_latched: (modifiers.mods_latched & !mask) | released,
+            mods_locked: (modifiers.mods_locked & !mask) | to_lock,
+            ..modifiers
+        });
+    }
+
+    /// Release every latched and locked modifier.
+    pub fn clear_modifiers(&mut self) {
+        let Some(seat) = self.seats.first_mut() else {
+            return;
+        };
+        let modifiers = seat.virtual_keyboard_modifiers;
+        self.set_virtual_keyboard_modifiers(RawModifiers {
+            mods_latched: 0,
+            mods_locked: 0,
+            ..modifiers
+        });
+    }
+
+    /// Switch to the layout at `group` of the virtual keyboard keymap. The
+    /// keymap stays whatever the app or the on-screen keyboard uploaded.
+    pub fn set_group(&mut self, group: u32) {
+        let Some(seat) = self.seats.first() else {
+            return;
+        };
+        let modifiers = seat.virtual_keyboard_modifiers;
+        self.set_virtual_keyboard_modifiers(RawModifiers {
+            group,
+            ..modifiers
+        });
+    }
+
+    /// Send `modifiers` through the virtual keyboard and report them if they
+    /// changed.
+    fn set_virtual_keyboard_modifiers(&mut self, modifiers: RawModifiers) {
+        let Some(seat) = self.seats.first_mut() else {
+            return;
+        };
+        let changed = seat.virtual_keyboard_modifiers != modifiers;
+        seat.virtual_keyboard_modifiers = modifiers;
+        // The compositor rejects modifiers before the first keymap, they are
+        // sent along with it instead.
+        if let (Some(vk), Some(_)) = (
+            seat.virtual_keyboard.as_ref(),
+            seat.virtual_keyboard_keymap.as_ref(),
+        ) {
             vk.modifiers(
                 modifiers.mods_depressed,
                 modifiers.mods_latched,
@@ -119,5 +264,10 @@ where
                 modifiers.group,
             );
         }
+        if changed {
+            self.sctk_events.push(SctkEvent::VirtualKeyboard(
+                VirtualKeyboardEvent::Modifiers(modifiers),
+            ));
+        }
     }
 }
@@ -3,12 +3,16 @@ pub mod clipboard;
 pub mod commands;
 pub mod conversion;
 pub mod dpi;
+pub mod engine;
 pub mod error;
 pub mod event_loop;
 mod handlers;
+pub mod osk;
 pub mod result;
 pub mod sctk_event;
 pub mod settings;
+#[cfg(feature = "software")]
+pub mod software;
 #[cfg(feature = "system")]
 pub mod system;
 pub mod util;
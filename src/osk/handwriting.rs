@@ -0,0 +1,309 @@
+//! The canvas handwriting is drawn on.
+//!
+//! A [`Pad`] captures strokes from the mouse, touch and tablet tools and
+//! reports each finished one, to be added to a
+//! [`HandwritingEngine`](crate::engine::handwriting::HandwritingEngine)
+//! whose candidates are then shown in the input method popup. The strokes of
+//! the character being written are drawn until it is committed.
+use std::collections::HashMap;
+
+use iced_runtime::command::platform_specific::wayland::layer_surface::SctkLayerSurfaceSettings;
+use iced_runtime::core::{
+    event::{self, wayland, PlatformSpecific},
+    layout,
+    mouse::{self, Cursor},
+    renderer, touch,
+    widget::{tree, Tree},
+    window, Background, Clipboard, Color, Element, Event, Layout, Length,
+    Point, Rectangle, Shell, Vector, Widget,
+};
+use sctk::shell::wlr_layer::{Anchor, KeyboardInteractivity, Layer};
+
+use super::widget::{Appearance, Pointer};
+use crate::engine::handwriting::Stroke;
+
+/// How far the pointer has to move before another point is recorded.
+const RESOLUTION: f32 = 2.0;
+
+/// Captures strokes and draws them.
+///
+/// Positions are relative to the top left corner of the pad. The background
+/// and ink use the `background` and `text` colors of the [`Appearance`].
+pub struct Pad<'a, Message> {
+    strokes: &'a [Stroke],
+    on_stroke: Box<dyn Fn(Stroke) -> Message + 'a>,
+    stroke_width: f32,
+    appearance: Appearance,
+}
+
+impl<'a, Message> Pad<'a, Message> {
+    /// Creates a pad drawing `strokes` and reporting new strokes as
+    /// `on_stroke`.
+    pub fn new(
+        strokes: &'a [Stroke],
+        on_stroke: impl Fn(Stroke) -> Message + 'a,
+    ) -> Self {
+        Self {
+            strokes,
+            on_stroke: Box::new(on_stroke),
+            stroke_width: 4.0,
+            appearance: Appearance::default(),
+        }
+    }
+
+    /// Sets the width of the strokes.
+    pub fn stroke_width(mut self, width: f32) -> Self {
+        self.stroke_width = width.max(1.0);
+        self
+    }
+
+    /// Sets the [`Appearance`].
+    pub fn appearance(mut self, appearance: Appearance) -> Self {
+        self.appearance = appearance;
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    drawing: HashMap<Pointer, Stroke>,
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for Pad<'a, Message>
+where
+    Renderer: renderer::Renderer,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn width(&self) -> Length {
+        Length::Fill
+    }
+
+    fn height(&self) -> Length {
+        Length::Fill
+    }
+
+    fn layout(
+        &self,
+        _renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        let size = limits.width(Length::Fill).height(Length::Fill).max();
+        layout::Node::new(size)
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: Cursor,
+        _renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        _viewport: &Rectangle,
+    ) -> event::Status {
+        let state = tree.state.downcast_mut::<State>();
+        let bounds = layout.bounds();
+        let origin = Vector::new(bounds.x, bounds.y);
+
+        let (pointer, position, lifted) = match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let Some(p) = cursor.position_over(bounds) else {
+                    return event::Status::Ignored;
+                };
+                let _ = state.drawing.insert(Pointer::Mouse, vec![]);
+                (Pointer::Mouse, Some(p), false)
+            }
+            Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                (Pointer::Mouse, Some(position), false)
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                (Pointer::Mouse, cursor.position(), true)
+            }
+            Event::Touch(touch::Event::FingerPressed { id, position }) => {
+                if !bounds.contains(position) {
+                    return event::Status::Ignored;
+                }
+                let _ = state.drawing.insert(Pointer::Finger(id), vec![]);
+                (Pointer::Finger(id), Some(position), false)
+            }
+            Event::Touch(touch::Event::FingerMoved { id, position }) => {
+                (Pointer::Finger(id), Some(position), false)
+            }
+            Event::Touch(touch::Event::FingerLifted { id, position }) => {
+                (Pointer::Finger(id), Some(position), true)
+            }
+            Event::PlatformSpecific(PlatformSpecific::Wayland(
+                wayland::Event::Tablet(tablet_event, _),
+            )) => match tablet_event {
+                wayland::TabletEvent::Down { tool, axes } => {
+                    let position = Point::new(axes.x as f32, axes.y as f32);
+                    if !bounds.contains(position) {
+                        return event::Status::Ignored;
+                    }
+                    let pointer = Pointer::Tool(tool.id);
+                    let _ = state.drawing.insert(pointer, vec![]);
+                    (pointer, Some(position), false)
+                }
+                wayland::TabletEvent::Motion { tool, axes } => (
+                    Pointer::Tool(tool.id),
+                    Some(Point::new(axes.x as f32, axes.y as f32)),
+                    false,
+                ),
+                wayland::TabletEvent::Up { tool, axes } => (
+                    Pointer::Tool(tool.id),
+                    Some(Point::new(axes.x as f32, axes.y as f32)),
+                    true,
+                ),
+                _ => return event::Status::Ignored,
+            },
+            Event::Touch(touch::Event::FingerLost { id, .. }) => {
+                // The compositor took the touch sequence, drop the stroke.
+                return match state.drawing.remove(&Pointer::Finger(id)) {
+                    Some(_) => event::Status::Captured,
+                    None => event::Status::Ignored,
+                };
+            }
+            _ => return event::Status::Ignored,
+        };
+
+        let Some(stroke) = state.drawing.get_mut(&pointer) else {
+            return event::Status::Ignored;
+        };
+        if let Some(point) = position.map(|p| p - origin) {
+            if stroke
+                .last()
+                .map_or(true, |last| last.distance(point) >= RESOLUTION)
+            {
+                stroke.push(point);
+            }
+        }
+        if lifted {
+            if let Some(stroke) = state.drawing.remove(&pointer) {
+                shell.publish((self.on_stroke)(stroke));
+            }
+        }
+        event::Status::Captured
+    }
+
+    fn mouse_interaction(
+        &self,
+        _tree: &Tree,
+        layout: Layout<'_>,
+        cursor: Cursor,
+        _viewport: &Rectangle,
+        _renderer: &Renderer,
+    ) -> mouse::Interaction {
+        if cursor.is_over(layout.bounds()) {
+            mouse::Interaction::Crosshair
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Renderer::Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let state = tree.state.downcast_ref::<State>();
+        let bounds = layout.bounds();
+        let origin = Vector::new(bounds.x, bounds.y);
+
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds,
+                border_radius: 0.0.into(),
+                border_width: 0.0,
+                border_color: Color::TRANSPARENT,
+            },
+            Background::Color(self.appearance.background),
+        );
+        renderer.with_layer(bounds, |renderer| {
+            for stroke in self.strokes.iter().chain(state.drawing.values()) {
+                self.draw_stroke(renderer, stroke, origin);
+            }
+        });
+    }
+}
+
+impl<'a, Message> Pad<'a, Message> {
+    /// Draws `stroke` as overlapping dots, the renderer has no paths.
+    fn draw_stroke<Renderer: renderer::Renderer>(
+        &self,
+        renderer: &mut Renderer,
+        stroke: &[Point],
+        origin: Vector,
+    ) {
+        let width = self.stroke_width;
+        let mut dot = |p: Point| {
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: Rectangle {
+                        x: origin.x + p.x - width / 2.0,
+                        y: origin.y + p.y - width / 2.0,
+                        width,
+                        height: width,
+                    },
+                    border_radius: (width / 2.0).into(),
+                    border_width: 0.0,
+                    border_color: Color::TRANSPARENT,
+                },
+                Background::Color(self.appearance.text),
+            );
+        };
+        if let Some(first) = stroke.first() {
+            dot(*first);
+        }
+        let step = width / 3.0;
+        for w in stroke.windows(2) {
+            let steps = (w[0].distance(w[1]) / step).ceil().max(1.0) as usize;
+            for i in 1..=steps {
+                let t = i as f32 / steps as f32;
+                dot(Point::new(
+                    w[0].x + t * (w[1].x - w[0].x),
+                    w[0].y + t * (w[1].y - w[0].y),
+                ));
+            }
+        }
+    }
+}
+
+impl<'a, Message, Renderer> From<Pad<'a, Message>>
+    for Element<'a, Message, Renderer>
+where
+    Message: 'a,
+    Renderer: renderer::Renderer + 'a,
+{
+    fn from(pad: Pad<'a, Message>) -> Self {
+        Element::new(pad)
+    }
+}
+
+/// Settings for a layer surface of `height` at the bottom of the output for
+/// a [`Pad`], e.g. for
+/// [`auto_show`](crate::commands::layer_surface::auto_show).
+pub fn surface(id: window::Id, height: u32) -> SctkLayerSurfaceSettings {
+    SctkLayerSurfaceSettings {
+        id,
+        layer: Layer::Top,
+        keyboard_interactivity: KeyboardInteractivity::None,
+        anchor: Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT,
+        namespace: String::from("handwriting"),
+        size: Some((None, Some(height))),
+        exclusive_zone: height as i32,
+        ..Default::default()
+    }
+}
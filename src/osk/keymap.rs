@@ -0,0 +1,93 @@
+//! XKB keymaps for on-screen layouts.
+//!
+//! The virtual keyboard sends key codes, which the focused client translates
+//! through the keymap of the virtual keyboard. A generated keymap gives every
+//! keysym of a layout a key of its own, so any layout can be typed no matter
+//! which keymap the physical keyboard uses.
+use std::{collections::HashMap, fmt::Write};
+
+use sctk::seat::keyboard::Keysym;
+
+use super::layout::{Layout, LayoutError, Modifier};
+
+/// The first key code XKB assigns to a key.
+const MIN_KEYCODE: u32 = 8;
+/// The last key code XKB can assign.
+const MAX_KEYCODE: u32 = 255;
+
+/// A keymap with one key per keysym of a layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    text: String,
+    codes: HashMap<u32, u32>,
+}
+
+impl Keymap {
+    /// Generate the keymap for `layout`, including keys for every
+    /// [`Modifier`].
+    pub fn new(layout: &Layout) -> Result<Self, LayoutError> {
+        let mut keysyms = layout.keysyms();
+        for modifier in Modifier::ALL {
+            if !keysyms.contains(&modifier.keysym()) {
+                keysyms.push(modifier.keysym());
+            }
+        }
+        if keysyms.len() > (MAX_KEYCODE - MIN_KEYCODE) as usize {
+            return Err(LayoutError::TooManyKeys);
+        }
+
+        let codes: HashMap<u32, u32> = keysyms
+            .iter()
+            .zip(MIN_KEYCODE + 1..)
+            .map(|(keysym, code)| (keysym.raw(), code))
+            .collect();
+
+        // `write!` to a `String` can't fail.
+        let mut keycodes = String::new();
+        let mut symbols = String::new();
+        for keysym in &keysyms {
+            let code = codes[&keysym.raw()];
+            let _ = writeln!(keycodes, "        <K{code}> = {code};");
+            let _ = writeln!(
+                symbols,
+                "        key <K{code}> {{ [ {:#x} ] }};",
+                keysym.raw()
+            );
+        }
+        for modifier in Modifier::ALL {
+            let code = codes[&modifier.keysym().raw()];
+            let _ = writeln!(
+                symbols,
+                "        modifier_map {} {{ <K{code}> }};",
+                modifier.xkb_name()
+            );
+        }
+
+        let text = format!(
+            "xkb_keymap {{\n\
+             \x20   xkb_keycodes \"(unnamed)\" {{\n\
+             \x20       minimum = {MIN_KEYCODE};\n\
+             \x20       maximum = {MAX_KEYCODE};\n\
+             {keycodes}\
+             \x20   }};\n\
+             \x20   xkb_types \"(unnamed)\" {{ include \"complete\" }};\n\
+             \x20   xkb_compatibility \"(unnamed)\" {{ include \"complete\" }};\n\
+             \x20   xkb_symbols \"(unnamed)\" {{\n\
+             {symbols}\
+             \x20   }};\n\
+             }};\n"
+        );
+
+        Ok(Self { text, codes })
+    }
+
+    /// The keymap in the XKB text format.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The evdev key code typing `keysym`, as sent by the virtual keyboard.
+    pub fn code(&self, keysym: Keysym) -> Option<u32> {
+        self.codes.get(&keysym.raw()).map(|code| code - MIN_KEYCODE)
+    }
+}
@@ -0,0 +1,425 @@
+//! Declarative keyboard layouts.
+//!
+//! A layout is a TOML file with one or more named views, each a list of rows
+//! of keys:
+//!
+//! ```toml
+//! name = "English (US)"
+//! # The view shown first, `base` if omitted.
+//! default_view = "base"
+//!
+//! [views.base]
+//! rows = [
+//!     ["q", "w", "e", "r", "t", "y", "u", "i", "o", "p"],
+//!     [{ label = "⇧", view = "upper", width = 1.5 }, "z", "x", { label = "⌫", keysym = "BackSpace" }],
+//!     [{ label = "Ctrl", modifier = "control" }, { label = "", keysym = "space", width = 5.0 }],
+//! ]
+//!
+//! [views.upper]
+//! # Return to the default view after one key.
+//! one_shot = true
+//! rows = [["Q", "W", "E", "R", "T", "Y", "U", "I", "O", "P"]]
+//! ```
+//!
+//! A key given as a string types that character and is labelled with it. A
+//! key given as a table has
+//!
+//! - `label`, the text shown on the key,
+//! - one of `keysym` (an XKB keysym name), `text` (a character),
+//!   `modifier` (`shift`, `control`, `alt` or `super`), `view` (the view to
+//!   switch to) or `spacer = true` (an empty gap),
+//! - `width`, relative to a regular key, `1.0` if omitted,
+//! - `alternates`, strings offered when the key is held down, e.g.
+//!   `{ text = "e", alternates = ["é", "è", "ê", "ë"] }`.
+use std::{collections::HashMap, fs, io, path::Path};
+
+use iced_runtime::core::Point;
+use sctk::seat::keyboard::Keysym;
+use serde::Deserialize;
+use xkbcommon::xkb;
+
+const DEFAULT_VIEW: &str = "base";
+
+/// An error while loading a layout.
+#[derive(Debug, thiserror::Error)]
+pub enum LayoutError {
+    /// The file could not be read.
+    #[error("failed to read layout: {0}")]
+    Io(#[from] io::Error),
+    /// The file is not valid TOML or does not match the layout format.
+    #[error("invalid layout: {0}")]
+    Parse(#[from] toml::de::Error),
+    /// A key could not be understood.
+    #[error("invalid key `{0}`")]
+    InvalidKey(String),
+    /// A keysym name is not known to XKB.
+    #[error("unknown keysym `{0}`")]
+    UnknownKeysym(String),
+    /// A key switches to a view which does not exist.
+    #[error("unknown view `{0}`")]
+    UnknownView(String),
+    /// The layout uses more distinct keysyms than fit in a keymap.
+    #[error("too many distinct keys for a keymap")]
+    TooManyKeys,
+    /// No layout with the name exists.
+    #[error("no layout named `{0}`")]
+    NotFound(String),
+}
+
+/// A modifier an on-screen key can apply to the next key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Modifier {
+    /// Shift
+    Shift,
+    /// Control
+    Control,
+    /// Alt, `Mod1`
+    Alt,
+    /// Super, `Mod4`
+    Super,
+}
+
+impl Modifier {
+    /// All modifiers.
+    pub const ALL: [Modifier; 4] = [
+        Modifier::Shift,
+        Modifier::Control,
+        Modifier::Alt,
+        Modifier::Super,
+    ];
+
+    /// The bit of the modifier in the masks of a generated keymap.
+    pub fn mask(self) -> u32 {
+        match self {
+            Modifier::Shift => 1 << 0,
+            Modifier::Control => 1 << 2,
+            Modifier::Alt => 1 << 3,
+            Modifier::Super => 1 << 6,
+        }
+    }
+
+    /// The keysym of the left modifier key.
+    pub fn keysym(self) -> Keysym {
+        match self {
+            Modifier::Shift => Keysym::Shift_L,
+            Modifier::Control => Keysym::Control_L,
+            Modifier::Alt => Keysym::Alt_L,
+            Modifier::Super => Keysym::Super_L,
+        }
+    }
+
+    /// The name of the modifier in XKB keymaps.
+    pub(crate) fn xkb_name(self) -> &'static str {
+        match self {
+            Modifier::Shift => "Shift",
+            Modifier::Control => "Control",
+            Modifier::Alt => "Mod1",
+            Modifier::Super => "Mod4",
+        }
+    }
+}
+
+/// What pressing a key does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyAction {
+    /// Type a keysym.
+    Keysym(Keysym),
+    /// Toggle a modifier for the next key.
+    Modifier(Modifier),
+    /// Switch to another view.
+    View(String),
+    /// Nothing, the key is a gap.
+    Spacer,
+}
+
+/// A key of a view.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Key {
+    /// The text shown on the key.
+    pub label: String,
+    /// The width relative to a regular key.
+    pub width: f32,
+    /// What pressing the key does.
+    pub action: KeyAction,
+    /// Strings offered when the key is held down.
+    pub alternates: Vec<String>,
+}
+
+/// A row of keys.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    /// The keys from left to right.
+    pub keys: Vec<Key>,
+}
+
+impl Row {
+    /// The width of the row in regular keys.
+    pub fn width(&self) -> f32 {
+        self.keys.iter().map(|k| k.width).sum()
+    }
+}
+
+/// A set of rows shown at the same time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct View {
+    /// The rows from top to bottom.
+    pub rows: Vec<Row>,
+    /// Whether the layout returns to its default view after a key is typed.
+    pub one_shot: bool,
+}
+
+impl View {
+    /// The key at `row` and `index`.
+    pub fn key(&self, row: usize, index: usize) -> Option<&Key> {
+        self.rows.get(row).and_then(|r| r.keys.get(index))
+    }
+
+    /// The width of the widest row in regular keys.
+    pub fn width(&self) -> f32 {
+        self.rows.iter().map(Row::width).fold(0.0, f32::max)
+    }
+
+    /// The center of the key at `row` and `index` in key units, the width of
+    /// a regular key horizontally and the height of a row vertically. Rows
+    /// are centered like the [`Keys`](super::Keys) widget draws them.
+    pub fn key_center(&self, row: usize, index: usize) -> Option<Point> {
+        let keys = &self.rows.get(row)?.keys;
+        let key = keys.get(index)?;
+        let before: f32 = keys[..index].iter().map(|k| k.width).sum();
+        let indent = (self.width() - self.rows[row].width()) / 2.0;
+        Some(Point::new(
+            indent + before + key.width / 2.0,
+            row as f32 + 0.5,
+        ))
+    }
+
+    /// The center of every key typing a single letter, in key units.
+    pub fn letters(&self) -> Vec<(char, Point)> {
+        self.rows
+            .iter()
+            .enumerate()
+            .flat_map(|(r, row)| {
+                row.keys.iter().enumerate().map(move |(i, key)| (r, i, key))
+            })
+            .filter_map(|(r, i, key)| {
+                let mut chars = key.label.chars();
+                match (&key.action, chars.next(), chars.next()) {
+                    (KeyAction::Keysym(_), Some(c), None)
+                        if c.is_alphabetic() =>
+                    {
+                        Some((c, self.key_center(r, i)?))
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
+
+/// An on-screen keyboard layout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    /// The human readable name.
+    pub name: String,
+    /// The view shown first.
+    pub default_view: String,
+    /// The views by name.
+    pub views: HashMap<String, View>,
+}
+
+impl Layout {
+    /// Load a layout file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LayoutError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parse the layout format.
+    pub fn parse(s: &str) -> Result<Self, LayoutError> {
+        let file: LayoutFile = toml::from_str(s)?;
+        let default_view = file
+            .default_view
+            .unwrap_or_else(|| DEFAULT_VIEW.to_string());
+
+        let mut views = HashMap::with_capacity(file.views.len());
+        for (name, view) in file.views {
+            let rows = view
+                .rows
+                .into_iter()
+                .map(|keys| {
+                    keys.into_iter()
+                        .map(KeyFile::resolve)
+                        .collect::<Result<_, _>>()
+                        .map(|keys| Row { keys })
+                })
+                .collect::<Result<_, _>>()?;
+            views.insert(
+                name,
+                View {
+                    rows,
+                    one_shot: view.one_shot,
+                },
+            );
+        }
+
+        if !views.contains_key(&default_view) {
+            return Err(LayoutError::UnknownView(default_view));
+        }
+        for view in views.values() {
+            for key in view.rows.iter().flat_map(|r| &r.keys) {
+                if let KeyAction::View(target) = &key.action {
+                    if !views.contains_key(target) {
+                        return Err(LayoutError::UnknownView(target.clone()));
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            name: file.name,
+            default_view,
+            views,
+        })
+    }
+
+    /// Every keysym the layout can type, each once. Single character
+    /// alternates are included.
+    pub fn keysyms(&self) -> Vec<Keysym> {
+        let keys = self
+            .views
+            .values()
+            .flat_map(|v| v.rows.iter().flat_map(|r| &r.keys));
+        let mut keysyms: Vec<Keysym> = keys
+            .clone()
+            .filter_map(|k| match k.action {
+                KeyAction::Keysym(keysym) => Some(keysym),
+                _ => None,
+            })
+            .chain(
+                keys.flat_map(|k| &k.alternates)
+                    .filter_map(|a| char_keysym(a).ok()),
+            )
+            .collect();
+        keysyms.sort_by_key(|k| k.raw());
+        keysyms.dedup();
+        keysyms
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LayoutFile {
+    #[serde(default)]
+    name: String,
+    default_view: Option<String>,
+    views: HashMap<String, ViewFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ViewFile {
+    #[serde(default)]
+    one_shot: bool,
+    rows: Vec<Vec<KeyFile>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum KeyFile {
+    Text(String),
+    Table(KeyTable),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyTable {
+    label: Option<String>,
+    keysym: Option<String>,
+    text: Option<String>,
+    modifier: Option<Modifier>,
+    view: Option<String>,
+    #[serde(default)]
+    spacer: bool,
+    width: Option<f32>,
+    #[serde(default)]
+    alternates: Vec<String>,
+}
+
+impl KeyFile {
+    fn resolve(self) -> Result<Key, LayoutError> {
+        let key = match self {
+            KeyFile::Text(text) => Key {
+                action: KeyAction::Keysym(char_keysym(&text)?),
+                label: text,
+                width: 1.0,
+                alternates: Vec::new(),
+            },
+            KeyFile::Table(table) => table.resolve()?,
+        };
+        if !(key.width.is_finite() && key.width > 0.0)
+            || key.alternates.iter().any(String::is_empty)
+        {
+            return Err(LayoutError::InvalidKey(key.label));
+        }
+        Ok(key)
+    }
+}
+
+impl KeyTable {
+    fn resolve(self) -> Result<Key, LayoutError> {
+        let actions = [
+            self.keysym.is_some(),
+            self.text.is_some(),
+            self.modifier.is_some(),
+            self.view.is_some(),
+            self.spacer,
+        ];
+        let description = self
+            .label
+            .clone()
+            .or_else(|| self.keysym.clone())
+            .or_else(|| self.text.clone())
+            .unwrap_or_default();
+        if actions.into_iter().filter(|a| *a).count() != 1 {
+            return Err(LayoutError::InvalidKey(description));
+        }
+
+        let (action, default_label) = if let Some(name) = self.keysym {
+            let keysym: Keysym =
+                xkb::keysym_from_name(&name, xkb::KEYSYM_NO_FLAGS).into();
+            if keysym == Keysym::NoSymbol {
+                return Err(LayoutError::UnknownKeysym(name));
+            }
+            (KeyAction::Keysym(keysym), name)
+        } else if let Some(text) = self.text {
+            (KeyAction::Keysym(char_keysym(&text)?), text)
+        } else if let Some(modifier) = self.modifier {
+            (KeyAction::Modifier(modifier), format!("{modifier:?}"))
+        } else if let Some(view) = self.view {
+            (KeyAction::View(view.clone()), view)
+        } else {
+            (KeyAction::Spacer, String::new())
+        };
+
+        Ok(Key {
+            label: self.label.unwrap_or(default_label),
+            width: self.width.unwrap_or(1.0),
+            action,
+            alternates: self.alternates,
+        })
+    }
+}
+
+/// The keysym typing the single character `text`.
+fn char_keysym(text: &str) -> Result<Keysym, LayoutError> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => {
+            let keysym: Keysym = xkb::utf32_to_keysym(c as u32).into();
+            if keysym == Keysym::NoSymbol {
+                Err(LayoutError::InvalidKey(text.to_string()))
+            } else {
+                Ok(keysym)
+            }
+        }
+        _ => Err(LayoutError::InvalidKey(text.to_string())),
+    }
+}
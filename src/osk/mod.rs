@@ -0,0 +1,400 @@
+//! On-screen keyboard.
+//!
+//! A [`Keyboard`] shows the current view of a [`Layout`] with the [`Keys`]
+//! widget, usually on a layer surface anchored to the bottom of the output,
+//! see [`Keyboard::surface`]. Pass those settings to
+//! [`auto_show`](crate::commands::layer_surface::auto_show) to show the
+//! keyboard only while a text input is active. Pressed keys are typed
+//! through the virtual keyboard with a [`Keymap`] generated for the layout,
+//! so they work in any client regardless of the keymap of the physical
+//! keyboard.
+//!
+//! The [`handwriting`] [`Pad`] takes the place of the keys for handwriting
+//! input.
+//!
+//! Layouts are looked up by name in
+//! `$XDG_CONFIG_HOME/iced_wayland_input_method/layouts/<name>.toml` first,
+//! the built-in `us` layout is used when no such file exists. See
+//! [`layout`] for the file format.
+pub mod handwriting;
+pub mod keymap;
+pub mod layout;
+pub mod widget;
+
+use std::{fs, path::PathBuf, time::Instant};
+
+use iced_runtime::command::platform_specific::wayland::{
+    input_method,
+    layer_surface::SctkLayerSurfaceSettings,
+    popup::{SctkPopupSettings, SctkPositioner},
+    virtual_keyboard::ActionInner,
+};
+use iced_runtime::core::event::wayland::{KeyEvent, RawModifiers};
+use iced_runtime::core::{Rectangle, Size};
+use iced_runtime::{window, Command};
+use sctk::reexports::protocols::xdg::shell::client::xdg_positioner::{
+    Anchor as PopupAnchor, ConstraintAdjustment, Gravity,
+};
+use sctk::seat::keyboard::Keysym;
+use sctk::shell::wlr_layer::{Anchor, KeyboardInteractivity, Layer};
+use xkbcommon::xkb;
+
+pub use handwriting::Pad;
+pub use keymap::Keymap;
+pub use layout::{Key, KeyAction, Layout, LayoutError, Modifier, View};
+pub use widget::{AlternateKeys, Appearance, KeyId, Keys, LongPress};
+
+use crate::{
+    commands::{
+        input_method::input_method_action,
+        popup,
+        virtual_keyboard::{tap_modifiers, virtual_keyboard_action},
+    },
+    engine::config_dir,
+};
+
+/// The name of the built-in layout.
+pub const DEFAULT_LAYOUT: &str = "us";
+
+const BUILTIN_LAYOUTS: [(&str, &str); 1] =
+    [("us", include_str!("../../data/osk/us.toml"))];
+
+/// The state of an on-screen keyboard.
+#[derive(Debug, Clone)]
+pub struct Keyboard {
+    layout: Layout,
+    keymap: Keymap,
+    view: String,
+    modifiers: RawModifiers,
+    start: Instant,
+    alternates: Option<OpenAlternates>,
+}
+
+/// The alternates popup of a held key.
+#[derive(Debug, Clone, Copy)]
+struct OpenAlternates {
+    key: KeyId,
+    popup: window::Id,
+    hovered: Option<usize>,
+}
+
+impl Keyboard {
+    /// Creates a keyboard showing the default view of `layout`.
+    pub fn new(layout: Layout) -> Result<Self, LayoutError> {
+        let keymap = Keymap::new(&layout)?;
+        Ok(Self {
+            view: layout.default_view.clone(),
+            layout,
+            keymap,
+            modifiers: RawModifiers::default(),
+            start: Instant::now(),
+            alternates: None,
+        })
+    }
+
+    /// Creates a keyboard with the layout called `name`, preferring the
+    /// user's layout directory over the built-in layouts.
+    pub fn load(name: &str) -> Result<Self, LayoutError> {
+        if let Some(path) = user_layout(name).filter(|p| p.is_file()) {
+            return Self::new(Layout::load(path)?);
+        }
+        let (_, builtin) = BUILTIN_LAYOUTS
+            .iter()
+            .find(|(n, _)| *n == name)
+            .ok_or_else(|| LayoutError::NotFound(name.to_string()))?;
+        Self::new(Layout::parse(builtin)?)
+    }
+
+    /// The names of every layout [`Keyboard::load`] can find, sorted.
+    pub fn available() -> Vec<String> {
+        let mut names: Vec<String> =
+            BUILTIN_LAYOUTS.iter().map(|(n, _)| n.to_string()).collect();
+        let user = config_dir()
+            .map(|dir| dir.join("layouts"))
+            .and_then(|dir| fs::read_dir(dir).ok());
+        for entry in user.into_iter().flatten().flatten() {
+            let path = entry.path();
+            if path.extension().map_or(false, |e| e == "toml") {
+                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                    names.push(stem.to_string());
+                }
+            }
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// The layout.
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// The keymap generated for the layout.
+    pub fn keymap(&self) -> &Keymap {
+        &self.keymap
+    }
+
+    /// The name of the view shown.
+    pub fn view_name(&self) -> &str {
+        &self.view
+    }
+
+    /// The view shown.
+    pub fn view(&self) -> &View {
+        &self.layout.views[&self.view]
+    }
+
+    /// Show the view called `name`, if the layout has one.
+    pub fn set_view(&mut self, name: &str) {
+        if self.layout.views.contains_key(name) {
+            self.view = name.to_string();
+        }
+    }
+
+    /// Whether `modifier` applies to the next key.
+    pub fn is_active(&self, modifier: Modifier) -> bool {
+        let active = self.modifiers.mods_depressed
+            | self.modifiers.mods_latched
+            | self.modifiers.mods_locked;
+        active & modifier.mask() != 0
+    }
+
+    /// Whether `modifier` is locked and applies until it is tapped again.
+    pub fn is_locked(&self, modifier: Modifier) -> bool {
+        self.modifiers.mods_locked & modifier.mask() != 0
+    }
+
+    /// Track the modifiers of the virtual keyboard, as reported by
+    /// `VirtualKeyboardEvent::Modifiers`.
+    pub fn modifiers_changed(&mut self, modifiers: RawModifiers) {
+        self.modifiers = modifiers;
+    }
+
+    /// Press and release the key `id` of the current view.
+    ///
+    /// Keysyms are typed through the virtual keyboard, which applies and then
+    /// clears latched modifiers. Modifier keys are sticky: a tap latches the
+    /// modifier for the next key, a second tap locks it and a third releases
+    /// it, see [`tap_modifiers`]. View keys only change the state of the
+    /// keyboard.
+    pub fn press<Message>(&mut self, id: KeyId) -> Command<Message> {
+        let Some(key) = self.view().key(id.row, id.index) else {
+            return Command::none();
+        };
+        match key.action.clone() {
+            KeyAction::Keysym(keysym) => {
+                let command = self.type_keysym(keysym);
+                self.typed();
+                command
+            }
+            KeyAction::Modifier(modifier) => tap_modifiers(modifier.mask()),
+            KeyAction::View(view) => {
+                self.view = view;
+                Command::none()
+            }
+            KeyAction::Spacer => Command::none(),
+        }
+    }
+
+    /// Return from a one-shot view after a key was typed.
+    fn typed(&mut self) {
+        if self.view().one_shot {
+            self.view = self.layout.default_view.clone();
+        }
+    }
+
+    /// Handle a [`LongPress`] reported by the [`Keys`] on the surface
+    /// `parent`, showing the alternates of the key in the popup `popup`.
+    ///
+    /// A chosen single character alternate is typed through the virtual
+    /// keyboard, longer ones are sent as `CommitString`, which has to be
+    /// applied with a commit like engine responses.
+    pub fn long_press<Message>(
+        &mut self,
+        event: LongPress,
+        parent: window::Id,
+        popup: window::Id,
+    ) -> Command<Message> {
+        match event {
+            LongPress::Open { key, bounds } => {
+                let close = self.close_alternates();
+                let count = self
+                    .view()
+                    .key(key.row, key.index)
+                    .map_or(0, |k| k.alternates.len());
+                if count == 0 {
+                    return close;
+                }
+                self.alternates = Some(OpenAlternates {
+                    key,
+                    popup,
+                    hovered: None,
+                });
+                Command::batch([
+                    close,
+                    popup::get_popup(alternates_popup(
+                        parent, popup, bounds, count,
+                    )),
+                ])
+            }
+            LongPress::Hover { key, index } => {
+                if let Some(open) =
+                    self.alternates.as_mut().filter(|o| o.key == key)
+                {
+                    open.hovered = Some(index);
+                }
+                Command::none()
+            }
+            LongPress::Choose { key, index } => {
+                let close = self.close_alternates();
+                let Some(alternate) = self
+                    .view()
+                    .key(key.row, key.index)
+                    .and_then(|k| k.alternates.get(index))
+                    .cloned()
+                else {
+                    return close;
+                };
+                let command = self.type_text(alternate);
+                self.typed();
+                Command::batch([close, command])
+            }
+            LongPress::Cancel { .. } => self.close_alternates(),
+        }
+    }
+
+    /// The alternates to show in the popup `id`, if it is the alternates
+    /// popup.
+    pub fn alternates(&self, id: window::Id) -> Option<AlternateKeys<'_>> {
+        let open = self.alternates.filter(|o| o.popup == id)?;
+        let key = self.view().key(open.key.row, open.key.index)?;
+        Some(AlternateKeys::new(&key.alternates, open.hovered))
+    }
+
+    fn close_alternates<Message>(&mut self) -> Command<Message> {
+        match self.alternates.take() {
+            Some(open) => popup::destroy_popup(open.popup),
+            None => Command::none(),
+        }
+    }
+
+    fn type_text<Message>(&mut self, text: String) -> Command<Message> {
+        let mut chars = text.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            let keysym: Keysym = xkb::utf32_to_keysym(c as u32).into();
+            if self.keymap.code(keysym).is_some() {
+                return self.type_keysym(keysym);
+            }
+        }
+        input_method_action(input_method::ActionInner::CommitString(text))
+    }
+
+    fn type_keysym<Message>(&mut self, keysym: Keysym) -> Command<Message> {
+        let Some(code) = self.keymap.code(keysym) else {
+            return Command::none();
+        };
+        let time = self.start.elapsed().as_millis() as u32;
+        let utf8 = Some(xkb::keysym_to_utf8(keysym.into()))
+            .map(|s| s.trim_end_matches('\0').to_string())
+            .filter(|s| !s.is_empty());
+        let event = KeyEvent {
+            time,
+            raw_code: code,
+            keysym,
+            utf8,
+        };
+
+        // The keymap is only uploaded when it changed.
+        let actions = [
+            ActionInner::Keymap(self.keymap.text().into()),
+            ActionInner::KeyPressed(event.clone()),
+            ActionInner::KeyReleased(KeyEvent {
+                utf8: None,
+                ..event
+            }),
+        ];
+        Command::batch(actions.into_iter().map(virtual_keyboard_action))
+    }
+
+    /// The [`Keys`] of the current view, reporting presses as `on_key` and
+    /// long presses, for [`Keyboard::long_press`], as `on_long_press`.
+    /// Chain [`Keys::on_swipe`] for gesture typing.
+    pub fn widget<'a, Message>(
+        &'a self,
+        on_key: impl Fn(KeyId) -> Message + 'a,
+        on_long_press: impl Fn(LongPress) -> Message + 'a,
+    ) -> Keys<'a, Message> {
+        Keys::new(self.view(), on_key)
+            .on_long_press(on_long_press)
+            .active(|action| match action {
+                KeyAction::Modifier(m) => self.is_active(*m),
+                _ => false,
+            })
+    }
+
+    /// Settings for a layer surface `row_height` logical pixels high per row,
+    /// anchored to the bottom of the output and reserving its space.
+    ///
+    /// The surface never takes keyboard focus, so the focused text input
+    /// stays active while keys are pressed.
+    pub fn surface(
+        &self,
+        id: window::Id,
+        row_height: u32,
+    ) -> SctkLayerSurfaceSettings {
+        let rows = self
+            .layout
+            .views
+            .values()
+            .map(|v| v.rows.len())
+            .max()
+            .unwrap_or(0) as u32;
+        let height = rows * row_height;
+        SctkLayerSurfaceSettings {
+            id,
+            layer: Layer::Top,
+            keyboard_interactivity: KeyboardInteractivity::None,
+            anchor: Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT,
+            namespace: String::from("osk"),
+            size: Some((None, Some(height))),
+            exclusive_zone: height as i32,
+            ..Default::default()
+        }
+    }
+}
+
+/// A popup for `count` alternates above the key at `key`, matching
+/// [`widget::alternates_bounds`].
+fn alternates_popup(
+    parent: window::Id,
+    id: window::Id,
+    key: Rectangle,
+    count: usize,
+) -> SctkPopupSettings {
+    let size = Size::new(key.width * count as f32, key.height);
+    SctkPopupSettings {
+        parent,
+        id,
+        positioner: SctkPositioner {
+            size: Some((size.width.ceil() as u32, size.height.ceil() as u32)),
+            size_limits: iced_runtime::core::layout::Limits::new(size, size),
+            anchor_rect: Rectangle {
+                x: key.x.round() as i32,
+                y: key.y.round() as i32,
+                width: key.width.round().max(1.0) as i32,
+                height: key.height.round().max(1.0) as i32,
+            },
+            anchor: PopupAnchor::Top,
+            gravity: Gravity::Top,
+            constraint_adjustment: ConstraintAdjustment::SlideX.bits(),
+            ..Default::default()
+        },
+        parent_size: None,
+        grab: false,
+    }
+}
+
+fn user_layout(name: &str) -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("layouts").join(format!("{name}.toml")))
+}
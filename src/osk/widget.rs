@@ -0,0 +1,762 @@
+//! The widgets drawing a view of a layout and the alternates of a key.
+use std::collections::HashMap;
+
+use iced_runtime::core::{
+    alignment, event, layout,
+    mouse::{self, Cursor},
+    renderer, text,
+    time::{Duration, Instant},
+    touch,
+    widget::{tree, Tree},
+    window, Background, Clipboard, Color, Element, Event, Layout, Length,
+    Point, Rectangle, Shell, Widget,
+};
+
+use super::layout::{KeyAction, View};
+
+/// How long a key has to be held down before its alternates are offered.
+pub const DEFAULT_LONG_PRESS_DELAY: Duration = Duration::from_millis(400);
+
+/// How far a swipe has to move before another point is recorded, in key
+/// units.
+const SWIPE_RESOLUTION: f32 = 0.1;
+/// How far a press has to travel to become a swipe without entering another
+/// key, in key units.
+const SWIPE_MIN_LENGTH: f32 = 1.0;
+
+/// The position of a key in the current view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyId {
+    /// The row, from the top.
+    pub row: usize,
+    /// The key in the row, from the left.
+    pub index: usize,
+}
+
+/// The progress of a long press on a key with alternates.
+///
+/// The mouse button or finger stays on the keyboard surface while the
+/// alternates are shown, the [`Keys`] widget tracks it and reports which
+/// alternate it is over.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LongPress {
+    /// The key was held down long enough, its alternates should be shown
+    /// in [`alternates_bounds`] of `bounds`.
+    Open {
+        /// The key held down.
+        key: KeyId,
+        /// The bounds of the key, relative to the keyboard surface.
+        bounds: Rectangle,
+    },
+    /// Another alternate is under the pointer.
+    Hover {
+        /// The key held down.
+        key: KeyId,
+        /// The alternate under the pointer.
+        index: usize,
+    },
+    /// The pointer was released over an alternate.
+    Choose {
+        /// The key held down.
+        key: KeyId,
+        /// The alternate chosen.
+        index: usize,
+    },
+    /// The pointer was released away from the alternates or the touch
+    /// sequence was cancelled, nothing is typed.
+    Cancel {
+        /// The key held down.
+        key: KeyId,
+    },
+}
+
+/// The colors of the keyboard.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Appearance {
+    /// The color behind the keys.
+    pub background: Color,
+    /// The color of a key.
+    pub key: Color,
+    /// The color of a key which is held down or a modifier which is active.
+    pub key_pressed: Color,
+    /// The color of the labels.
+    pub text: Color,
+    /// The radius of the key corners.
+    pub border_radius: f32,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            background: Color::from_rgb8(0x20, 0x20, 0x20),
+            key: Color::from_rgb8(0x44, 0x44, 0x44),
+            key_pressed: Color::from_rgb8(0x70, 0x70, 0x70),
+            text: Color::WHITE,
+            border_radius: 6.0,
+        }
+    }
+}
+
+/// The bounds of `count` alternates shown for the key at `key`: one key
+/// sized cell each, in a row above the key, centered on it and kept
+/// horizontally within `within`.
+pub fn alternates_bounds(
+    key: Rectangle,
+    count: usize,
+    within: Rectangle,
+) -> Rectangle {
+    let width = key.width * count as f32;
+    let x = (key.center_x() - width / 2.0)
+        .min(within.x + within.width - width)
+        .max(within.x);
+    Rectangle {
+        x,
+        y: key.y - key.height,
+        width,
+        height: key.height,
+    }
+}
+
+/// The alternate in `bounds` closest to `x`.
+fn alternate_at(bounds: Rectangle, count: usize, x: f32) -> usize {
+    let cell = bounds.width / count.max(1) as f32;
+    (((x - bounds.x) / cell).max(0.0) as usize).min(count.saturating_sub(1))
+}
+
+/// The keys of a [`View`], laid out row by row to fill the available space.
+///
+/// A key is reported through `on_key` when the mouse button or finger
+/// pressing it is released over it. Keys with alternates report a
+/// [`LongPress`] instead if they are held down, see [`Keys::on_long_press`].
+/// Moving onto other keys before releasing is a swipe, see
+/// [`Keys::on_swipe`].
+pub struct Keys<'a, Message> {
+    view: &'a View,
+    on_key: Box<dyn Fn(KeyId) -> Message + 'a>,
+    on_long_press: Option<Box<dyn Fn(LongPress) -> Message + 'a>>,
+    on_swipe: Option<Box<dyn Fn(Vec<Point>) -> Message + 'a>>,
+    long_press_delay: Duration,
+    is_active: Box<dyn Fn(&KeyAction) -> bool + 'a>,
+    spacing: f32,
+    text_size: Option<f32>,
+    appearance: Appearance,
+}
+
+impl<'a, Message> Keys<'a, Message> {
+    /// Creates the keys of `view`.
+    pub fn new(view: &'a View, on_key: impl Fn(KeyId) -> Message + 'a) -> Self {
+        Self {
+            view,
+            on_key: Box::new(on_key),
+            on_long_press: None,
+            on_swipe: None,
+            long_press_delay: DEFAULT_LONG_PRESS_DELAY,
+            is_active: Box::new(|_| false),
+            spacing: 4.0,
+            text_size: None,
+            appearance: Appearance::default(),
+        }
+    }
+
+    /// Report long presses on keys with alternates. Without this, such keys
+    /// are typed like any other key.
+    pub fn on_long_press(
+        mut self,
+        on_long_press: impl Fn(LongPress) -> Message + 'a,
+    ) -> Self {
+        self.on_long_press = Some(Box::new(on_long_press));
+        self
+    }
+
+    /// Report swipes across the keys as their path in key units, see
+    /// [`View::key_center`], for gesture typing with
+    /// [`SwipeEngine`](crate::engine::swipe::SwipeEngine). Without this,
+    /// moving off a key before releasing cancels it.
+    pub fn on_swipe(
+        mut self,
+        on_swipe: impl Fn(Vec<Point>) -> Message + 'a,
+    ) -> Self {
+        self.on_swipe = Some(Box::new(on_swipe));
+        self
+    }
+
+    /// Sets how long a key has to be held down to show its alternates.
+    pub fn long_press_delay(mut self, delay: Duration) -> Self {
+        self.long_press_delay = delay;
+        self
+    }
+
+    /// Highlight keys for which `is_active` returns true, e.g. latched
+    /// modifiers.
+    pub fn active(
+        mut self,
+        is_active: impl Fn(&KeyAction) -> bool + 'a,
+    ) -> Self {
+        self.is_active = Box::new(is_active);
+        self
+    }
+
+    /// Sets the gap between keys.
+    pub fn spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Sets the size of the labels.
+    pub fn text_size(mut self, size: f32) -> Self {
+        self.text_size = Some(size);
+        self
+    }
+
+    /// Sets the [`Appearance`].
+    pub fn appearance(mut self, appearance: Appearance) -> Self {
+        self.appearance = appearance;
+        self
+    }
+
+    /// The bounds of every key which can be pressed.
+    fn keys(
+        &self,
+        bounds: Rectangle,
+    ) -> impl Iterator<Item = (KeyId, Rectangle)> + '_ {
+        let rows = self.view.rows.len().max(1) as f32;
+        let unit = bounds.width / self.view.width().max(1.0);
+        let row_height = bounds.height / rows;
+        let spacing = self.spacing;
+
+        self.view.rows.iter().enumerate().flat_map(move |(r, row)| {
+            let y = bounds.y + r as f32 * row_height;
+            // Rows narrower than the widest one are centered.
+            let mut x = bounds.x + (bounds.width - row.width() * unit) / 2.0;
+            row.keys.iter().enumerate().filter_map(move |(index, key)| {
+                let key_bounds = Rectangle {
+                    x: x + spacing / 2.0,
+                    y: y + spacing / 2.0,
+                    width: (key.width * unit - spacing).max(0.0),
+                    height: (row_height - spacing).max(0.0),
+                };
+                x += key.width * unit;
+                (key.action != KeyAction::Spacer)
+                    .then_some((KeyId { row: r, index }, key_bounds))
+            })
+        })
+    }
+
+    fn key_at(&self, bounds: Rectangle, position: Point) -> Option<KeyId> {
+        self.keys(bounds)
+            .find(|(_, b)| b.contains(position))
+            .map(|(id, _)| id)
+    }
+
+    fn key_bounds(&self, bounds: Rectangle, key: KeyId) -> Option<Rectangle> {
+        self.keys(bounds).find(|(id, _)| *id == key).map(|(_, b)| b)
+    }
+
+    /// `position` in key units, see [`View::key_center`].
+    fn to_units(&self, bounds: Rectangle, position: Point) -> Point {
+        let unit = bounds.width / self.view.width().max(1.0);
+        let row_height = bounds.height / self.view.rows.len().max(1) as f32;
+        Point::new(
+            (position.x - bounds.x) / unit,
+            (position.y - bounds.y) / row_height,
+        )
+    }
+
+    fn alternates(&self, key: KeyId) -> usize {
+        match self.on_long_press {
+            Some(_) => self
+                .view
+                .key(key.row, key.index)
+                .map_or(0, |k| k.alternates.len()),
+            None => 0,
+        }
+    }
+
+    fn long_press(&self, shell: &mut Shell<'_, Message>, event: LongPress) {
+        if let Some(on_long_press) = &self.on_long_press {
+            shell.publish(on_long_press(event));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) enum Pointer {
+    Mouse,
+    Finger(touch::Finger),
+    /// A tablet tool, by its id.
+    Tool(u32),
+}
+
+#[derive(Debug, Clone)]
+struct Press {
+    key: KeyId,
+    since: Instant,
+    /// The alternates of the key and the one under the pointer, once the key
+    /// was held long enough.
+    alternates: Option<(Rectangle, usize)>,
+    /// The path in key units, if swipes are reported.
+    path: Vec<Point>,
+    /// Whether the pointer entered another key or travelled
+    /// [`SWIPE_MIN_LENGTH`].
+    swiping: bool,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    pressed: HashMap<Pointer, Press>,
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for Keys<'a, Message>
+where
+    Renderer: text::Renderer,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn width(&self) -> Length {
+        Length::Fill
+    }
+
+    fn height(&self) -> Length {
+        Length::Fill
+    }
+
+    fn layout(
+        &self,
+        _renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        let size = limits.width(Length::Fill).height(Length::Fill).max();
+        layout::Node::new(size)
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: Cursor,
+        _renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        _viewport: &Rectangle,
+    ) -> event::Status {
+        let state = tree.state.downcast_mut::<State>();
+        let bounds = layout.bounds();
+
+        enum Phase {
+            Pressed,
+            Moved,
+            Released,
+        }
+
+        let (pointer, position, phase) = match event {
+            Event::Window(window::Event::RedrawRequested(now)) => {
+                for press in state.pressed.values_mut() {
+                    let count = self.alternates(press.key);
+                    if count == 0
+                        || press.swiping
+                        || press.alternates.is_some()
+                        || now.duration_since(press.since)
+                            < self.long_press_delay
+                    {
+                        continue;
+                    }
+                    let Some(key_bounds) = self.key_bounds(bounds, press.key)
+                    else {
+                        continue;
+                    };
+                    let alternates =
+                        alternates_bounds(key_bounds, count, bounds);
+                    let index =
+                        alternate_at(alternates, count, key_bounds.center_x());
+                    press.alternates = Some((alternates, index));
+                    self.long_press(
+                        shell,
+                        LongPress::Open {
+                            key: press.key,
+                            bounds: key_bounds,
+                        },
+                    );
+                    self.long_press(
+                        shell,
+                        LongPress::Hover {
+                            key: press.key,
+                            index,
+                        },
+                    );
+                }
+                return event::Status::Ignored;
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                (Pointer::Mouse, cursor.position(), Phase::Pressed)
+            }
+            Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                (Pointer::Mouse, Some(position), Phase::Moved)
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                (Pointer::Mouse, cursor.position(), Phase::Released)
+            }
+            Event::Touch(touch::Event::FingerPressed { id, position }) => {
+                (Pointer::Finger(id), Some(position), Phase::Pressed)
+            }
+            Event::Touch(touch::Event::FingerMoved { id, position }) => {
+                (Pointer::Finger(id), Some(position), Phase::Moved)
+            }
+            Event::Touch(touch::Event::FingerLifted { id, position }) => {
+                (Pointer::Finger(id), Some(position), Phase::Released)
+            }
+            Event::Touch(touch::Event::FingerLost { id, .. }) => {
+                // The compositor took the touch sequence, nothing is typed.
+                return match state.pressed.remove(&Pointer::Finger(id)) {
+                    Some(press) => {
+                        if press.alternates.is_some() {
+                            self.long_press(
+                                shell,
+                                LongPress::Cancel { key: press.key },
+                            );
+                        }
+                        event::Status::Captured
+                    }
+                    None => event::Status::Ignored,
+                };
+            }
+            _ => return event::Status::Ignored,
+        };
+
+        match phase {
+            Phase::Pressed => {
+                match position.and_then(|p| self.key_at(bounds, p)) {
+                    Some(key) => {
+                        let since = Instant::now();
+                        if self.alternates(key) > 0 {
+                            shell.request_redraw(window::RedrawRequest::At(
+                                since + self.long_press_delay,
+                            ));
+                        }
+                        let path = match (&self.on_swipe, position) {
+                            (Some(_), Some(p)) => {
+                                vec![self.to_units(bounds, p)]
+                            }
+                            _ => Vec::new(),
+                        };
+                        let _ = state.pressed.insert(
+                            pointer,
+                            Press {
+                                key,
+                                since,
+                                alternates: None,
+                                path,
+                                swiping: false,
+                            },
+                        );
+                        event::Status::Captured
+                    }
+                    None => event::Status::Ignored,
+                }
+            }
+            Phase::Moved => {
+                let Some(press) = state.pressed.get_mut(&pointer) else {
+                    return event::Status::Ignored;
+                };
+                let Some(position) = position else {
+                    return event::Status::Captured;
+                };
+                let Some((alternates, hovered)) = press.alternates.as_mut()
+                else {
+                    if self.on_swipe.is_some() {
+                        let point = self.to_units(bounds, position);
+                        if press.path.last().map_or(true, |last| {
+                            last.distance(point) >= SWIPE_RESOLUTION
+                        }) {
+                            press.path.push(point);
+                        }
+                        let other_key = matches!(
+                            self.key_at(bounds, position),
+                            Some(key) if key != press.key
+                        );
+                        let length: f32 = press
+                            .path
+                            .windows(2)
+                            .map(|w| w[0].distance(w[1]))
+                            .sum();
+                        if other_key || length >= SWIPE_MIN_LENGTH {
+                            press.swiping = true;
+                        }
+                    }
+                    return event::Status::Captured;
+                };
+                let count = self.alternates(press.key);
+                let index = alternate_at(*alternates, count, position.x);
+                if index != *hovered {
+                    *hovered = index;
+                    self.long_press(
+                        shell,
+                        LongPress::Hover {
+                            key: press.key,
+                            index,
+                        },
+                    );
+                }
+                event::Status::Captured
+            }
+            Phase::Released => {
+                let Some(press) = state.pressed.remove(&pointer) else {
+                    return event::Status::Ignored;
+                };
+                match press.alternates {
+                    Some((alternates, hovered)) => {
+                        // Sliding back down onto the keyboard cancels.
+                        let event = match position {
+                            Some(p)
+                                if p.y
+                                    > alternates.y
+                                        + 2.0 * alternates.height =>
+                            {
+                                LongPress::Cancel { key: press.key }
+                            }
+                            _ => LongPress::Choose {
+                                key: press.key,
+                                index: hovered,
+                            },
+                        };
+                        self.long_press(shell, event);
+                    }
+                    None if press.swiping => {
+                        let mut path = press.path;
+                        if let Some(p) = position {
+                            path.push(self.to_units(bounds, p));
+                        }
+                        if let Some(on_swipe) = &self.on_swipe {
+                            shell.publish(on_swipe(path));
+                        }
+                    }
+                    None => {
+                        // Sliding onto another key cancels it. A drift into
+                        // the gutter short of a swipe still types the key.
+                        let key = position.and_then(|p| self.key_at(bounds, p));
+                        let drifted = key.is_none() && self.on_swipe.is_some();
+                        if key == Some(press.key) || drifted {
+                            shell.publish((self.on_key)(press.key));
+                        }
+                    }
+                }
+                event::Status::Captured
+            }
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        _tree: &Tree,
+        layout: Layout<'_>,
+        cursor: Cursor,
+        _viewport: &Rectangle,
+        _renderer: &Renderer,
+    ) -> mouse::Interaction {
+        match cursor.position() {
+            Some(p) if self.key_at(layout.bounds(), p).is_some() => {
+                mouse::Interaction::Pointer
+            }
+            _ => mouse::Interaction::default(),
+        }
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Renderer::Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let state = tree.state.downcast_ref::<State>();
+        let bounds = layout.bounds();
+
+        fill(renderer, bounds, 0.0, self.appearance.background);
+        for (id, key_bounds) in self.keys(bounds) {
+            let Some(key) = self.view.key(id.row, id.index) else {
+                continue;
+            };
+            let pressed = state.pressed.values().any(|p| p.key == id)
+                || (self.is_active)(&key.action);
+            draw_key(
+                renderer,
+                key_bounds,
+                &key.label,
+                pressed,
+                &self.appearance,
+                self.text_size,
+            );
+        }
+    }
+}
+
+impl<'a, Message, Renderer> From<Keys<'a, Message>>
+    for Element<'a, Message, Renderer>
+where
+    Message: 'a,
+    Renderer: text::Renderer + 'a,
+{
+    fn from(keys: Keys<'a, Message>) -> Self {
+        Element::new(keys)
+    }
+}
+
+/// The alternates of a key, for the popup shown on a [`LongPress`].
+///
+/// The widget only draws, the choice is made through the [`Keys`] widget
+/// which keeps receiving the pointer.
+pub struct AlternateKeys<'a> {
+    alternates: &'a [String],
+    hovered: Option<usize>,
+    text_size: Option<f32>,
+    appearance: Appearance,
+}
+
+impl<'a> AlternateKeys<'a> {
+    /// Creates the cells of `alternates`, highlighting `hovered`.
+    pub fn new(alternates: &'a [String], hovered: Option<usize>) -> Self {
+        Self {
+            alternates,
+            hovered,
+            text_size: None,
+            appearance: Appearance::default(),
+        }
+    }
+
+    /// Sets the size of the labels.
+    pub fn text_size(mut self, size: f32) -> Self {
+        self.text_size = Some(size);
+        self
+    }
+
+    /// Sets the [`Appearance`].
+    pub fn appearance(mut self, appearance: Appearance) -> Self {
+        self.appearance = appearance;
+        self
+    }
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for AlternateKeys<'a>
+where
+    Renderer: text::Renderer,
+{
+    fn width(&self) -> Length {
+        Length::Fill
+    }
+
+    fn height(&self) -> Length {
+        Length::Fill
+    }
+
+    fn layout(
+        &self,
+        _renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        let size = limits.width(Length::Fill).height(Length::Fill).max();
+        layout::Node::new(size)
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Renderer::Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let cell = bounds.width / self.alternates.len().max(1) as f32;
+
+        fill(
+            renderer,
+            bounds,
+            self.appearance.border_radius,
+            self.appearance.background,
+        );
+        for (i, alternate) in self.alternates.iter().enumerate() {
+            draw_key(
+                renderer,
+                Rectangle {
+                    x: bounds.x + i as f32 * cell + 2.0,
+                    y: bounds.y + 2.0,
+                    width: (cell - 4.0).max(0.0),
+                    height: (bounds.height - 4.0).max(0.0),
+                },
+                alternate,
+                self.hovered == Some(i),
+                &self.appearance,
+                self.text_size,
+            );
+        }
+    }
+}
+
+impl<'a, Message, Renderer> From<AlternateKeys<'a>>
+    for Element<'a, Message, Renderer>
+where
+    Renderer: text::Renderer + 'a,
+{
+    fn from(alternates: AlternateKeys<'a>) -> Self {
+        Element::new(alternates)
+    }
+}
+
+fn fill<Renderer: renderer::Renderer>(
+    renderer: &mut Renderer,
+    bounds: Rectangle,
+    border_radius: f32,
+    color: Color,
+) {
+    renderer.fill_quad(
+        renderer::Quad {
+            bounds,
+            border_radius: border_radius.into(),
+            border_width: 0.0,
+            border_color: Color::TRANSPARENT,
+        },
+        Background::Color(color),
+    );
+}
+
+fn draw_key<Renderer: text::Renderer>(
+    renderer: &mut Renderer,
+    bounds: Rectangle,
+    label: &str,
+    pressed: bool,
+    appearance: &Appearance,
+    text_size: Option<f32>,
+) {
+    let color = if pressed {
+        appearance.key_pressed
+    } else {
+        appearance.key
+    };
+    fill(renderer, bounds, appearance.border_radius, color);
+    renderer.fill_text(text::Text {
+        content: label,
+        bounds: Rectangle {
+            x: bounds.center_x(),
+            y: bounds.center_y(),
+            ..bounds
+        },
+        size: text_size.unwrap_or_else(|| renderer.default_size()),
+        line_height: text::LineHeight::default(),
+        color: appearance.text,
+        font: renderer.default_font(),
+        horizontal_alignment: alignment::Horizontal::Center,
+        vertical_alignment: alignment::Vertical::Center,
+        shaping: text::Shaping::Advanced,
+    });
+}
@@ -4,8 +4,11 @@ use crate::{
         keysym_to_vkey, modifiers_to_native, pointer_axis_to_native,
         pointer_button_to_native,
     },
-    dpi::PhysicalSize,
-    handlers::input_method::keyboard::RawModifiers,
+    dpi::{LogicalPosition, PhysicalSize},
+    handlers::input_method::{
+        keyboard::{ComposeStatus, RawModifiers},
+        Privacy, Profile, TextChange,
+    },
 };
 
 use iced_futures::core::event::{
@@ -14,7 +17,7 @@ use iced_futures::core::event::{
 };
 use iced_runtime::{
     command::platform_specific::wayland::data_device::DndIcon,
-    core::{event::wayland, keyboard, mouse, window, Point},
+    core::{event::wayland, keyboard, mouse, touch, window, Point},
     keyboard::KeyCode,
     window::Id as SurfaceId,
 };
@@ -25,7 +28,7 @@ use sctk::{
         protocol::{
             wl_data_device_manager::DndAction, wl_keyboard::WlKeyboard,
             wl_output::WlOutput, wl_pointer::WlPointer, wl_seat::WlSeat,
-            wl_surface::WlSurface,
+            wl_surface::WlSurface, wl_touch::WlTouch,
         },
         Proxy,
     },
@@ -159,7 +162,19 @@ pub enum SctkEvent {
         variant: InputMethodPopupEventVariant,
         id: WlSurface,
     },
-    // TODO data device & touch
+    TouchEvent {
+        variant: TouchEventVariant,
+        touch_id: WlTouch,
+        seat_id: WlSeat,
+        /// The surface the touch point went down on
+        surface: WlSurface,
+    },
+    TabletEvent {
+        variant: TabletEventVariant,
+        seat_id: WlSeat,
+        /// The surface the tool or pad is focused on
+        surface: WlSurface,
+    },
 
     //
     // Surface Events
@@ -207,7 +222,10 @@ pub enum SctkEvent {
         event: DndOfferEvent,
         surface: WlSurface,
     },
-    SelectionOffer(SelectionOfferEvent),
+    SelectionOffer(SelectionOfferEvent, SelectionKind),
+    DataControl(DataControlEvent),
+    ForeignToplevel(ForeignToplevelEvent),
+    VirtualKeyboard(VirtualKeyboardEvent),
 }
 
 #[derive(Debug, Clone)]
@@ -234,6 +252,25 @@ pub enum DataSourceEvent {
     },
 }
 
+/// The selection an offer belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelectionKind {
+    /// The regular clipboard, `wl_data_device`.
+    #[default]
+    Clipboard,
+    /// The primary selection, pasted with a middle click.
+    Primary,
+}
+
+impl From<SelectionKind> for wayland::SelectionKind {
+    fn from(kind: SelectionKind) -> Self {
+        match kind {
+            SelectionKind::Clipboard => wayland::SelectionKind::Clipboard,
+            SelectionKind::Primary => wayland::SelectionKind::Primary,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum SelectionOfferEvent {
     /// A Selection offer has been introduced with the given mime types.
@@ -247,6 +284,32 @@ pub enum SelectionOfferEvent {
     },
 }
 
+#[derive(Debug, Clone)]
+pub enum VirtualKeyboardEvent {
+    /// The modifiers set on the virtual keyboard changed.
+    Modifiers(RawModifiers),
+}
+
+#[derive(Debug, Clone)]
+pub enum ForeignToplevelEvent {
+    Opened(wayland::ForeignToplevel),
+    Changed(wayland::ForeignToplevel),
+    Closed(wayland::ForeignToplevel),
+    /// The open toplevels, in reply to a request.
+    List(Vec<wayland::ForeignToplevel>),
+}
+
+#[derive(Debug, Clone)]
+pub enum DataControlEvent {
+    /// The text of a new selection, read without keyboard focus.
+    Selection {
+        /// mime type of the data
+        mime_type: String,
+        /// The raw data
+        data: Vec<u8>,
+    },
+}
+
 #[derive(Debug, Clone)]
 pub enum DndOfferEvent {
     /// A DnD offer has been introduced with the given mime types.
@@ -302,6 +365,82 @@ pub enum KeyboardEventVariant {
     Modifiers(Modifiers),
 }
 
+/// Positions are relative to the surface the point went down on.
+#[derive(Debug, Clone)]
+pub enum TouchEventVariant {
+    Down {
+        id: i32,
+        position: LogicalPosition<f64>,
+    },
+    Up {
+        id: i32,
+        position: LogicalPosition<f64>,
+    },
+    Motion {
+        id: i32,
+        position: LogicalPosition<f64>,
+    },
+    /// The compositor cancelled the touch sequence.
+    Cancel {
+        id: i32,
+        position: LogicalPosition<f64>,
+    },
+}
+
+impl TouchEventVariant {
+    pub fn id(&self) -> i32 {
+        match self {
+            Self::Down { id, .. }
+            | Self::Up { id, .. }
+            | Self::Motion { id, .. }
+            | Self::Cancel { id, .. } => *id,
+        }
+    }
+
+    pub fn position(&self) -> LogicalPosition<f64> {
+        match self {
+            Self::Down { position, .. }
+            | Self::Up { position, .. }
+            | Self::Motion { position, .. }
+            | Self::Cancel { position, .. } => *position,
+        }
+    }
+}
+
+/// Tool events are reported once their frame is complete, with the state
+/// of every axis at that point.
+#[derive(Debug, Clone)]
+pub enum TabletEventVariant {
+    ProximityIn {
+        tool: wayland::TabletTool,
+        axes: wayland::TabletAxes,
+    },
+    ProximityOut {
+        tool: wayland::TabletTool,
+    },
+    Down {
+        tool: wayland::TabletTool,
+        axes: wayland::TabletAxes,
+    },
+    Up {
+        tool: wayland::TabletTool,
+        axes: wayland::TabletAxes,
+    },
+    Motion {
+        tool: wayland::TabletTool,
+        axes: wayland::TabletAxes,
+    },
+    Button {
+        tool: wayland::TabletTool,
+        button: u32,
+        pressed: bool,
+    },
+    PadButton {
+        button: u32,
+        pressed: bool,
+    },
+}
+
 #[derive(Debug, Clone)]
 pub enum InputMethodEventVariant {
     Activate,
@@ -313,6 +452,9 @@ pub enum InputMethodEventVariant {
     },
     TextChangeCause(WEnum<ChangeCause>),
     ContentType(WEnum<ContentHint>, WEnum<ContentPurpose>),
+    Privacy(Privacy),
+    Profile(Profile),
+    TextChanged(TextChange),
     Done,
 }
 
@@ -322,6 +464,10 @@ pub enum InputMethodKeyboardEventVariant {
     Repeat(KeyEvent),
     Release(KeyEvent),
     Modifiers(Modifiers, RawModifiers),
+    Compose(ComposeStatus),
+    /// The names of the layouts of a new keymap.
+    Layouts(Vec<String>),
+    LayoutChanged { index: u32, name: String },
 }
 
 #[derive(Debug, Clone)]
@@ -506,6 +652,64 @@ impl SctkEvent {
                 .into_iter()
                 .collect(), // TODO Ashley: conversion
             },
+            SctkEvent::TouchEvent { variant, .. } => {
+                let id = touch::Finger(variant.id() as u64);
+                let position = variant.position();
+                let position = Point::new(position.x as f32, position.y as f32);
+                let event = match variant {
+                    TouchEventVariant::Down { .. } => {
+                        touch::Event::FingerPressed { id, position }
+                    }
+                    TouchEventVariant::Up { .. } => {
+                        touch::Event::FingerLifted { id, position }
+                    }
+                    TouchEventVariant::Motion { .. } => {
+                        touch::Event::FingerMoved { id, position }
+                    }
+                    TouchEventVariant::Cancel { .. } => {
+                        touch::Event::FingerLost { id, position }
+                    }
+                };
+                vec![iced_runtime::core::Event::Touch(event)]
+            }
+            SctkEvent::TabletEvent {
+                variant, surface, ..
+            } => {
+                let event = match variant {
+                    TabletEventVariant::ProximityIn { tool, axes } => {
+                        wayland::TabletEvent::ProximityIn { tool, axes }
+                    }
+                    TabletEventVariant::ProximityOut { tool } => {
+                        wayland::TabletEvent::ProximityOut { tool }
+                    }
+                    TabletEventVariant::Down { tool, axes } => {
+                        wayland::TabletEvent::Down { tool, axes }
+                    }
+                    TabletEventVariant::Up { tool, axes } => {
+                        wayland::TabletEvent::Up { tool, axes }
+                    }
+                    TabletEventVariant::Motion { tool, axes } => {
+                        wayland::TabletEvent::Motion { tool, axes }
+                    }
+                    TabletEventVariant::Button {
+                        tool,
+                        button,
+                        pressed,
+                    } => wayland::TabletEvent::Button {
+                        tool,
+                        button,
+                        pressed,
+                    },
+                    TabletEventVariant::PadButton { button, pressed } => {
+                        wayland::TabletEvent::PadButton { button, pressed }
+                    }
+                };
+                vec![iced_runtime::core::Event::PlatformSpecific(
+                    PlatformSpecific::Wayland(wayland::Event::Tablet(
+                        event, surface,
+                    )),
+                )]
+            }
             // TODO: Not really handled here anyway
             SctkEvent::InputMethodEvent {
                 variant,
@@ -536,6 +740,27 @@ impl SctkEvent {
                 InputMethodEventVariant::ContentType(_, _) => {
                     Default::default()
                 }
+                InputMethodEventVariant::Privacy(privacy) => {
+                    vec![iced_runtime::core::Event::PlatformSpecific(
+                        PlatformSpecific::Wayland(wayland::Event::InputMethod(
+                            wayland::InputMethodEvent::Privacy(privacy.into()),
+                        )),
+                    )]
+                }
+                InputMethodEventVariant::Profile(profile) => {
+                    vec![iced_runtime::core::Event::PlatformSpecific(
+                        PlatformSpecific::Wayland(wayland::Event::InputMethod(
+                            wayland::InputMethodEvent::Profile(profile.into()),
+                        )),
+                    )]
+                }
+                InputMethodEventVariant::TextChanged(change) => {
+                    vec![iced_runtime::core::Event::PlatformSpecific(
+                        PlatformSpecific::Wayland(wayland::Event::InputMethod(
+                            wayland::InputMethodEvent::TextChanged(change.into()),
+                        )),
+                    )]
+                }
                 InputMethodEventVariant::Done => Default::default(),
             },
             SctkEvent::InputMethodKeyboardEvent { variant } => match variant {
@@ -635,6 +860,43 @@ impl SctkEvent {
                         )),
                     )]
                 }
+                InputMethodKeyboardEventVariant::Compose(status) => {
+                    vec![iced_runtime::core::Event::PlatformSpecific(
+                        PlatformSpecific::Wayland(
+                            wayland::Event::InputMethodKeyboard(
+                                wayland::InputMethodKeyboardEvent::Compose(
+                                    status.into(),
+                                ),
+                            ),
+                        ),
+                    )]
+                }
+                InputMethodKeyboardEventVariant::Layouts(layouts) => {
+                    vec![iced_runtime::core::Event::PlatformSpecific(
+                        PlatformSpecific::Wayland(
+                            wayland::Event::InputMethodKeyboard(
+                                wayland::InputMethodKeyboardEvent::Layouts(
+                                    layouts,
+                                ),
+                            ),
+                        ),
+                    )]
+                }
+                InputMethodKeyboardEventVariant::LayoutChanged {
+                    index,
+                    name,
+                } => {
+                    vec![iced_runtime::core::Event::PlatformSpecific(
+                        PlatformSpecific::Wayland(
+                            wayland::Event::InputMethodKeyboard(
+                                wayland::InputMethodKeyboardEvent::LayoutChanged {
+                                    index,
+                                    name,
+                                },
+                            ),
+                        ),
+                    )]
+                }
             },
             SctkEvent::KeyboardEvent {
                 variant,
@@ -1037,7 +1299,7 @@ impl SctkEvent {
                     .collect()
                 }
             },
-            SctkEvent::SelectionOffer(so) => match so {
+            SctkEvent::SelectionOffer(so, kind) => match so {
                 SelectionOfferEvent::Offer(mime_types) => {
                     Some(iced_runtime::core::Event::PlatformSpecific(
                         PlatformSpecific::Wayland(
@@ -1048,6 +1310,7 @@ impl SctkEvent {
                                         .map(|m| m.to_string())
                                         .collect(),
                                 ),
+                                kind.into(),
                             ),
                         ),
                     ))
@@ -1062,6 +1325,22 @@ impl SctkEvent {
                                     data,
                                     mime_type,
                                 },
+                                kind.into(),
+                            ),
+                        ),
+                    ))
+                    .into_iter()
+                    .collect()
+                }
+            },
+            SctkEvent::VirtualKeyboard(event) => match event {
+                VirtualKeyboardEvent::Modifiers(raw_modifiers) => {
+                    Some(iced_runtime::core::Event::PlatformSpecific(
+                        PlatformSpecific::Wayland(
+                            wayland::Event::VirtualKeyboard(
+                                wayland::VirtualKeyboardEvent::Modifiers(
+                                    raw_modifiers.into(),
+                                ),
                             ),
                         ),
                     ))
@@ -1069,6 +1348,41 @@ impl SctkEvent {
                     .collect()
                 }
             },
+            SctkEvent::DataControl(event) => match event {
+                DataControlEvent::Selection { mime_type, data } => {
+                    Some(iced_runtime::core::Event::PlatformSpecific(
+                        PlatformSpecific::Wayland(wayland::Event::DataControl(
+                            wayland::DataControlEvent::Selection {
+                                mime_type,
+                                data,
+                            },
+                        )),
+                    ))
+                    .into_iter()
+                    .collect()
+                }
+            },
+            SctkEvent::ForeignToplevel(event) => {
+                let event = match event {
+                    ForeignToplevelEvent::Opened(toplevel) => {
+                        wayland::ForeignToplevelEvent::Opened(toplevel)
+                    }
+                    ForeignToplevelEvent::Changed(toplevel) => {
+                        wayland::ForeignToplevelEvent::Changed(toplevel)
+                    }
+                    ForeignToplevelEvent::Closed(toplevel) => {
+                        wayland::ForeignToplevelEvent::Closed(toplevel)
+                    }
+                    ForeignToplevelEvent::List(toplevels) => {
+                        wayland::ForeignToplevelEvent::List(toplevels)
+                    }
+                };
+                vec![iced_runtime::core::Event::PlatformSpecific(
+                    PlatformSpecific::Wayland(wayland::Event::ForeignToplevel(
+                        event,
+                    )),
+                )]
+            }
             SctkEvent::DataSource(event) => match event {
                 DataSourceEvent::DndDropPerformed => {
                     Some(iced_runtime::core::Event::PlatformSpecific(
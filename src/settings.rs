@@ -1,8 +1,12 @@
+use std::{path::PathBuf, time::Duration};
+
 use iced_runtime::command::platform_specific::wayland::{
     layer_surface::SctkLayerSurfaceSettings, window::SctkWindowSettings, 
     input_method_popup::InputMethodPopupSettings,
 };
 
+use crate::engine::PrivacyWatch;
+
 #[derive(Debug)]
 pub struct Settings<Flags> {
     /// The data needed to initialize an [`Application`].
@@ -17,6 +21,56 @@ pub struct Settings<Flags> {
     pub surface: InitialSurface,
     /// whether the application should exit on close of all windows
     pub exit_on_close_request: bool,
+    /// compose table used by the input method keyboard
+    pub compose: ComposeSettings,
+    /// how sensitive text inputs are treated
+    pub privacy: PrivacySettings,
+    /// behavior of the virtual keyboard modifiers
+    pub virtual_keyboard: VirtualKeyboardSettings,
+}
+
+/// Policy for text inputs holding passwords and other sensitive content.
+///
+/// Learning, history, clipboard and prediction are always off for such
+/// inputs, this only configures what goes beyond that.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrivacySettings {
+    /// Whether keys pass through without composing, in the compose table of
+    /// the input method keyboard and in the engines.
+    pub bypass_composition: bool,
+    /// Updated with the privacy of the active text input. Engines follow the
+    /// default watch on their own, see [`PrivacyWatch`].
+    pub watch: PrivacyWatch,
+}
+
+/// How the virtual keyboard treats tapped modifiers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VirtualKeyboardSettings {
+    /// A second tap of a latched modifier within this interval locks it, a
+    /// later one releases it.
+    pub double_tap_interval: Duration,
+}
+
+impl Default for VirtualKeyboardSettings {
+    fn default() -> Self {
+        Self {
+            double_tap_interval: Duration::from_millis(400),
+        }
+    }
+}
+
+/// Where the input method keyboard loads its compose table from.
+///
+/// By default the table of the locale from `LC_ALL`, `LC_CTYPE` or `LANG` is
+/// used, which libxkbcommon also looks up in `$XCOMPOSEFILE` and
+/// `~/.XCompose`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ComposeSettings {
+    /// Locale used instead of the one from the environment.
+    pub locale: Option<String>,
+    /// Compose file used instead of the locale's table. It may pull in the
+    /// system table with `include "%L"`.
+    pub file: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone)]
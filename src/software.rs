@@ -0,0 +1,344 @@
+//! A software renderer presenting through `wl_shm`.
+//!
+//! [`Compositor`] draws with `tiny-skia` on the CPU, so popups and the
+//! on-screen keyboard work on machines without GPU acceleration, e.g.
+//! kiosks and virtual machines. Frames are handed to the event loop, which
+//! copies them into buffers of its shm pool and attaches them to their
+//! surfaces after each redraw. [`run`](crate::run) connects the compositor
+//! to the queue of its event loop, each event loop has its own. Event loops
+//! run by hand connect it with [`SctkEventLoop::connect_software`].
+//!
+//! ```ignore
+//! iced_wayland_input_method::run::<App, Executor, software::Compositor<Theme>>(
+//!     settings,
+//!     software::Settings::default(),
+//! )
+//! ```
+//!
+//! [`Compositor::screenshot`] renders without presenting, for tests
+//! comparing pixel output.
+//!
+//! [`SctkEventLoop::connect_software`]: crate::event_loop::SctkEventLoop::connect_software
+use std::{
+    any::Any,
+    fmt::Debug,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use iced_graphics::{
+    compositor::{self, Information, SurfaceError},
+    Primitive, Viewport,
+};
+use iced_runtime::core::{Color, Rectangle, Size};
+use iced_tiny_skia::{Backend, Renderer};
+use raw_window_handle::{
+    HasRawDisplayHandle, HasRawWindowHandle, RawWindowHandle,
+};
+use sctk::reexports::client::{
+    backend::ObjectId,
+    protocol::{wl_shm, wl_surface::WlSurface},
+    Connection, Proxy,
+};
+use sctk::shm::multi::MultiPool;
+
+use crate::event_loop::state::SctkState;
+
+/// Buffers kept per surface, one can be drawn while the compositor holds
+/// the other.
+const BUFFERS: usize = 2;
+
+/// Settings of the software [`Compositor`].
+#[derive(Debug, Clone, Default)]
+pub struct Settings {
+    /// Settings of the `tiny-skia` backend.
+    pub renderer: iced_tiny_skia::Settings,
+    frames: Frames,
+}
+
+impl Settings {
+    /// Settings drawing with `renderer`.
+    pub fn new(renderer: iced_tiny_skia::Settings) -> Self {
+        Self {
+            renderer,
+            frames: Frames::default(),
+        }
+    }
+}
+
+/// Make `settings` present into `frames` if they are the settings of a
+/// software [`Compositor`].
+pub(crate) fn connect<S: Any>(mut settings: S, frames: &Frames) -> S {
+    if let Some(settings) =
+        (&mut settings as &mut dyn Any).downcast_mut::<Settings>()
+    {
+        settings.frames = frames.clone();
+    }
+    settings
+}
+
+/// Frames presented since the event loop last attached them.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Frames(Arc<Mutex<Vec<Frame>>>);
+
+impl Frames {
+    /// Queue `frame`, replacing an older one for the same surface.
+    fn queue(&self, frame: Frame) {
+        let mut frames = self.0.lock().unwrap();
+        frames.retain(|f| f.surface != frame.surface);
+        frames.push(frame);
+    }
+
+    fn take(&self) -> Vec<Frame> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+/// A rendered frame waiting to be attached to its surface.
+#[derive(Debug)]
+struct Frame {
+    surface: ObjectId,
+    width: u32,
+    height: u32,
+    /// Premultiplied `Argb8888` pixels.
+    pixels: Vec<u8>,
+}
+
+/// A surface drawn by a [`Compositor`].
+#[derive(Debug)]
+pub struct Surface {
+    /// The `wl_surface`, `None` when the window is not a wayland surface.
+    id: Option<ObjectId>,
+    pixmap: tiny_skia::Pixmap,
+    clip_mask: tiny_skia::Mask,
+}
+
+impl Surface {
+    fn resize(&mut self, width: u32, height: u32) {
+        let (width, height) = (width.max(1), height.max(1));
+        if self.pixmap.width() != width || self.pixmap.height() != height {
+            self.pixmap = tiny_skia::Pixmap::new(width, height).unwrap();
+            self.clip_mask = tiny_skia::Mask::new(width, height).unwrap();
+        }
+    }
+}
+
+/// A compositor rendering on the CPU and presenting through `wl_shm`.
+#[derive(Debug)]
+pub struct Compositor<Theme> {
+    frames: Frames,
+    _theme: PhantomData<Theme>,
+}
+
+impl<Theme> iced_graphics::Compositor for Compositor<Theme> {
+    type Settings = Settings;
+    type Renderer = Renderer<Theme>;
+    type Surface = Surface;
+
+    fn new<W: HasRawWindowHandle + HasRawDisplayHandle>(
+        settings: Self::Settings,
+        _compatible_window: Option<&W>,
+    ) -> Result<(Self, Self::Renderer), compositor::Error> {
+        Ok((
+            Self {
+                frames: settings.frames,
+                _theme: PhantomData,
+            },
+            Renderer::new(Backend::new(settings.renderer)),
+        ))
+    }
+
+    fn create_surface<W: HasRawWindowHandle + HasRawDisplayHandle>(
+        &mut self,
+        window: &W,
+        width: u32,
+        height: u32,
+    ) -> Self::Surface {
+        let id = match window.raw_window_handle() {
+            // SAFETY: the handle points to a live `wl_surface` of the
+            // connection the event loop runs on.
+            RawWindowHandle::Wayland(handle) => unsafe {
+                ObjectId::from_ptr(
+                    WlSurface::interface(),
+                    handle.surface.cast(),
+                )
+            }
+            .ok(),
+            _ => None,
+        };
+        let (width, height) = (width.max(1), height.max(1));
+        Surface {
+            id,
+            pixmap: tiny_skia::Pixmap::new(width, height).unwrap(),
+            clip_mask: tiny_skia::Mask::new(width, height).unwrap(),
+        }
+    }
+
+    fn configure_surface(
+        &mut self,
+        surface: &mut Self::Surface,
+        width: u32,
+        height: u32,
+    ) {
+        surface.resize(width, height);
+    }
+
+    fn fetch_information(&self) -> Information {
+        Information {
+            adapter: String::from("CPU"),
+            backend: String::from("tiny-skia (wl_shm)"),
+        }
+    }
+
+    fn present<T: AsRef<str>>(
+        &mut self,
+        renderer: &mut Self::Renderer,
+        surface: &mut Self::Surface,
+        viewport: &Viewport,
+        background_color: Color,
+        overlay: &[T],
+    ) -> Result<(), SurfaceError> {
+        renderer.with_primitives(|backend, primitives| {
+            draw(
+                backend,
+                surface,
+                primitives,
+                viewport,
+                background_color,
+                overlay,
+            )
+        });
+        let Some(id) = surface.id.clone() else {
+            return Ok(());
+        };
+        // iced_tiny_skia draws BGRA, as `Argb8888` is laid out in memory.
+        self.frames.queue(Frame {
+            surface: id,
+            width: surface.pixmap.width(),
+            height: surface.pixmap.height(),
+            pixels: surface.pixmap.data().to_vec(),
+        });
+        Ok(())
+    }
+
+    fn screenshot<T: AsRef<str>>(
+        &mut self,
+        renderer: &mut Self::Renderer,
+        surface: &mut Self::Surface,
+        viewport: &Viewport,
+        background_color: Color,
+        overlay: &[T],
+    ) -> Vec<u8> {
+        let mut offscreen = Surface {
+            id: None,
+            pixmap: surface.pixmap.clone(),
+            clip_mask: surface.clip_mask.clone(),
+        };
+        renderer.with_primitives(|backend, primitives| {
+            draw(
+                backend,
+                &mut offscreen,
+                primitives,
+                viewport,
+                background_color,
+                overlay,
+            )
+        });
+        offscreen
+            .pixmap
+            .pixels()
+            .iter()
+            .flat_map(|pixel| {
+                // Red and blue are swapped, see `present`.
+                let color = pixel.demultiply();
+                [color.blue(), color.green(), color.red(), color.alpha()]
+            })
+            .collect()
+    }
+}
+
+/// Redraw all of `surface`.
+fn draw<T: AsRef<str>>(
+    backend: &mut Backend,
+    surface: &mut Surface,
+    primitives: &[Primitive],
+    viewport: &Viewport,
+    background_color: Color,
+    overlay: &[T],
+) {
+    let size = viewport.physical_size();
+    surface.resize(size.width, size.height);
+    let damage = [Rectangle::with_size(Size::new(
+        surface.pixmap.width() as f32,
+        surface.pixmap.height() as f32,
+    ))];
+    backend.draw(
+        &mut surface.pixmap.as_mut(),
+        &mut surface.clip_mask,
+        primitives,
+        viewport,
+        &damage,
+        background_color,
+        overlay,
+    );
+}
+
+impl<T: 'static + Debug> SctkState<T> {
+    /// Attach the frames presented by the software [`Compositor`] to their
+    /// surfaces. A frame whose buffers are all held by the compositor waits
+    /// for a frame callback, the compositor sends it once it is done with
+    /// the frame on screen and the loop tries again.
+    pub(crate) fn present_software_frames(&mut self, connection: &Connection) {
+        let frames = self.software_frames.take();
+        if frames.is_empty() {
+            return;
+        }
+        if self.multipool.is_none() {
+            match MultiPool::new(&self.shm_state) {
+                Ok(pool) => self.multipool = Some(pool),
+                Err(err) => {
+                    tracing::error!("Failed to create shm pool: {}", err);
+                    return;
+                }
+            }
+        }
+        let pool = self.multipool.as_mut().unwrap();
+        for frame in frames {
+            let Ok(surface) =
+                WlSurface::from_id(connection, frame.surface.clone())
+            else {
+                // The surface is gone, so are its buffers.
+                for i in 0..BUFFERS {
+                    let _ = pool.remove(&(frame.surface.clone(), i));
+                }
+                continue;
+            };
+            let (width, height) = (frame.width as i32, frame.height as i32);
+            let mut attached = false;
+            for i in 0..BUFFERS {
+                let Ok((_, buffer, canvas)) = pool.create_buffer(
+                    width,
+                    width * 4,
+                    height,
+                    &(frame.surface.clone(), i),
+                    wl_shm::Format::Argb8888,
+                ) else {
+                    continue;
+                };
+                canvas[..frame.pixels.len()].copy_from_slice(&frame.pixels);
+                surface.attach(Some(buffer), 0, 0);
+                surface.damage_buffer(0, 0, width, height);
+                surface.commit();
+                attached = true;
+                break;
+            }
+            if !attached {
+                // Nothing else may wake the loop until the buffers are
+                // released.
+                surface.frame(&self.queue_handle, surface.clone());
+                surface.commit();
+                self.software_frames.queue(frame);
+            }
+        }
+    }
+}
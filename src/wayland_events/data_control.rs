@@ -0,0 +1,12 @@
+/// Data control events, reported without keyboard focus.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataControlEvent {
+    /// The text of a new selection. Selections marked by a password manager
+    /// are never reported.
+    Selection {
+        /// The mime type the selection was read as.
+        mime_type: String,
+        /// The data
+        data: Vec<u8>,
+    },
+}
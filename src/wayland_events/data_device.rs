@@ -67,6 +67,16 @@ pub enum SelectionOfferEvent {
     },
 }
 
+/// The selection a [`SelectionOfferEvent`] belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelectionKind {
+    /// The regular clipboard.
+    #[default]
+    Clipboard,
+    /// The primary selection, usually pasted with a middle click.
+    Primary,
+}
+
 /// A ReadPipe and the mime type of the data.
 #[derive(Debug, Clone)]
 pub struct ReadData {
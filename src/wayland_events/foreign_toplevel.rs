@@ -0,0 +1,36 @@
+/// Foreign toplevel events, about the toplevels of all clients.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForeignToplevelEvent {
+    /// A toplevel was opened, sent once its initial state is known.
+    Opened(ForeignToplevel),
+    /// The title, app id or state of a toplevel changed.
+    Changed(ForeignToplevel),
+    /// A toplevel was closed.
+    Closed(ForeignToplevel),
+    /// The open toplevels in the order they were opened, in reply to a
+    /// request for them.
+    List(Vec<ForeignToplevel>),
+}
+
+/// A toplevel of any client, as reported by the compositor.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForeignToplevel {
+    /// Identifies the toplevel while it is open.
+    pub id: u32,
+    /// The title
+    pub title: String,
+    /// The app id, usually the name of the desktop file
+    pub app_id: String,
+    /// An identifier unique across the session, when the compositor
+    /// reports one
+    pub identifier: Option<String>,
+    /// Whether the toplevel is focused. Only known to compositors
+    /// supporting `zwlr_foreign_toplevel_manager_v1`.
+    pub activated: bool,
+    /// Whether the toplevel is maximized
+    pub maximized: bool,
+    /// Whether the toplevel is minimized
+    pub minimized: bool,
+    /// Whether the toplevel is fullscreen
+    pub fullscreen: bool,
+}
@@ -27,10 +27,77 @@ pub enum InputMethodEvent {
     TextChangeCause(WEnum<ChangeCause>),
     /// content purpose and hint
     ContentType(WEnum<ContentHint>, WEnum<ContentPurpose>),
+    /// The privacy of the text input changed, sent before `Done`
+    Privacy(Privacy),
+    /// The profile of the text input changed, sent before `Done`
+    Profile(Profile),
+    /// The surrounding text changed, sent before `Done`
+    TextChanged(TextChange),
     /// apply state
     Done,
 }
 
+/// Privacy of the active text input, derived from its content type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Privacy {
+    /// The input holds sensitive content. Nothing typed into it may be
+    /// learned, stored in history or the clipboard, or used for prediction.
+    pub private: bool,
+    /// Keys should be passed through without composition.
+    pub bypass_composition: bool,
+}
+
+
+/// Where a change of the surrounding text came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextChange {
+    /// The change echoes text committed by the input method.
+    Echo,
+    /// The text or cursor was changed by the user or the client, any
+    /// composition in progress is stale. The event loop already cleared the
+    /// preedit.
+    External,
+}
+
+/// How input is offered for a text input, derived from its content purpose
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Profile {
+    /// Regular text
+    #[default]
+    Text,
+    /// Digits, numbers, dates and times
+    Numeric,
+    /// Phone numbers
+    Phone,
+    /// Email addresses
+    Email,
+    /// URLs
+    Url,
+    /// Terminal input, typed as is
+    Terminal,
+}
+
+impl Profile {
+    /// Whether a numeric layout should be shown.
+    pub fn is_numeric(self) -> bool {
+        matches!(self, Profile::Numeric | Profile::Phone)
+    }
+
+    /// Whether text may be corrected or predicted.
+    pub fn auto_correct(self) -> bool {
+        matches!(self, Profile::Text)
+    }
+
+    /// Text the layout should offer as single keys.
+    pub fn shortcuts(self) -> &'static [&'static str] {
+        match self {
+            Profile::Email => &["@", ".", ".com"],
+            Profile::Url => &["/", ".", ".com"],
+            Profile::Phone => &["+", "*", "#"],
+            Profile::Text | Profile::Numeric | Profile::Terminal => &[],
+        }
+    }
+}
 
 // /// Input method keyboard events
 // #[derive(Debug, Clone, PartialEq, Eq)]
@@ -50,6 +117,82 @@ pub enum InputMethodKeyboardEvent {
     Repeat(KeyEvent),
     /// Modifiers are updated
     Modifiers(Modifiers, RawModifiers),
+    /// A compose sequence progressed, sent before the key press
+    Compose(ComposeStatus),
+    /// A new keymap was received, with the names of its layouts indexed by
+    /// XKB group
+    Layouts(Vec<String>),
+    /// The effective layout changed, sent after `Modifiers`
+    LayoutChanged {
+        /// The XKB group of the layout
+        index: u32,
+        /// The name of the layout in the keymap
+        name: String,
+    },
+}
+
+/// Progress of a compose sequence, e.g. a dead key or `Multi_key` sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComposeStatus {
+    /// The sequence is in progress, with the keysyms typed so far.
+    Composing(Vec<Keysym>),
+    /// The sequence is complete, the composed text is the `utf8` of the
+    /// following key press.
+    Composed(Vec<Keysym>),
+    /// The sequence does not match any entry and was dropped.
+    Cancelled(Vec<Keysym>),
+}
+
+impl ComposeStatus {
+    /// The keysyms of the sequence.
+    pub fn keysyms(&self) -> &[Keysym] {
+        match self {
+            ComposeStatus::Composing(keysyms)
+            | ComposeStatus::Composed(keysyms)
+            | ComposeStatus::Cancelled(keysyms) => keysyms,
+        }
+    }
+
+    /// Text to show as preedit while the sequence is in progress.
+    ///
+    /// Dead keys are shown as their spacing accent, `Multi_key` as `·`.
+    /// Finished and cancelled sequences have no preedit.
+    pub fn preedit(&self) -> String {
+        match self {
+            ComposeStatus::Composing(keysyms) => {
+                keysyms.iter().filter_map(|k| preedit_char(*k)).collect()
+            }
+            ComposeStatus::Composed(_) | ComposeStatus::Cancelled(_) => {
+                String::new()
+            }
+        }
+    }
+}
+
+fn preedit_char(keysym: Keysym) -> Option<char> {
+    let c = match keysym {
+        Keysym::Multi_key => '·',
+        Keysym::dead_grave => '`',
+        Keysym::dead_acute => '´',
+        Keysym::dead_circumflex => '^',
+        Keysym::dead_tilde => '~',
+        Keysym::dead_macron => '¯',
+        Keysym::dead_breve => '˘',
+        Keysym::dead_abovedot => '˙',
+        Keysym::dead_diaeresis => '¨',
+        Keysym::dead_abovering => '˚',
+        Keysym::dead_doubleacute => '˝',
+        Keysym::dead_caron => 'ˇ',
+        Keysym::dead_cedilla => '¸',
+        Keysym::dead_ogonek => '˛',
+        Keysym::dead_iota => 'ͺ',
+        Keysym::dead_belowdot => '.',
+        Keysym::dead_stroke => '/',
+        Keysym::dead_currency => '¤',
+        Keysym::dead_greek => 'µ',
+        _ => return keysym.key_char(),
+    };
+    Some(c)
 }
 
 /// Data associated with a key press or release event.
@@ -1,9 +1,13 @@
+mod data_control;
 mod data_device;
+mod foreign_toplevel;
 mod input_method;
 mod layer;
 mod output;
 mod popup;
 mod seat;
+mod tablet;
+mod virtual_keyboard;
 mod window;
 
 use crate::{time::Instant, window::Id};
@@ -11,12 +15,16 @@ use sctk::reexports::client::protocol::{
     wl_output::WlOutput, wl_seat::WlSeat, wl_surface::WlSurface,
 };
 
+pub use data_control::*;
 pub use data_device::*;
+pub use foreign_toplevel::*;
 pub use input_method::*;
 pub use layer::*;
 pub use output::*;
 pub use popup::*;
 pub use seat::*;
+pub use tablet::*;
+pub use virtual_keyboard::*;
 pub use window::*;
 
 /// wayland events
@@ -36,14 +44,22 @@ pub enum Event {
     DataSource(DataSourceEvent),
     /// Dnd Offer events
     DndOffer(DndOfferEvent),
-    /// Selection Offer events
-    SelectionOffer(SelectionOfferEvent),
+    /// Selection Offer events, for the clipboard or the primary selection
+    SelectionOffer(SelectionOfferEvent, SelectionKind),
+    /// Data control events
+    DataControl(DataControlEvent),
+    /// Tablet events
+    Tablet(TabletEvent, WlSurface),
+    /// Foreign toplevel events
+    ForeignToplevel(ForeignToplevelEvent),
     /// Frame events
     Frame(Instant, WlSurface, Id),
     /// Input Method
     InputMethod(InputMethodEvent),
     /// Input Method Keyboard Event
     InputMethodKeyboard(InputMethodKeyboardEvent),
+    /// Virtual keyboard events
+    VirtualKeyboard(VirtualKeyboardEvent),
     // /// Input Method Popup Event
     // InputMethodPopup(InputMethodPopupEvent)
 }
@@ -0,0 +1,112 @@
+/// Tablet events, for the tool or pad focused on a surface.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TabletEvent {
+    /// A tool came into proximity of the surface.
+    ProximityIn {
+        /// The tool
+        tool: TabletTool,
+        /// The state of the tool
+        axes: TabletAxes,
+    },
+    /// A tool left the proximity of the surface.
+    ProximityOut {
+        /// The tool
+        tool: TabletTool,
+    },
+    /// A tool touched the tablet.
+    Down {
+        /// The tool
+        tool: TabletTool,
+        /// The state of the tool
+        axes: TabletAxes,
+    },
+    /// A tool stopped touching the tablet.
+    Up {
+        /// The tool
+        tool: TabletTool,
+        /// The state of the tool
+        axes: TabletAxes,
+    },
+    /// A tool moved or one of its axes changed.
+    Motion {
+        /// The tool
+        tool: TabletTool,
+        /// The state of the tool
+        axes: TabletAxes,
+    },
+    /// A button of a tool was pressed or released.
+    Button {
+        /// The tool
+        tool: TabletTool,
+        /// The evdev button code
+        button: u32,
+        /// Whether the button is now pressed
+        pressed: bool,
+    },
+    /// A button of a pad was pressed or released.
+    PadButton {
+        /// The index of the button on the pad
+        button: u32,
+        /// Whether the button is now pressed
+        pressed: bool,
+    },
+}
+
+/// The type of a tablet tool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TabletToolKind {
+    /// A pen
+    Pen,
+    /// The eraser end of a pen
+    Eraser,
+    /// A paintbrush
+    Brush,
+    /// A pencil
+    Pencil,
+    /// An airbrush
+    Airbrush,
+    /// A finger
+    Finger,
+    /// A mouse bound to the tablet
+    Mouse,
+    /// A mouse tool with a lens
+    Lens,
+}
+
+/// A physical tool used on a tablet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TabletTool {
+    /// Identifies the tool while it is known to the compositor.
+    pub id: u32,
+    /// The type of the tool.
+    pub kind: TabletToolKind,
+    /// The serial number of the tool, if the hardware reports one.
+    pub hardware_serial: Option<u64>,
+    /// The Wacom tool id, if the hardware reports one.
+    pub hardware_id: Option<u64>,
+}
+
+/// The state of a tablet tool. Axes the tool does not have stay at zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TabletAxes {
+    /// x coordinate, relative to the surface
+    pub x: f64,
+    /// y coordinate, relative to the surface
+    pub y: f64,
+    /// The pressure, from 0 to 1
+    pub pressure: f32,
+    /// The distance from the tablet, from 0 to 1
+    pub distance: f32,
+    /// The tilt towards the positive x axis, in degrees
+    pub tilt_x: f32,
+    /// The tilt towards the positive y axis, in degrees
+    pub tilt_y: f32,
+    /// The clockwise rotation, in degrees
+    pub rotation: f32,
+    /// The position of the slider, from -1 to 1
+    pub slider: f32,
+    /// The rotation of the wheel since the last event, in degrees
+    pub wheel: f32,
+    /// Whether the tool touches the tablet
+    pub down: bool,
+}
@@ -0,0 +1,9 @@
+use super::RawModifiers;
+
+/// Virtual keyboard events
+#[derive(Debug, Clone, PartialEq)]
+pub enum VirtualKeyboardEvent {
+    /// The modifiers sent through the virtual keyboard changed, e.g. a latch
+    /// was used up by a key.
+    Modifiers(RawModifiers),
+}
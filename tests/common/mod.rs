@@ -0,0 +1,1478 @@
+//! An in-process mock compositor for integration tests.
+//!
+//! The compositor runs `wayland-server` on its own thread and offers the
+//! globals the event loop needs: `wl_compositor`, `wl_shm`, `xdg_wm_base`,
+//! `wl_data_device_manager`, `zwp_primary_selection_device_manager_v1`,
+//! `wl_seat`, layer shell, `zwp_input_method_manager_v2` and
+//! `zwp_virtual_keyboard_manager_v1`. Toplevels are reported through the
+//! protocol chosen with [`Harness::with_toplevels`]. The seat has no
+//! devices until a test adds a keyboard with [`Compositor::add_keyboard`].
+//! Attached shm buffers are released once replaced, unless
+//! [`Compositor::hold_buffers`] was called.
+//!
+//! Tests script text input and keyboard events through [`Compositor`], run
+//! the client event loop with [`Harness::run_until`] and assert on the
+//! [`Request`]s the crate sent. Nothing is rendered, it runs headless.
+#![allow(dead_code)]
+
+use std::{
+    fs::File,
+    io::{Read, Write},
+    os::{
+        fd::{AsFd, OwnedFd},
+        unix::{fs::FileExt, net::UnixStream},
+    },
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use iced_wayland_input_method::{
+    application::Event,
+    engine::PrivacyWatch,
+    event_loop::{control_flow::ControlFlow, SctkEventLoop},
+    sctk_event::{IcedSctkEvent, SctkEvent},
+    settings::{InitialSurface, PrivacySettings, Settings},
+};
+use rustix::{
+    fs::{memfd_create, MemfdFlags},
+    pipe::{pipe_with, PipeFlags},
+};
+use sctk::reexports::client::Connection;
+use wayland_protocols::{
+    ext::foreign_toplevel_list::v1::server::{
+        ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1,
+        ext_foreign_toplevel_list_v1::ExtForeignToplevelListV1,
+    },
+    wp::{
+        primary_selection::zv1::server::{
+            zwp_primary_selection_device_manager_v1::{
+                self, ZwpPrimarySelectionDeviceManagerV1,
+            },
+            zwp_primary_selection_device_v1::{
+                self, ZwpPrimarySelectionDeviceV1,
+            },
+            zwp_primary_selection_offer_v1::{
+                self, ZwpPrimarySelectionOfferV1,
+            },
+            zwp_primary_selection_source_v1::{
+                self, ZwpPrimarySelectionSourceV1,
+            },
+        },
+        text_input::zv3::server::zwp_text_input_v3::{
+            ChangeCause, ContentHint, ContentPurpose,
+        },
+    },
+    xdg::shell::server::{
+        xdg_popup::XdgPopup,
+        xdg_positioner::XdgPositioner,
+        xdg_surface::{self, XdgSurface},
+        xdg_toplevel::XdgToplevel,
+        xdg_wm_base::{self, XdgWmBase},
+    },
+};
+use wayland_protocols_misc::{
+    zwp_input_method_v2::server::{
+        zwp_input_method_keyboard_grab_v2::ZwpInputMethodKeyboardGrabV2,
+        zwp_input_method_manager_v2::{self, ZwpInputMethodManagerV2},
+        zwp_input_method_v2::{self, ZwpInputMethodV2},
+        zwp_input_popup_surface_v2::ZwpInputPopupSurfaceV2,
+    },
+    zwp_virtual_keyboard_v1::server::{
+        zwp_virtual_keyboard_manager_v1::{self, ZwpVirtualKeyboardManagerV1},
+        zwp_virtual_keyboard_v1::{self, ZwpVirtualKeyboardV1},
+    },
+};
+use wayland_protocols_wlr::{
+    foreign_toplevel::v1::server::{
+        zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1},
+        zwlr_foreign_toplevel_manager_v1::ZwlrForeignToplevelManagerV1,
+    },
+    layer_shell::v1::server::{
+        zwlr_layer_shell_v1::{self, ZwlrLayerShellV1},
+        zwlr_layer_surface_v1::{self, ZwlrLayerSurfaceV1},
+    },
+};
+use wayland_server::{
+    backend::{ClientData, ClientId, DisconnectReason},
+    protocol::{
+        wl_buffer::WlBuffer,
+        wl_callback::WlCallback,
+        wl_compositor::{self, WlCompositor},
+        wl_data_device::WlDataDevice,
+        wl_data_device_manager::{self, WlDataDeviceManager},
+        wl_data_source::WlDataSource,
+        wl_keyboard::{self, KeymapFormat, WlKeyboard},
+        wl_region::WlRegion,
+        wl_seat::{self, WlSeat},
+        wl_shm::{self, WlShm},
+        wl_shm_pool::{self, WlShmPool},
+        wl_surface::{self, WlSurface},
+    },
+    Client, DataInit, Dispatch, Display, DisplayHandle, GlobalDispatch, New,
+    Resource,
+};
+
+/// How long [`Harness::run_until`] waits before failing the test.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A self-contained keymap with two layouts, `English (US)` and `German`.
+/// Its keys are `a` (30), `c` (46), `space`, `Return`, `BackSpace`, `Escape`
+/// and `Shift_L` (42), by evdev code.
+pub const KEYMAP: &str = r#"xkb_keymap {
+    xkb_keycodes "mock" {
+        minimum = 8;
+        maximum = 255;
+        <ESC> = 9;
+        <BKSP> = 22;
+        <RTRN> = 36;
+        <AC01> = 38;
+        <LFSH> = 50;
+        <AB03> = 54;
+        <SPCE> = 65;
+    };
+    xkb_types "mock" {
+        type "ONE_LEVEL" {
+            modifiers = none;
+            level_name[Level1] = "Any";
+        };
+        type "ALPHABETIC" {
+            modifiers = Shift + Lock;
+            map[Shift] = Level2;
+            map[Lock] = Level2;
+            level_name[Level1] = "Base";
+            level_name[Level2] = "Caps";
+        };
+    };
+    xkb_compatibility "mock" {
+        interpret Shift_L {
+            action = SetMods(modifiers = Shift);
+        };
+    };
+    xkb_symbols "mock" {
+        name[Group1] = "English (US)";
+        name[Group2] = "German";
+        key <ESC> { [ Escape ] };
+        key <BKSP> { [ BackSpace ] };
+        key <RTRN> { [ Return ] };
+        key <SPCE> { [ space ] };
+        key <LFSH> { [ Shift_L ] };
+        key <AC01> {
+            type = "ALPHABETIC",
+            symbols[Group1] = [ a, A ],
+            symbols[Group2] = [ a, A ]
+        };
+        key <AB03> {
+            type = "ALPHABETIC",
+            symbols[Group1] = [ c, C ],
+            symbols[Group2] = [ c, C ]
+        };
+        modifier_map Shift { <LFSH> };
+    };
+};"#;
+
+/// A request the crate sent, in the order it was received.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+    GrabKeyboard,
+    CommitString(String),
+    SetPreeditString {
+        text: String,
+        cursor_begin: i32,
+        cursor_end: i32,
+    },
+    DeleteSurroundingText {
+        before_length: u32,
+        after_length: u32,
+    },
+    Commit(u32),
+    GetInputPopupSurface,
+    VirtualKeyboardKeymap(String),
+    VirtualKey {
+        key: u32,
+        pressed: bool,
+    },
+    VirtualModifiers {
+        depressed: u32,
+        latched: u32,
+        locked: u32,
+        group: u32,
+    },
+    LayerSurface {
+        namespace: String,
+    },
+    /// The primary selection was set to a source offering `mime_types`.
+    SetPrimarySelection {
+        mime_types: Vec<String>,
+    },
+    UnsetPrimarySelection,
+    /// The crate was sent the primary selection and `complete`ly read it or
+    /// closed the pipe early.
+    PrimarySelectionSent {
+        complete: bool,
+    },
+    /// An shm buffer was attached, `pixel` is its first one as laid out in
+    /// memory.
+    AttachBuffer {
+        width: i32,
+        height: i32,
+        format: wl_shm::Format,
+        pixel: [u8; 4],
+    },
+}
+
+/// The protocol toplevels are reported with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Toplevels {
+    None,
+    /// `zwlr_foreign_toplevel_manager_v1`
+    Wlr,
+    /// `ext_foreign_toplevel_list_v1`
+    Ext,
+}
+
+/// A toplevel reported by the [`Compositor`].
+#[derive(Debug, Clone)]
+pub enum Toplevel {
+    Wlr(ZwlrForeignToplevelHandleV1),
+    Ext(ExtForeignToplevelHandleV1),
+}
+
+impl Toplevel {
+    /// The id the crate reports the toplevel with.
+    pub fn id(&self) -> u32 {
+        match self {
+            Self::Wlr(handle) => handle.id().protocol_id(),
+            Self::Ext(handle) => handle.id().protocol_id(),
+        }
+    }
+}
+
+/// State shared between the compositor thread and the test.
+#[derive(Debug, Default)]
+struct Shared {
+    requests: Vec<Request>,
+    input_method: Option<ZwpInputMethodV2>,
+    keyboard_grab: Option<ZwpInputMethodKeyboardGrabV2>,
+    virtual_keyboard: Option<ZwpVirtualKeyboardV1>,
+    seat: Option<WlSeat>,
+    keyboard: Option<WlKeyboard>,
+    primary_selection_device: Option<ZwpPrimarySelectionDeviceV1>,
+    primary_selection_source: Option<ZwpPrimarySelectionSourceV1>,
+    display: Option<DisplayHandle>,
+    toplevel_manager: Option<ZwlrForeignToplevelManagerV1>,
+    toplevel_list: Option<ExtForeignToplevelListV1>,
+    /// Buffers replaced on a surface are held rather than released.
+    hold_buffers: bool,
+    held_buffers: Vec<WlBuffer>,
+    /// The number of `done` events sent.
+    done_count: u32,
+    key_serial: u32,
+}
+
+#[derive(Debug)]
+struct Server {
+    shared: Arc<Mutex<Shared>>,
+    layer_surfaces: Vec<(WlSurface, ZwlrLayerSurfaceV1, bool)>,
+    configure_serial: u32,
+    /// The buffer attached to each surface.
+    attached: Vec<(WlSurface, WlBuffer)>,
+}
+
+/// An shm buffer, in the file of its pool.
+#[derive(Debug)]
+struct ShmBuffer {
+    pool: Arc<File>,
+    offset: i32,
+    width: i32,
+    height: i32,
+    format: wl_shm::Format,
+}
+
+struct TestClient;
+
+impl ClientData for TestClient {
+    fn initialized(&self, _client_id: ClientId) {}
+
+    fn disconnected(&self, _client_id: ClientId, _reason: DisconnectReason) {}
+}
+
+/// The mock compositor, running until dropped.
+pub struct Compositor {
+    shared: Arc<Mutex<Shared>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Compositor {
+    /// Start a compositor serving `stream`, reporting `toplevels`.
+    pub fn start(stream: UnixStream, toplevels: Toplevels) -> Self {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let thread = {
+            let shared = shared.clone();
+            let stop = stop.clone();
+            thread::spawn(move || {
+                let mut display = Display::<Server>::new().unwrap();
+                let mut dh = display.handle();
+                create_globals(&dh, toplevels);
+                dh.insert_client(stream, Arc::new(TestClient)).unwrap();
+                shared.lock().unwrap().display = Some(dh.clone());
+                let mut server = Server {
+                    shared,
+                    layer_surfaces: Vec::new(),
+                    configure_serial: 0,
+                    attached: Vec::new(),
+                };
+                ready_tx.send(()).unwrap();
+                while !stop.load(Ordering::Relaxed) {
+                    display.dispatch_clients(&mut server).unwrap();
+                    display.flush_clients().unwrap();
+                    thread::sleep(Duration::from_millis(1));
+                }
+            })
+        };
+        ready_rx.recv().unwrap();
+        Self {
+            shared,
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    /// The requests received so far.
+    pub fn requests(&self) -> Vec<Request> {
+        self.shared.lock().unwrap().requests.clone()
+    }
+
+    /// Take the requests received so far.
+    pub fn take_requests(&self) -> Vec<Request> {
+        std::mem::take(&mut self.shared.lock().unwrap().requests)
+    }
+
+    /// Whether the input method and its keyboard grab were created.
+    pub fn is_ready(&self) -> bool {
+        let shared = self.shared.lock().unwrap();
+        shared.input_method.is_some()
+            && shared.keyboard_grab.is_some()
+            && shared.virtual_keyboard.is_some()
+    }
+
+    /// The number of `done` events sent, the serial of the next commit.
+    pub fn serial(&self) -> u32 {
+        self.shared.lock().unwrap().done_count
+    }
+
+    fn input_method(&self) -> ZwpInputMethodV2 {
+        self.shared
+            .lock()
+            .unwrap()
+            .input_method
+            .clone()
+            .expect("no input method")
+    }
+
+    fn keyboard_grab(&self) -> ZwpInputMethodKeyboardGrabV2 {
+        self.shared
+            .lock()
+            .unwrap()
+            .keyboard_grab
+            .clone()
+            .expect("keyboard not grabbed")
+    }
+
+    pub fn activate(&self) {
+        self.input_method().activate();
+    }
+
+    pub fn deactivate(&self) {
+        self.input_method().deactivate();
+    }
+
+    pub fn surrounding_text(&self, text: &str, cursor: u32, anchor: u32) {
+        self.input_method()
+            .surrounding_text(text.to_string(), cursor, anchor);
+    }
+
+    pub fn text_change_cause(&self, cause: ChangeCause) {
+        self.input_method().text_change_cause(cause);
+    }
+
+    pub fn content_type(&self, hint: ContentHint, purpose: ContentPurpose) {
+        self.input_method().content_type(hint, purpose);
+    }
+
+    pub fn done(&self) {
+        self.input_method().done();
+        self.shared.lock().unwrap().done_count += 1;
+    }
+
+    /// Press or release the key with the evdev `key` code.
+    pub fn key(&self, key: u32, pressed: bool) {
+        let grab = self.keyboard_grab();
+        let serial = self.next_key_serial();
+        let state = if pressed {
+            wl_keyboard::KeyState::Pressed
+        } else {
+            wl_keyboard::KeyState::Released
+        };
+        grab.key(serial, 0, key, state);
+    }
+
+    pub fn modifiers(
+        &self,
+        depressed: u32,
+        latched: u32,
+        locked: u32,
+        group: u32,
+    ) {
+        let grab = self.keyboard_grab();
+        let serial = self.next_key_serial();
+        grab.modifiers(serial, depressed, latched, locked, group);
+    }
+
+    /// Give the seat a keyboard, input then has serials the crate may set
+    /// selections with.
+    pub fn add_keyboard(&self) {
+        let seat = self.shared.lock().unwrap().seat.clone().expect("no seat");
+        seat.capabilities(wl_seat::Capability::Keyboard);
+    }
+
+    /// Whether the crate created the keyboard of [`Compositor::add_keyboard`].
+    pub fn has_keyboard(&self) -> bool {
+        self.shared.lock().unwrap().keyboard.is_some()
+    }
+
+    /// Press or release the key with the evdev `key` code on the keyboard of
+    /// the seat rather than the input method.
+    pub fn seat_key(&self, key: u32, pressed: bool) {
+        let keyboard = self
+            .shared
+            .lock()
+            .unwrap()
+            .keyboard
+            .clone()
+            .expect("no keyboard");
+        let serial = self.next_key_serial();
+        let state = if pressed {
+            wl_keyboard::KeyState::Pressed
+        } else {
+            wl_keyboard::KeyState::Released
+        };
+        keyboard.key(serial, 0, key, state);
+    }
+
+    /// Hold the buffers the crate replaces until
+    /// [`Compositor::release_buffers`].
+    pub fn hold_buffers(&self) {
+        self.shared.lock().unwrap().hold_buffers = true;
+    }
+
+    /// Release the held buffers and stop holding them.
+    pub fn release_buffers(&self) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.hold_buffers = false;
+        for buffer in shared.held_buffers.drain(..) {
+            buffer.release();
+        }
+    }
+
+    /// Whether the crate created its primary selection device.
+    pub fn has_primary_selection_device(&self) -> bool {
+        self.shared
+            .lock()
+            .unwrap()
+            .primary_selection_device
+            .is_some()
+    }
+
+    /// Offer `data` as the primary selection in each of `mime_types`.
+    pub fn offer_primary_selection(&self, mime_types: &[&str], data: &[u8]) {
+        let shared = self.shared.lock().unwrap();
+        let device = shared
+            .primary_selection_device
+            .clone()
+            .expect("no primary selection device");
+        let display = shared.display.clone().unwrap();
+        drop(shared);
+        let client = device.client().unwrap();
+        let offer = client
+            .create_resource::<ZwpPrimarySelectionOfferV1, _, Server>(
+                &display,
+                device.version(),
+                data.to_vec(),
+            )
+            .unwrap();
+        device.data_offer(&offer);
+        for mime_type in mime_types {
+            offer.offer(mime_type.to_string());
+        }
+        device.selection(Some(&offer));
+    }
+
+    /// Open a toplevel of `app_id`, reported once the crate bound the
+    /// toplevel protocol.
+    pub fn open_toplevel(&self, app_id: &str, title: &str) -> Toplevel {
+        let shared = self.shared.lock().unwrap();
+        let display = shared.display.clone().unwrap();
+        let toplevel = if let Some(manager) = shared.toplevel_manager.clone() {
+            let handle = manager
+                .client()
+                .unwrap()
+                .create_resource::<ZwlrForeignToplevelHandleV1, _, Server>(
+                    &display,
+                    manager.version(),
+                    (),
+                )
+                .unwrap();
+            manager.toplevel(&handle);
+            Toplevel::Wlr(handle)
+        } else {
+            let list = shared.toplevel_list.clone().expect("no toplevels");
+            let handle = list
+                .client()
+                .unwrap()
+                .create_resource::<ExtForeignToplevelHandleV1, _, Server>(
+                    &display,
+                    list.version(),
+                    (),
+                )
+                .unwrap();
+            list.toplevel(&handle);
+            handle
+                .identifier(format!("{app_id}-{}", handle.id().protocol_id()));
+            Toplevel::Ext(handle)
+        };
+        drop(shared);
+        match &toplevel {
+            Toplevel::Wlr(handle) => {
+                handle.app_id(app_id.to_string());
+                handle.title(title.to_string());
+                handle.state(Vec::new());
+                handle.done();
+            }
+            Toplevel::Ext(handle) => {
+                handle.app_id(app_id.to_string());
+                handle.title(title.to_string());
+                handle.done();
+            }
+        }
+        toplevel
+    }
+
+    /// Whether the crate bound the toplevel protocol.
+    pub fn tracks_toplevels(&self) -> bool {
+        let shared = self.shared.lock().unwrap();
+        shared.toplevel_manager.is_some() || shared.toplevel_list.is_some()
+    }
+
+    /// Retitle `toplevel`.
+    pub fn set_toplevel_title(&self, toplevel: &Toplevel, title: &str) {
+        match toplevel {
+            Toplevel::Wlr(handle) => {
+                handle.title(title.to_string());
+                handle.done();
+            }
+            Toplevel::Ext(handle) => {
+                handle.title(title.to_string());
+                handle.done();
+            }
+        }
+    }
+
+    /// Set the states of a `zwlr_foreign_toplevel_handle_v1`, sent as an
+    /// array of native endian values.
+    pub fn set_toplevel_state(
+        &self,
+        toplevel: &Toplevel,
+        states: &[zwlr_foreign_toplevel_handle_v1::State],
+    ) {
+        let Toplevel::Wlr(handle) = toplevel else {
+            panic!("only wlr toplevels have states");
+        };
+        handle.state(
+            states
+                .iter()
+                .flat_map(|state| (*state as u32).to_ne_bytes())
+                .collect(),
+        );
+        handle.done();
+    }
+
+    pub fn close_toplevel(&self, toplevel: &Toplevel) {
+        match toplevel {
+            Toplevel::Wlr(handle) => handle.closed(),
+            Toplevel::Ext(handle) => handle.closed(),
+        }
+    }
+
+    /// Ask the source of the primary selection for `mime_type`. The returned
+    /// thread finishes with the data once the crate closes the pipe.
+    pub fn receive_primary_selection(
+        &self,
+        mime_type: &str,
+    ) -> JoinHandle<Vec<u8>> {
+        let source = self
+            .shared
+            .lock()
+            .unwrap()
+            .primary_selection_source
+            .clone()
+            .expect("no primary selection");
+        let (read, write) = pipe_with(PipeFlags::CLOEXEC).unwrap();
+        source.send(mime_type.to_string(), write.as_fd());
+        drop(write);
+        thread::spawn(move || {
+            let mut data = Vec::new();
+            File::from(read).read_to_end(&mut data).unwrap();
+            data
+        })
+    }
+
+    fn next_key_serial(&self) -> u32 {
+        let mut shared = self.shared.lock().unwrap();
+        shared.key_serial += 1;
+        shared.key_serial
+    }
+}
+
+impl Drop for Compositor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// The event loop of the crate connected to a [`Compositor`].
+pub struct Harness {
+    pub event_loop: SctkEventLoop<()>,
+    pub compositor: Compositor,
+    /// The events the event loop reported so far.
+    pub events: Vec<SctkEvent>,
+    /// The privacy watch of the event loop, not shared with other tests.
+    pub privacy: PrivacyWatch,
+}
+
+impl Harness {
+    /// Connect to a new compositor and wait until the input method is set
+    /// up. The requests sent while setting up are dropped, the events are
+    /// kept since the keymap may arrive with them.
+    pub fn new() -> Self {
+        Self::with_settings(|_| {})
+    }
+
+    /// Like [`Harness::new`], with the settings adjusted by `f`.
+    pub fn with_settings(f: impl FnOnce(&mut Settings<()>)) -> Self {
+        Self::build(Toplevels::None, f)
+    }
+
+    /// Like [`Harness::new`], with a compositor reporting toplevels through
+    /// `toplevels`.
+    pub fn with_toplevels(toplevels: Toplevels) -> Self {
+        Self::build(toplevels, |_| {})
+    }
+
+    fn build(toplevels: Toplevels, f: impl FnOnce(&mut Settings<()>)) -> Self {
+        let (client, server) = UnixStream::pair().unwrap();
+        let compositor = Compositor::start(server, toplevels);
+        let connection = Connection::from_socket(client).unwrap();
+        let privacy = PrivacyWatch::new(Default::default());
+        let mut settings = Settings {
+            flags: (),
+            kbd_repeat: None,
+            ptr_theme: None,
+            surface: InitialSurface::None,
+            exit_on_close_request: false,
+            compose: Default::default(),
+            privacy: PrivacySettings {
+                watch: privacy.clone(),
+                ..Default::default()
+            },
+            virtual_keyboard: Default::default(),
+        };
+        f(&mut settings);
+        let event_loop = SctkEventLoop::with_connection(connection, &settings);
+        let mut harness = Self {
+            event_loop,
+            compositor,
+            events: Vec::new(),
+            privacy,
+        };
+        harness.run_until(|h| h.compositor.is_ready());
+        harness.compositor.take_requests();
+        harness
+    }
+
+    /// Queue an action, as a command returned by the application would.
+    pub fn send(&self, event: Event<()>) {
+        self.event_loop.user_events_sender.send(event).unwrap();
+    }
+
+    /// Run the event loop until `done` holds, collecting events.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `done` does not hold within five seconds.
+    pub fn run_until(&mut self, mut done: impl FnMut(&Harness) -> bool) {
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            let mut events = Vec::new();
+            // One iteration, waiting briefly for the compositor thread.
+            self.event_loop
+                .run_return(|event, _, control_flow| match event {
+                    IcedSctkEvent::NewEvents(_) => {
+                        *control_flow = ControlFlow::WaitUntil(
+                            Instant::now() + Duration::from_millis(5),
+                        );
+                    }
+                    IcedSctkEvent::SctkEvent(event) => events.push(event),
+                    IcedSctkEvent::RedrawEventsCleared => {
+                        *control_flow = ControlFlow::ExitWithCode(0);
+                    }
+                    _ => {}
+                });
+            self.events.extend(events);
+            if done(self) {
+                return;
+            }
+            assert!(
+                Instant::now() < deadline,
+                "timed out, events: {:#?}, requests: {:#?}",
+                self.events,
+                self.compositor.requests()
+            );
+        }
+    }
+
+    /// Run the event loop until an event matches `f`.
+    pub fn run_until_event(&mut self, f: impl Fn(&SctkEvent) -> bool) {
+        self.run_until(|h| h.events.iter().any(&f));
+    }
+
+    /// Run the event loop until the compositor received `request`.
+    pub fn run_until_request(&mut self, request: &Request) {
+        self.run_until(|h| h.compositor.requests().contains(request));
+    }
+}
+
+fn create_globals(dh: &DisplayHandle, toplevels: Toplevels) {
+    dh.create_global::<Server, WlCompositor, ()>(5, ());
+    dh.create_global::<Server, WlShm, ()>(1, ());
+    dh.create_global::<Server, XdgWmBase, ()>(5, ());
+    dh.create_global::<Server, WlDataDeviceManager, ()>(3, ());
+    dh.create_global::<Server, ZwpPrimarySelectionDeviceManagerV1, ()>(1, ());
+    dh.create_global::<Server, WlSeat, ()>(7, ());
+    dh.create_global::<Server, ZwlrLayerShellV1, ()>(4, ());
+    dh.create_global::<Server, ZwpInputMethodManagerV2, ()>(1, ());
+    dh.create_global::<Server, ZwpVirtualKeyboardManagerV1, ()>(1, ());
+    match toplevels {
+        Toplevels::None => {}
+        Toplevels::Wlr => {
+            dh.create_global::<Server, ZwlrForeignToplevelManagerV1, ()>(3, ());
+        }
+        Toplevels::Ext => {
+            dh.create_global::<Server, ExtForeignToplevelListV1, ()>(1, ());
+        }
+    }
+}
+
+/// A sealed copy of `keymap` with its terminating nul byte.
+fn keymap_fd(keymap: &str) -> (OwnedFd, u32) {
+    let fd = memfd_create("mock-keymap", MemfdFlags::CLOEXEC).unwrap();
+    let mut file = File::from(fd);
+    file.write_all(keymap.as_bytes()).unwrap();
+    file.write_all(&[0]).unwrap();
+    (file.into(), keymap.len() as u32 + 1)
+}
+
+/// Read a keymap shared by the client, which may have left the file offset
+/// at its end.
+fn read_keymap(fd: OwnedFd, size: u32) -> String {
+    let file = File::from(fd);
+    let mut data = vec![0; size as usize];
+    let read = file.read_at(&mut data, 0).unwrap();
+    data.truncate(read);
+    if let Some(nul) = data.iter().position(|b| *b == 0) {
+        data.truncate(nul);
+    }
+    String::from_utf8(data).unwrap()
+}
+
+macro_rules! global {
+    ($($iface:ty),* $(,)?) => {
+        $(
+            impl GlobalDispatch<$iface, ()> for Server {
+                fn bind(
+                    _: &mut Self,
+                    _: &DisplayHandle,
+                    _: &Client,
+                    resource: New<$iface>,
+                    _: &(),
+                    data_init: &mut DataInit<'_, Self>,
+                ) {
+                    data_init.init(resource, ());
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! ignore_requests {
+    ($($iface:ty),* $(,)?) => {
+        $(
+            impl Dispatch<$iface, ()> for Server {
+                fn request(
+                    _: &mut Self,
+                    _: &Client,
+                    _: &$iface,
+                    _: <$iface as Resource>::Request,
+                    _: &(),
+                    _: &DisplayHandle,
+                    _: &mut DataInit<'_, Self>,
+                ) {
+                }
+            }
+        )*
+    };
+}
+
+global!(
+    WlCompositor,
+    WlDataDeviceManager,
+    ZwpPrimarySelectionDeviceManagerV1,
+    ZwlrLayerShellV1,
+    ZwpInputMethodManagerV2,
+    ZwpVirtualKeyboardManagerV1,
+);
+
+impl GlobalDispatch<ZwlrForeignToplevelManagerV1, ()> for Server {
+    fn bind(
+        state: &mut Self,
+        _: &DisplayHandle,
+        _: &Client,
+        resource: New<ZwlrForeignToplevelManagerV1>,
+        _: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let manager = data_init.init(resource, ());
+        state.shared.lock().unwrap().toplevel_manager = Some(manager);
+    }
+}
+
+impl GlobalDispatch<ExtForeignToplevelListV1, ()> for Server {
+    fn bind(
+        state: &mut Self,
+        _: &DisplayHandle,
+        _: &Client,
+        resource: New<ExtForeignToplevelListV1>,
+        _: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let list = data_init.init(resource, ());
+        state.shared.lock().unwrap().toplevel_list = Some(list);
+    }
+}
+
+ignore_requests!(
+    WlRegion,
+    WlCallback,
+    WlDataDevice,
+    WlDataSource,
+    XdgPositioner,
+    XdgToplevel,
+    XdgPopup,
+    ZwpInputMethodKeyboardGrabV2,
+    ZwpInputPopupSurfaceV2,
+    WlKeyboard,
+    ZwlrForeignToplevelManagerV1,
+    ZwlrForeignToplevelHandleV1,
+    ExtForeignToplevelListV1,
+    ExtForeignToplevelHandleV1,
+);
+
+impl GlobalDispatch<WlShm, ()> for Server {
+    fn bind(
+        _: &mut Self,
+        _: &DisplayHandle,
+        _: &Client,
+        resource: New<WlShm>,
+        _: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let shm = data_init.init(resource, ());
+        shm.format(wl_shm::Format::Argb8888);
+        shm.format(wl_shm::Format::Xrgb8888);
+    }
+}
+
+impl GlobalDispatch<XdgWmBase, ()> for Server {
+    fn bind(
+        _: &mut Self,
+        _: &DisplayHandle,
+        _: &Client,
+        resource: New<XdgWmBase>,
+        _: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl GlobalDispatch<WlSeat, ()> for Server {
+    fn bind(
+        state: &mut Self,
+        _: &DisplayHandle,
+        _: &Client,
+        resource: New<WlSeat>,
+        _: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let seat = data_init.init(resource, ());
+        // No devices, input reaches the crate through the input method.
+        seat.capabilities(wl_seat::Capability::empty());
+        if seat.version() >= 2 {
+            seat.name("seat0".to_string());
+        }
+        state.shared.lock().unwrap().seat = Some(seat);
+    }
+}
+
+impl Dispatch<WlCompositor, ()> for Server {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &WlCompositor,
+        request: wl_compositor::Request,
+        _: &(),
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            wl_compositor::Request::CreateSurface { id } => {
+                data_init.init(id, ());
+            }
+            wl_compositor::Request::CreateRegion { id } => {
+                data_init.init(id, ());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<WlSurface, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        surface: &WlSurface,
+        request: wl_surface::Request,
+        _: &(),
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            wl_surface::Request::Frame { callback } => {
+                data_init.init(callback, ()).done(0);
+            }
+            wl_surface::Request::Attach {
+                buffer: Some(buffer),
+                ..
+            } => {
+                let shm = buffer.data::<ShmBuffer>().unwrap();
+                let mut pixel = [0; 4];
+                shm.pool
+                    .read_exact_at(&mut pixel, shm.offset as u64)
+                    .unwrap();
+                let mut shared = state.shared.lock().unwrap();
+                shared.requests.push(Request::AttachBuffer {
+                    width: shm.width,
+                    height: shm.height,
+                    format: shm.format,
+                    pixel,
+                });
+                let old =
+                    match state.attached.iter_mut().find(|(s, _)| s == surface)
+                    {
+                        Some((_, old)) => Some(std::mem::replace(old, buffer)),
+                        None => {
+                            state.attached.push((surface.clone(), buffer));
+                            None
+                        }
+                    };
+                match old {
+                    Some(old) if shared.hold_buffers => {
+                        shared.held_buffers.push(old)
+                    }
+                    Some(old) => old.release(),
+                    None => {}
+                }
+            }
+            wl_surface::Request::Commit => {
+                // Configure layer surfaces on their initial commit.
+                let mut serial = state.configure_serial;
+                for (s, layer_surface, configured) in &mut state.layer_surfaces
+                {
+                    if *s == *surface && !*configured {
+                        serial += 1;
+                        layer_surface.configure(serial, 800, 200);
+                        *configured = true;
+                    }
+                }
+                state.configure_serial = serial;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<WlShm, ()> for Server {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &WlShm,
+        request: wl_shm::Request,
+        _: &(),
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let wl_shm::Request::CreatePool { id, fd, .. } = request {
+            data_init.init(id, Arc::new(File::from(fd)));
+        }
+    }
+}
+
+impl Dispatch<WlShmPool, Arc<File>> for Server {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &WlShmPool,
+        request: wl_shm_pool::Request,
+        pool: &Arc<File>,
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let wl_shm_pool::Request::CreateBuffer {
+            id,
+            offset,
+            width,
+            height,
+            format,
+            ..
+        } = request
+        {
+            data_init.init(
+                id,
+                ShmBuffer {
+                    pool: pool.clone(),
+                    offset,
+                    width,
+                    height,
+                    format: format.into_result().unwrap(),
+                },
+            );
+        }
+    }
+}
+
+impl Dispatch<WlBuffer, ShmBuffer> for Server {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        buffer: &WlBuffer,
+        _: <WlBuffer as Resource>::Request,
+        _: &ShmBuffer,
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+        // Destroyed, it is no longer on screen nor held.
+        state.attached.retain(|(_, b)| b != buffer);
+        state
+            .shared
+            .lock()
+            .unwrap()
+            .held_buffers
+            .retain(|b| b != buffer);
+    }
+}
+
+impl Dispatch<XdgWmBase, ()> for Server {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &XdgWmBase,
+        request: xdg_wm_base::Request,
+        _: &(),
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            xdg_wm_base::Request::CreatePositioner { id } => {
+                data_init.init(id, ());
+            }
+            xdg_wm_base::Request::GetXdgSurface { id, .. } => {
+                data_init.init(id, ());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<XdgSurface, ()> for Server {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &XdgSurface,
+        request: xdg_surface::Request,
+        _: &(),
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            xdg_surface::Request::GetToplevel { id } => {
+                data_init.init(id, ());
+            }
+            xdg_surface::Request::GetPopup { id, .. } => {
+                data_init.init(id, ());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<WlSeat, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        _: &WlSeat,
+        request: wl_seat::Request,
+        _: &(),
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let wl_seat::Request::GetKeyboard { id } = request {
+            let keyboard = data_init.init(id, ());
+            let (fd, size) = keymap_fd(KEYMAP);
+            keyboard.keymap(KeymapFormat::XkbV1, fd.as_fd(), size);
+            if keyboard.version() >= 4 {
+                keyboard.repeat_info(0, 0);
+            }
+            state.shared.lock().unwrap().keyboard = Some(keyboard);
+        }
+    }
+}
+
+impl Dispatch<WlDataDeviceManager, ()> for Server {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &WlDataDeviceManager,
+        request: wl_data_device_manager::Request,
+        _: &(),
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            wl_data_device_manager::Request::CreateDataSource { id } => {
+                data_init.init(id, ());
+            }
+            wl_data_device_manager::Request::GetDataDevice { id, .. } => {
+                data_init.init(id, ());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwpPrimarySelectionDeviceManagerV1, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        _: &ZwpPrimarySelectionDeviceManagerV1,
+        request: zwp_primary_selection_device_manager_v1::Request,
+        _: &(),
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            zwp_primary_selection_device_manager_v1::Request::CreateSource {
+                id,
+            } => {
+                data_init.init(id, Mutex::new(Vec::new()));
+            }
+            zwp_primary_selection_device_manager_v1::Request::GetDevice {
+                id,
+                ..
+            } => {
+                let device = data_init.init(id, ());
+                state.shared.lock().unwrap().primary_selection_device =
+                    Some(device);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwpPrimarySelectionDeviceV1, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        _: &ZwpPrimarySelectionDeviceV1,
+        request: zwp_primary_selection_device_v1::Request,
+        _: &(),
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+        if let zwp_primary_selection_device_v1::Request::SetSelection {
+            source,
+            ..
+        } = request
+        {
+            let mut shared = state.shared.lock().unwrap();
+            let request = match source.as_ref() {
+                Some(source) => Request::SetPrimarySelection {
+                    mime_types: source
+                        .data::<Mutex<Vec<String>>>()
+                        .unwrap()
+                        .lock()
+                        .unwrap()
+                        .clone(),
+                },
+                None => Request::UnsetPrimarySelection,
+            };
+            shared.primary_selection_source = source;
+            shared.requests.push(request);
+        }
+    }
+}
+
+impl Dispatch<ZwpPrimarySelectionSourceV1, Mutex<Vec<String>>> for Server {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &ZwpPrimarySelectionSourceV1,
+        request: zwp_primary_selection_source_v1::Request,
+        mime_types: &Mutex<Vec<String>>,
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+        if let zwp_primary_selection_source_v1::Request::Offer { mime_type } =
+            request
+        {
+            mime_types.lock().unwrap().push(mime_type);
+        }
+    }
+}
+
+impl Dispatch<ZwpPrimarySelectionOfferV1, Vec<u8>> for Server {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        _: &ZwpPrimarySelectionOfferV1,
+        request: zwp_primary_selection_offer_v1::Request,
+        data: &Vec<u8>,
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+        if let zwp_primary_selection_offer_v1::Request::Receive { fd, .. } =
+            request
+        {
+            // Written from another thread, the pipe fills up long before
+            // large selections are sent.
+            let shared = state.shared.clone();
+            let data = data.clone();
+            thread::spawn(move || {
+                let complete = File::from(fd).write_all(&data).is_ok();
+                shared
+                    .lock()
+                    .unwrap()
+                    .requests
+                    .push(Request::PrimarySelectionSent { complete });
+            });
+        }
+    }
+}
+
+impl Dispatch<ZwlrLayerShellV1, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        _: &ZwlrLayerShellV1,
+        request: zwlr_layer_shell_v1::Request,
+        _: &(),
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let zwlr_layer_shell_v1::Request::GetLayerSurface {
+            id,
+            surface,
+            namespace,
+            ..
+        } = request
+        {
+            let layer_surface = data_init.init(id, ());
+            state.layer_surfaces.push((surface, layer_surface, false));
+            state
+                .shared
+                .lock()
+                .unwrap()
+                .requests
+                .push(Request::LayerSurface { namespace });
+        }
+    }
+}
+
+impl Dispatch<ZwlrLayerSurfaceV1, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        layer_surface: &ZwlrLayerSurfaceV1,
+        request: zwlr_layer_surface_v1::Request,
+        _: &(),
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+        if let zwlr_layer_surface_v1::Request::Destroy = request {
+            state.layer_surfaces.retain(|(_, l, _)| l != layer_surface);
+        }
+    }
+}
+
+impl Dispatch<ZwpInputMethodManagerV2, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        _: &ZwpInputMethodManagerV2,
+        request: zwp_input_method_manager_v2::Request,
+        _: &(),
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let zwp_input_method_manager_v2::Request::GetInputMethod {
+            input_method,
+            ..
+        } = request
+        {
+            let input_method = data_init.init(input_method, ());
+            state.shared.lock().unwrap().input_method = Some(input_method);
+        }
+    }
+}
+
+impl Dispatch<ZwpInputMethodV2, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        _: &ZwpInputMethodV2,
+        request: zwp_input_method_v2::Request,
+        _: &(),
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let mut shared = state.shared.lock().unwrap();
+        let request = match request {
+            zwp_input_method_v2::Request::CommitString { text } => {
+                Request::CommitString(text)
+            }
+            zwp_input_method_v2::Request::SetPreeditString {
+                text,
+                cursor_begin,
+                cursor_end,
+            } => Request::SetPreeditString {
+                text,
+                cursor_begin,
+                cursor_end,
+            },
+            zwp_input_method_v2::Request::DeleteSurroundingText {
+                before_length,
+                after_length,
+            } => Request::DeleteSurroundingText {
+                before_length,
+                after_length,
+            },
+            zwp_input_method_v2::Request::Commit { serial } => {
+                Request::Commit(serial)
+            }
+            zwp_input_method_v2::Request::GetInputPopupSurface {
+                id, ..
+            } => {
+                data_init.init(id, ());
+                Request::GetInputPopupSurface
+            }
+            zwp_input_method_v2::Request::GrabKeyboard { keyboard } => {
+                let grab = data_init.init(keyboard, ());
+                let (fd, size) = keymap_fd(KEYMAP);
+                grab.keymap(KeymapFormat::XkbV1, fd.as_fd(), size);
+                // Keys do not repeat.
+                grab.repeat_info(0, 0);
+                shared.keyboard_grab = Some(grab);
+                Request::GrabKeyboard
+            }
+            _ => return,
+        };
+        shared.requests.push(request);
+    }
+}
+
+impl Dispatch<ZwpVirtualKeyboardManagerV1, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        _: &ZwpVirtualKeyboardManagerV1,
+        request: zwp_virtual_keyboard_manager_v1::Request,
+        _: &(),
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let zwp_virtual_keyboard_manager_v1::Request::CreateVirtualKeyboard {
+            id,
+            ..
+        } = request
+        {
+            let virtual_keyboard = data_init.init(id, ());
+            state.shared.lock().unwrap().virtual_keyboard =
+                Some(virtual_keyboard);
+        }
+    }
+}
+
+impl Dispatch<ZwpVirtualKeyboardV1, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        _: &ZwpVirtualKeyboardV1,
+        request: zwp_virtual_keyboard_v1::Request,
+        _: &(),
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+        let request = match request {
+            zwp_virtual_keyboard_v1::Request::Keymap { fd, size, .. } => {
+                Request::VirtualKeyboardKeymap(read_keymap(fd, size))
+            }
+            zwp_virtual_keyboard_v1::Request::Key { key, state, .. } => {
+                Request::VirtualKey {
+                    key,
+                    pressed: state == 1,
+                }
+            }
+            zwp_virtual_keyboard_v1::Request::Modifiers {
+                mods_depressed,
+                mods_latched,
+                mods_locked,
+                group,
+            } => Request::VirtualModifiers {
+                depressed: mods_depressed,
+                latched: mods_latched,
+                locked: mods_locked,
+                group,
+            },
+            _ => return,
+        };
+        state.shared.lock().unwrap().requests.push(request);
+    }
+}
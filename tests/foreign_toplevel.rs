@@ -0,0 +1,157 @@
+mod common;
+
+use common::{Harness, Toplevel, Toplevels};
+use iced_wayland_input_method::{
+    application::Event,
+    core::event::wayland::{ForeignToplevel, ForeignToplevelEvent},
+    runtime::command::platform_specific::wayland::foreign_toplevel::ActionInner,
+    sctk_event::{self, SctkEvent},
+};
+use wayland_protocols_wlr::foreign_toplevel::v1::server::zwlr_foreign_toplevel_handle_v1::State;
+
+/// The toplevel events the event loop reported.
+fn toplevel_events(harness: &Harness) -> Vec<ForeignToplevelEvent> {
+    harness
+        .events
+        .iter()
+        .filter_map(|event| match event {
+            SctkEvent::ForeignToplevel(event) => Some(match event.clone() {
+                sctk_event::ForeignToplevelEvent::Opened(t) => {
+                    ForeignToplevelEvent::Opened(t)
+                }
+                sctk_event::ForeignToplevelEvent::Changed(t) => {
+                    ForeignToplevelEvent::Changed(t)
+                }
+                sctk_event::ForeignToplevelEvent::Closed(t) => {
+                    ForeignToplevelEvent::Closed(t)
+                }
+                sctk_event::ForeignToplevelEvent::List(list) => {
+                    ForeignToplevelEvent::List(list)
+                }
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Run the event loop until `event` was reported.
+fn run_until_toplevel_event(
+    harness: &mut Harness,
+    event: ForeignToplevelEvent,
+) {
+    harness.run_until(|h| toplevel_events(h).contains(&event));
+}
+
+fn request_toplevels(harness: &mut Harness) {
+    harness.send(Event::ForeignToplevel(ActionInner::RequestToplevels.into()));
+}
+
+fn open(harness: &mut Harness, app_id: &str, title: &str) -> Toplevel {
+    harness.run_until(|h| h.compositor.tracks_toplevels());
+    harness.compositor.open_toplevel(app_id, title)
+}
+
+#[test]
+fn wlr_toplevels_are_tracked_with_their_state() {
+    let mut harness = Harness::with_toplevels(Toplevels::Wlr);
+    let handle = open(&mut harness, "org.gnome.Console", "~");
+    let mut toplevel = ForeignToplevel {
+        id: handle.id(),
+        title: "~".to_string(),
+        app_id: "org.gnome.Console".to_string(),
+        ..Default::default()
+    };
+    run_until_toplevel_event(
+        &mut harness,
+        ForeignToplevelEvent::Opened(toplevel.clone()),
+    );
+
+    harness.compositor.set_toplevel_state(
+        &handle,
+        &[State::Activated, State::Maximized, State::Fullscreen],
+    );
+    toplevel.activated = true;
+    toplevel.maximized = true;
+    toplevel.fullscreen = true;
+    run_until_toplevel_event(
+        &mut harness,
+        ForeignToplevelEvent::Changed(toplevel.clone()),
+    );
+
+    // States not listed are cleared.
+    harness
+        .compositor
+        .set_toplevel_state(&handle, &[State::Minimized]);
+    toplevel.activated = false;
+    toplevel.maximized = false;
+    toplevel.fullscreen = false;
+    toplevel.minimized = true;
+    run_until_toplevel_event(
+        &mut harness,
+        ForeignToplevelEvent::Changed(toplevel.clone()),
+    );
+
+    request_toplevels(&mut harness);
+    run_until_toplevel_event(
+        &mut harness,
+        ForeignToplevelEvent::List(vec![toplevel.clone()]),
+    );
+
+    harness.compositor.close_toplevel(&handle);
+    run_until_toplevel_event(
+        &mut harness,
+        ForeignToplevelEvent::Closed(toplevel),
+    );
+    request_toplevels(&mut harness);
+    run_until_toplevel_event(&mut harness, ForeignToplevelEvent::List(vec![]));
+}
+
+#[test]
+fn ext_toplevels_are_tracked() {
+    let mut harness = Harness::with_toplevels(Toplevels::Ext);
+    let first = open(&mut harness, "org.gnome.Console", "~");
+    let second = open(&mut harness, "firefox", "Mozilla Firefox");
+    let mut toplevels: Vec<_> = [
+        (&first, "org.gnome.Console", "~"),
+        (&second, "firefox", "Mozilla Firefox"),
+    ]
+    .into_iter()
+    .map(|(handle, app_id, title)| ForeignToplevel {
+        id: handle.id(),
+        title: title.to_string(),
+        app_id: app_id.to_string(),
+        identifier: Some(format!("{app_id}-{}", handle.id())),
+        ..Default::default()
+    })
+    .collect();
+    for toplevel in &toplevels {
+        run_until_toplevel_event(
+            &mut harness,
+            ForeignToplevelEvent::Opened(toplevel.clone()),
+        );
+    }
+
+    harness.compositor.set_toplevel_title(&first, "~/src");
+    toplevels[0].title = "~/src".to_string();
+    run_until_toplevel_event(
+        &mut harness,
+        ForeignToplevelEvent::Changed(toplevels[0].clone()),
+    );
+
+    request_toplevels(&mut harness);
+    run_until_toplevel_event(
+        &mut harness,
+        ForeignToplevelEvent::List(toplevels.clone()),
+    );
+
+    harness.compositor.close_toplevel(&first);
+    run_until_toplevel_event(
+        &mut harness,
+        ForeignToplevelEvent::Closed(toplevels.remove(0)),
+    );
+    request_toplevels(&mut harness);
+    run_until_toplevel_event(
+        &mut harness,
+        ForeignToplevelEvent::List(toplevels),
+    );
+}
@@ -0,0 +1,340 @@
+mod common;
+
+use common::{Harness, Request};
+use iced_wayland_input_method::{
+    application::Event,
+    core::event::wayland::TextChange,
+    runtime::command::platform_specific::wayland::input_method::ActionInner,
+    sctk_event::{
+        InputMethodEventVariant, InputMethodKeyboardEventVariant, SctkEvent,
+    },
+};
+use wayland_protocols::wp::text_input::zv3::server::zwp_text_input_v3::ChangeCause;
+
+/// evdev code of the `a` key.
+const KEY_A: u32 = 30;
+
+fn input_method_events(harness: &Harness) -> Vec<InputMethodEventVariant> {
+    harness
+        .events
+        .iter()
+        .filter_map(|event| match event {
+            SctkEvent::InputMethodEvent { variant } => Some(variant.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn keyboard_events(harness: &Harness) -> Vec<InputMethodKeyboardEventVariant> {
+    harness
+        .events
+        .iter()
+        .filter_map(|event| match event {
+            SctkEvent::InputMethodKeyboardEvent { variant } => {
+                Some(variant.clone())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn activation_is_reported_in_order() {
+    let mut harness = Harness::new();
+    harness.compositor.activate();
+    harness.compositor.surrounding_text("hello", 5, 5);
+    harness.compositor.done();
+    harness.run_until_event(|event| {
+        matches!(
+            event,
+            SctkEvent::InputMethodEvent {
+                variant: InputMethodEventVariant::Done
+            }
+        )
+    });
+
+    let events = input_method_events(&harness);
+    let activate = events
+        .iter()
+        .position(|e| matches!(e, InputMethodEventVariant::Activate))
+        .expect("no activate");
+    let surrounding = events
+        .iter()
+        .position(|e| {
+            matches!(
+                e,
+                InputMethodEventVariant::SurroundingText { text, cursor: 5, anchor: 5 }
+                    if text == "hello"
+            )
+        })
+        .expect("no surrounding text");
+    let done = events
+        .iter()
+        .position(|e| matches!(e, InputMethodEventVariant::Done))
+        .unwrap();
+    assert!(activate < surrounding && surrounding < done);
+}
+
+#[test]
+fn commit_string_is_sent_with_the_serial() {
+    let mut harness = Harness::new();
+    harness.compositor.activate();
+    harness.compositor.done();
+    harness.run_until_event(|event| {
+        matches!(
+            event,
+            SctkEvent::InputMethodEvent {
+                variant: InputMethodEventVariant::Done
+            }
+        )
+    });
+
+    let serial = harness.compositor.serial();
+    harness.send(Event::InputMethod(
+        ActionInner::CommitString("hi".to_string()).into(),
+    ));
+    harness.send(Event::InputMethod(ActionInner::Commit(serial).into()));
+    harness.run_until_request(&Request::Commit(serial));
+
+    assert_eq!(
+        harness.compositor.take_requests(),
+        vec![
+            Request::CommitString("hi".to_string()),
+            Request::Commit(serial)
+        ]
+    );
+}
+
+#[test]
+fn preedit_and_deletion_are_sent() {
+    let mut harness = Harness::new();
+    harness.send(Event::InputMethod(
+        ActionInner::SetPreeditString {
+            string: "ni".to_string(),
+            cursor_begin: 2,
+            cursor_end: 2,
+        }
+        .into(),
+    ));
+    harness.send(Event::InputMethod(
+        ActionInner::DeleteSurroundingText {
+            before_length: 1,
+            after_length: 0,
+        }
+        .into(),
+    ));
+    harness.send(Event::InputMethod(ActionInner::Commit(0).into()));
+    harness.run_until_request(&Request::Commit(0));
+
+    assert_eq!(
+        harness.compositor.take_requests(),
+        vec![
+            Request::SetPreeditString {
+                text: "ni".to_string(),
+                cursor_begin: 2,
+                cursor_end: 2,
+            },
+            Request::DeleteSurroundingText {
+                before_length: 1,
+                after_length: 0,
+            },
+            Request::Commit(0),
+        ]
+    );
+}
+
+/// Run until the next surrounding text change is reported and return it.
+fn text_changed(harness: &mut Harness) -> TextChange {
+    harness.events.clear();
+    harness.run_until_event(|event| {
+        matches!(
+            event,
+            SctkEvent::InputMethodEvent {
+                variant: InputMethodEventVariant::TextChanged(_)
+            }
+        )
+    });
+    input_method_events(harness)
+        .into_iter()
+        .find_map(|e| match e {
+            InputMethodEventVariant::TextChanged(change) => Some(change.into()),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn committed_preedit_is_echoed() {
+    let mut harness = Harness::new();
+    harness.compositor.activate();
+    harness.compositor.done();
+    harness.run_until_event(|event| {
+        matches!(
+            event,
+            SctkEvent::InputMethodEvent {
+                variant: InputMethodEventVariant::Done
+            }
+        )
+    });
+
+    let serial = harness.compositor.serial();
+    harness.send(Event::InputMethod(
+        ActionInner::SetPreeditString {
+            string: "ni".to_string(),
+            cursor_begin: 2,
+            cursor_end: 2,
+        }
+        .into(),
+    ));
+    harness.send(Event::InputMethod(ActionInner::Commit(serial).into()));
+    harness.run_until_request(&Request::Commit(serial));
+
+    harness.compositor.surrounding_text("ni", 2, 2);
+    harness
+        .compositor
+        .text_change_cause(ChangeCause::InputMethod);
+    harness.compositor.done();
+    assert_eq!(text_changed(&mut harness), TextChange::Echo);
+
+    harness.compositor.surrounding_text("nix", 3, 3);
+    harness.compositor.text_change_cause(ChangeCause::Other);
+    harness.compositor.done();
+    assert_eq!(text_changed(&mut harness), TextChange::External);
+}
+
+#[test]
+fn external_changes_between_echoes_are_told_apart() {
+    let mut harness = Harness::new();
+    harness.compositor.activate();
+    harness.compositor.done();
+    let serial = harness.compositor.serial();
+    for text in ["a", "b"] {
+        harness.send(Event::InputMethod(
+            ActionInner::CommitString(text.to_string()).into(),
+        ));
+        harness.send(Event::InputMethod(ActionInner::Commit(serial).into()));
+    }
+    harness.run_until(|h| {
+        h.compositor
+            .requests()
+            .iter()
+            .filter(|r| **r == Request::Commit(serial))
+            .count()
+            == 2
+    });
+
+    // The user typed before the client echoed both commits.
+    harness.compositor.surrounding_text("x", 1, 1);
+    harness.compositor.text_change_cause(ChangeCause::Other);
+    harness.compositor.done();
+    assert_eq!(text_changed(&mut harness), TextChange::External);
+    for _ in 0..2 {
+        harness
+            .compositor
+            .text_change_cause(ChangeCause::InputMethod);
+        harness.compositor.done();
+        assert_eq!(text_changed(&mut harness), TextChange::Echo);
+    }
+    // Nothing is outstanding anymore.
+    harness
+        .compositor
+        .text_change_cause(ChangeCause::InputMethod);
+    harness.compositor.done();
+    assert_eq!(text_changed(&mut harness), TextChange::External);
+}
+
+#[test]
+fn external_changes_clear_the_preedit() {
+    let mut harness = Harness::new();
+    harness.compositor.activate();
+    harness.compositor.done();
+    let serial = harness.compositor.serial();
+    harness.send(Event::InputMethod(
+        ActionInner::SetPreeditString {
+            string: ":smi".to_string(),
+            cursor_begin: 4,
+            cursor_end: 4,
+        }
+        .into(),
+    ));
+    harness.send(Event::InputMethod(ActionInner::Commit(serial).into()));
+    harness.run_until_request(&Request::Commit(serial));
+    harness.compositor.take_requests();
+    let external_changes = harness.privacy.external_changes();
+
+    harness.compositor.surrounding_text("hello", 5, 5);
+    harness.compositor.text_change_cause(ChangeCause::Other);
+    harness.compositor.done();
+    assert_eq!(text_changed(&mut harness), TextChange::External);
+    harness.run_until_request(&Request::Commit(serial + 1));
+    assert_eq!(
+        harness.compositor.take_requests(),
+        vec![
+            Request::SetPreeditString {
+                text: String::new(),
+                cursor_begin: 0,
+                cursor_end: 0,
+            },
+            Request::Commit(serial + 1),
+        ]
+    );
+    // Engines following the watch drop their query on the next key.
+    assert_eq!(harness.privacy.external_changes(), external_changes + 1);
+
+    // Without a preedit there is nothing to clear.
+    harness.compositor.text_change_cause(ChangeCause::Other);
+    harness.compositor.done();
+    assert_eq!(text_changed(&mut harness), TextChange::External);
+    assert!(harness.compositor.take_requests().is_empty());
+}
+
+#[test]
+fn grabbed_keys_are_reported() {
+    let mut harness = Harness::new();
+    harness.compositor.key(KEY_A, true);
+    harness.compositor.key(KEY_A, false);
+    harness.run_until(|h| {
+        keyboard_events(h)
+            .iter()
+            .any(|e| matches!(e, InputMethodKeyboardEventVariant::Release(_)))
+    });
+
+    let press = keyboard_events(&harness)
+        .into_iter()
+        .find_map(|e| match e {
+            InputMethodKeyboardEventVariant::Press(key) => Some(key),
+            _ => None,
+        })
+        .expect("no press");
+    assert_eq!(press.raw_code, KEY_A);
+    assert_eq!(press.utf8.as_deref(), Some("a"));
+}
+
+#[test]
+fn layouts_are_reported() {
+    let mut harness = Harness::new();
+    harness.run_until(|h| {
+        keyboard_events(h)
+            .iter()
+            .any(|e| matches!(e, InputMethodKeyboardEventVariant::Layouts(_)))
+    });
+    let layouts = keyboard_events(&harness).into_iter().find_map(|e| match e {
+        InputMethodKeyboardEventVariant::Layouts(layouts) => Some(layouts),
+        _ => None,
+    });
+    assert_eq!(
+        layouts,
+        Some(vec!["English (US)".to_string(), "German".to_string()])
+    );
+
+    harness.compositor.modifiers(0, 0, 0, 1);
+    harness.run_until(|h| {
+        keyboard_events(h).iter().any(|e| {
+            matches!(
+                e,
+                InputMethodKeyboardEventVariant::LayoutChanged { index: 1, name }
+                    if name == "German"
+            )
+        })
+    });
+}
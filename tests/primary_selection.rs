@@ -0,0 +1,127 @@
+mod common;
+
+use common::{Harness, Request};
+use iced_wayland_input_method::{
+    application::Event,
+    clipboard::Contents,
+    runtime::command::platform_specific::wayland::data_device::ActionInner,
+    sctk_event::{SctkEvent, SelectionKind, SelectionOfferEvent},
+};
+
+/// The evdev code of `a` in the mock keymap.
+const KEY_A: u32 = 30;
+
+/// The primary selection data the crate read.
+fn primary_data(harness: &Harness) -> Vec<(String, Vec<u8>)> {
+    harness
+        .events
+        .iter()
+        .filter_map(|event| match event {
+            SctkEvent::SelectionOffer(
+                SelectionOfferEvent::Data { mime_type, data },
+                SelectionKind::Primary,
+            ) => Some((mime_type.clone(), data.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Give the seat a keyboard and type on it, the selection may only be set
+/// with the serial of some input.
+fn type_on_keyboard(harness: &mut Harness) {
+    harness.compositor.add_keyboard();
+    harness.run_until(|h| h.compositor.has_keyboard());
+    harness.compositor.seat_key(KEY_A, true);
+    harness.compositor.seat_key(KEY_A, false);
+    harness.run_until_event(|event| {
+        matches!(event, SctkEvent::KeyboardEvent { .. })
+    });
+}
+
+/// Offer `data` as the primary selection once the crate can receive it.
+fn offer(harness: &mut Harness, mime_types: &[&str], data: &[u8]) {
+    harness.run_until(|h| h.compositor.has_primary_selection_device());
+    harness.compositor.offer_primary_selection(mime_types, data);
+    harness.run_until_event(|event| {
+        matches!(
+            event,
+            SctkEvent::SelectionOffer(
+                SelectionOfferEvent::Offer(_),
+                SelectionKind::Primary,
+            )
+        )
+    });
+}
+
+#[test]
+fn primary_selection_is_set_and_unset() {
+    let mut harness = Harness::new();
+    type_on_keyboard(&mut harness);
+
+    let contents = Contents::text("déjà vu");
+    harness.send(Event::DataDevice(
+        ActionInner::SetPrimarySelection {
+            mime_types: contents.mime_types(),
+            data: Box::new(contents.clone()),
+        }
+        .into(),
+    ));
+    harness.run_until_request(&Request::SetPrimarySelection {
+        mime_types: contents.mime_types(),
+    });
+
+    let reader = harness.compositor.receive_primary_selection("text/plain");
+    harness.run_until(|_| reader.is_finished());
+    assert_eq!(reader.join().unwrap(), "déjà vu".as_bytes());
+
+    harness.send(Event::DataDevice(ActionInner::UnsetPrimarySelection.into()));
+    harness.run_until_request(&Request::UnsetPrimarySelection);
+}
+
+#[test]
+fn primary_selection_is_read() {
+    let mut harness = Harness::new();
+    offer(&mut harness, &["text/plain", "UTF8_STRING"], b"selected");
+    assert!(harness.events.iter().any(|event| matches!(
+        event,
+        SctkEvent::SelectionOffer(
+            SelectionOfferEvent::Offer(mime_types),
+            SelectionKind::Primary,
+        ) if mime_types == &["text/plain", "UTF8_STRING"]
+    )));
+
+    harness.send(Event::DataDevice(
+        ActionInner::RequestPrimarySelectionData {
+            mime_type: "UTF8_STRING".to_string(),
+        }
+        .into(),
+    ));
+    harness.run_until(|h| !primary_data(h).is_empty());
+    assert_eq!(
+        primary_data(&harness),
+        [("UTF8_STRING".to_string(), b"selected".to_vec())]
+    );
+}
+
+#[test]
+fn oversized_primary_selection_is_dropped() {
+    let mut harness = Harness::new();
+    // Twice the limit of the crate.
+    offer(&mut harness, &["text/plain"], &b"a".repeat(2 << 20));
+
+    harness.send(Event::DataDevice(
+        ActionInner::RequestPrimarySelectionData {
+            mime_type: "text/plain".to_string(),
+        }
+        .into(),
+    ));
+    // The crate closes the pipe once the limit is exceeded.
+    harness
+        .run_until_request(&Request::PrimarySelectionSent { complete: false });
+    let mut iterations = 0;
+    harness.run_until(|_| {
+        iterations += 1;
+        iterations > 10
+    });
+    assert!(primary_data(&harness).is_empty());
+}
@@ -0,0 +1,250 @@
+//! Pixel output of the software compositor and its presentation through
+//! `wl_shm`.
+#![cfg(feature = "software")]
+
+mod common;
+
+use common::{Harness, Request};
+use iced_graphics::{Compositor as _, Viewport};
+use iced_tiny_skia::Renderer;
+use iced_wayland_input_method::{
+    core::{
+        renderer::{Quad, Renderer as _},
+        Background, Color, Rectangle, Size,
+    },
+    runtime::command::platform_specific::wayland::layer_surface::SctkLayerSurfaceSettings,
+    software::{Compositor, Settings, Surface},
+};
+use raw_window_handle::{
+    HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
+    WaylandDisplayHandle, WaylandWindowHandle, XlibDisplayHandle,
+    XlibWindowHandle,
+};
+use sctk::reexports::client::{protocol::wl_surface::WlSurface, Proxy};
+use wayland_server::protocol::wl_shm;
+
+const WIDTH: u32 = 40;
+const HEIGHT: u32 = 20;
+
+/// A window which is not a wayland surface, frames are only rendered.
+struct Offscreen;
+
+unsafe impl HasRawWindowHandle for Offscreen {
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        RawWindowHandle::Xlib(XlibWindowHandle::empty())
+    }
+}
+
+unsafe impl HasRawDisplayHandle for Offscreen {
+    fn raw_display_handle(&self) -> RawDisplayHandle {
+        RawDisplayHandle::Xlib(XlibDisplayHandle::empty())
+    }
+}
+
+/// A layer surface of the event loop of a [`Harness`].
+struct Layer(WlSurface);
+
+unsafe impl HasRawWindowHandle for Layer {
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        let mut handle = WaylandWindowHandle::empty();
+        handle.surface = self.0.id().as_ptr().cast();
+        RawWindowHandle::Wayland(handle)
+    }
+}
+
+unsafe impl HasRawDisplayHandle for Layer {
+    fn raw_display_handle(&self) -> RawDisplayHandle {
+        RawDisplayHandle::Wayland(WaylandDisplayHandle::empty())
+    }
+}
+
+fn setup(
+    scale_factor: f64,
+) -> (Compositor<()>, Renderer<()>, Surface, Viewport) {
+    let (mut compositor, renderer) =
+        Compositor::new(Settings::default(), Some(&Offscreen)).unwrap();
+    let viewport =
+        Viewport::with_physical_size(Size::new(WIDTH, HEIGHT), scale_factor);
+    let surface = compositor.create_surface(&Offscreen, WIDTH, HEIGHT);
+    (compositor, renderer, surface, viewport)
+}
+
+/// The RGBA pixel at `x`, `y` of a screenshot.
+fn pixel(pixels: &[u8], x: u32, y: u32) -> [u8; 4] {
+    let i = ((y * WIDTH + x) * 4) as usize;
+    pixels[i..i + 4].try_into().unwrap()
+}
+
+fn fill(renderer: &mut Renderer<()>, bounds: Rectangle, color: Color) {
+    renderer.fill_quad(
+        Quad {
+            bounds,
+            border_radius: 0.0.into(),
+            border_width: 0.0,
+            border_color: Color::TRANSPARENT,
+        },
+        Background::Color(color),
+    );
+}
+
+#[test]
+fn background_fills_the_surface() {
+    let (mut compositor, mut renderer, mut surface, viewport) = setup(1.0);
+    let pixels = compositor.screenshot(
+        &mut renderer,
+        &mut surface,
+        &viewport,
+        Color::from_rgb8(0x20, 0x40, 0x80),
+        &[] as &[String],
+    );
+
+    assert_eq!(pixels.len(), (WIDTH * HEIGHT * 4) as usize);
+    assert!(pixels
+        .chunks_exact(4)
+        .all(|pixel| pixel == [0x20, 0x40, 0x80, 0xff]));
+}
+
+#[test]
+fn quads_are_drawn_in_logical_pixels() {
+    let (mut compositor, mut renderer, mut surface, viewport) = setup(2.0);
+    fill(
+        &mut renderer,
+        Rectangle::new([0.0, 0.0].into(), Size::new(5.0, 5.0)),
+        Color::from_rgb8(0xff, 0, 0),
+    );
+    let pixels = compositor.screenshot(
+        &mut renderer,
+        &mut surface,
+        &viewport,
+        Color::WHITE,
+        &[] as &[String],
+    );
+
+    // 5 logical pixels are 10 physical ones at a scale of 2.
+    assert_eq!(pixel(&pixels, 0, 0), [0xff, 0, 0, 0xff]);
+    assert_eq!(pixel(&pixels, 9, 9), [0xff, 0, 0, 0xff]);
+    assert_eq!(pixel(&pixels, 10, 0), [0xff, 0xff, 0xff, 0xff]);
+    assert_eq!(pixel(&pixels, 0, 10), [0xff, 0xff, 0xff, 0xff]);
+}
+
+#[test]
+fn translucent_pixels_are_not_premultiplied() {
+    let (mut compositor, mut renderer, mut surface, viewport) = setup(1.0);
+    let pixels = compositor.screenshot(
+        &mut renderer,
+        &mut surface,
+        &viewport,
+        Color::from_rgba8(0xff, 0x80, 0, 0.5),
+        &[] as &[String],
+    );
+
+    let [r, g, b, a] = pixel(&pixels, WIDTH / 2, HEIGHT / 2);
+    assert_eq!((r, b), (0xff, 0));
+    assert!(g.abs_diff(0x80) <= 1, "green is {g}");
+    assert!(a.abs_diff(0x80) <= 1, "alpha is {a}");
+}
+
+/// A compositor presenting on a new layer surface of `harness`.
+fn setup_presenting(
+    harness: &mut Harness,
+) -> (Compositor<()>, Renderer<()>, Surface, Viewport) {
+    let (_, wl_surface) = harness
+        .event_loop
+        .get_layer_surface(SctkLayerSurfaceSettings {
+            size: Some((Some(WIDTH), Some(HEIGHT))),
+            ..Default::default()
+        })
+        .unwrap();
+    let settings = harness.event_loop.connect_software(Settings::default());
+    let (mut compositor, renderer) =
+        Compositor::new(settings, Some(&Offscreen)).unwrap();
+    let viewport = Viewport::with_physical_size(Size::new(WIDTH, HEIGHT), 1.0);
+    let surface = compositor.create_surface(&Layer(wl_surface), WIDTH, HEIGHT);
+    (compositor, renderer, surface, viewport)
+}
+
+/// The buffers attached so far.
+fn attached(harness: &Harness) -> Vec<Request> {
+    harness
+        .compositor
+        .requests()
+        .into_iter()
+        .filter(|request| matches!(request, Request::AttachBuffer { .. }))
+        .collect()
+}
+
+/// An attached `Argb8888` buffer of the whole surface whose first pixel
+/// is `color`, premultiplied and in memory order.
+fn buffer(color: [u8; 3]) -> Request {
+    let [r, g, b] = color;
+    Request::AttachBuffer {
+        width: WIDTH as i32,
+        height: HEIGHT as i32,
+        format: wl_shm::Format::Argb8888,
+        pixel: [b, g, r, 0xff],
+    }
+}
+
+/// Present a frame filled with `color` and run the event loop until
+/// `attached_after` buffers were attached in total.
+fn present(
+    harness: &mut Harness,
+    (compositor, renderer, surface, viewport): &mut (
+        Compositor<()>,
+        Renderer<()>,
+        Surface,
+        Viewport,
+    ),
+    color: [u8; 3],
+    attached_after: usize,
+) {
+    let [r, g, b] = color;
+    compositor
+        .present(
+            renderer,
+            surface,
+            viewport,
+            Color::from_rgb8(r, g, b),
+            &[] as &[String],
+        )
+        .unwrap();
+    harness.run_until(|h| attached(h).len() >= attached_after);
+}
+
+#[test]
+fn frames_are_attached_to_their_surface() {
+    let mut harness = Harness::new();
+    let mut presenting = setup_presenting(&mut harness);
+
+    present(&mut harness, &mut presenting, [0x20, 0x40, 0x80], 1);
+    assert_eq!(attached(&harness), [buffer([0x20, 0x40, 0x80])]);
+}
+
+#[test]
+fn frames_wait_for_a_released_buffer() {
+    let mut harness = Harness::new();
+    let mut presenting = setup_presenting(&mut harness);
+    harness.compositor.hold_buffers();
+
+    present(&mut harness, &mut presenting, [0xff, 0, 0], 1);
+    present(&mut harness, &mut presenting, [0, 0xff, 0], 2);
+    // Both buffers are held, the frame waits.
+    present(&mut harness, &mut presenting, [0, 0, 0xff], 0);
+    let mut iterations = 0;
+    harness.run_until(|_| {
+        iterations += 1;
+        iterations > 10
+    });
+    assert_eq!(attached(&harness).len(), 2);
+
+    harness.compositor.release_buffers();
+    harness.run_until(|h| attached(h).len() == 3);
+    assert_eq!(
+        attached(&harness),
+        [
+            buffer([0xff, 0, 0]),
+            buffer([0, 0xff, 0]),
+            buffer([0, 0, 0xff])
+        ]
+    );
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use std::{thread, time::Duration};
+
+use common::{Harness, Request, KEYMAP};
+use iced_wayland_input_method::{
+    application::Event,
+    runtime::command::platform_specific::wayland::virtual_keyboard::ActionInner,
+};
+
+/// The xkb mask of Shift.
+const SHIFT: u32 = 1;
+
+fn upload_keymap(harness: &mut Harness) {
+    harness.send(Event::VirtualKeyboard(
+        ActionInner::Keymap(KEYMAP.to_string()).into(),
+    ));
+    harness
+        .run_until_request(&Request::VirtualKeyboardKeymap(KEYMAP.to_string()));
+    harness.compositor.take_requests();
+}
+
+#[test]
+fn keymap_is_uploaded_once() {
+    let mut harness = Harness::new();
+    upload_keymap(&mut harness);
+
+    harness.send(Event::VirtualKeyboard(
+        ActionInner::Keymap(KEYMAP.to_string()).into(),
+    ));
+    harness.send(Event::VirtualKeyboard(ActionInner::SetGroup(0).into()));
+    harness.run_until(|h| !h.compositor.requests().is_empty());
+    assert!(!harness
+        .compositor
+        .requests()
+        .iter()
+        .any(|r| matches!(r, Request::VirtualKeyboardKeymap(_))));
+}
+
+#[test]
+fn tapped_modifiers_latch_then_lock() {
+    let mut harness = Harness::new();
+    upload_keymap(&mut harness);
+
+    harness.send(Event::VirtualKeyboard(
+        ActionInner::TapModifiers(SHIFT).into(),
+    ));
+    let latched = Request::VirtualModifiers {
+        depressed: 0,
+        latched: SHIFT,
+        locked: 0,
+        group: 0,
+    };
+    harness.run_until_request(&latched);
+
+    harness.send(Event::VirtualKeyboard(
+        ActionInner::TapModifiers(SHIFT).into(),
+    ));
+    harness.run_until_request(&Request::VirtualModifiers {
+        depressed: 0,
+        latched: 0,
+        locked: SHIFT,
+        group: 0,
+    });
+}
+
+#[test]
+fn late_second_tap_releases_the_latch() {
+    let mut harness = Harness::with_settings(|settings| {
+        settings.virtual_keyboard.double_tap_interval = Duration::ZERO;
+    });
+    upload_keymap(&mut harness);
+
+    harness.send(Event::VirtualKeyboard(
+        ActionInner::TapModifiers(SHIFT).into(),
+    ));
+    harness.run_until_request(&Request::VirtualModifiers {
+        depressed: 0,
+        latched: SHIFT,
+        locked: 0,
+        group: 0,
+    });
+    harness.compositor.take_requests();
+
+    thread::sleep(Duration::from_millis(1));
+    harness.send(Event::VirtualKeyboard(
+        ActionInner::TapModifiers(SHIFT).into(),
+    ));
+    let released = Request::VirtualModifiers {
+        depressed: 0,
+        latched: 0,
+        locked: 0,
+        group: 0,
+    };
+    harness.run_until_request(&released);
+    assert_eq!(harness.compositor.take_requests(), vec![released]);
+}
+
+#[test]
+fn group_is_switched() {
+    let mut harness = Harness::new();
+    upload_keymap(&mut harness);
+
+    harness.send(Event::VirtualKeyboard(ActionInner::SetGroup(1).into()));
+    harness.run_until_request(&Request::VirtualModifiers {
+        depressed: 0,
+        latched: 0,
+        locked: 0,
+        group: 1,
+    });
+}